@@ -0,0 +1,187 @@
+use bot::{
+    collector::{bybit_collector::BybitCollector, paper_collector::PaperCollector},
+    core::{avellaneda_stoikov::AvellanedaStoikov, kill_policy::KillPolicy},
+    executor::{
+        paper_executor::PaperExecutor,
+        quote_executor::{QuoteExecutor, QuoteTolerance},
+        risk_executor::{RiskExecutor, RiskLimits},
+    },
+    models::{BotAction, BybitBookSnapshot, Decimal, InstrumentRegistry, Natr, PriceHistory},
+    paper_trade::{paper_exchange::PaperExchange, types::PaperExchangeMessage},
+    state::{OrderBookState, PendingOrdersState, PositionState, PriceState, TradeFlowState},
+};
+use hayate_core::{
+    kill_switch::KillSwitch,
+    mappers::ExecutorMap,
+    registry::StateRegistry,
+    run::{run_bot, RunOptions},
+    supervisor::Supervisor,
+};
+use tokio_util::sync::CancellationToken;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let instruments = InstrumentRegistry::load_bybit("spot")
+        .await
+        .expect("Failed to load instruments from Bybit");
+    let instrument = instruments
+        .get("BTCUSDT")
+        .cloned()
+        .expect("Instrument BTCUSDT not found");
+
+    let market_making_bot = AvellanedaStoikov {
+        interval_ms: 500,
+        instrument,
+        order_amount: Decimal::from(1),
+        risk_aversion: Decimal::from_str_unchecked("0.01"),
+        order_intensity: None,
+        time_horizon: Decimal::from(60),
+        min_spread: Decimal::from_str_unchecked("0.1"),
+        max_inventory: Decimal::from(5),
+        flow_skew: Decimal::from_str_unchecked("0.5"),
+        kill_policy: KillPolicy {
+            flatten: true,
+            ..Default::default()
+        },
+    };
+
+    // Create a channel for sending messages to the PaperExchange
+    let (msg_tx, msg_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    // Shutdown
+    let shutdown = CancellationToken::new();
+    let supervisor = Supervisor::new(shutdown.clone());
+    let kill_switch = KillSwitch::new();
+
+    // Market data and the paper exchange outlive the bot to acknowledge its cancels
+    let bybit_collector = BybitCollector::new(supervisor.stopped_token());
+    let mut paper_exchange = PaperExchange::new()
+        .with_kill_switch(kill_switch.clone())
+        .with_instruments(instruments)
+        .with_trade_flow(TradeFlowState::new().with_window(60_000));
+    let paper_collector = PaperCollector::new(paper_exchange.subscribe());
+    let paper_executor = ExecutorMap::new(
+        Box::new(PaperExecutor::new(msg_tx)),
+        |action: BotAction| match action {
+            BotAction::PlaceOrder(order) => Some(PaperExchangeMessage::PlaceOrder(order)),
+            BotAction::CancelOrder(order) => Some(PaperExchangeMessage::CancelOrder(order)),
+            BotAction::AmendOrder(order) => Some(PaperExchangeMessage::AmendOrder(order)),
+            BotAction::UpdateQuotes(_) => None,
+        },
+    );
+    let mut states = StateRegistry::new();
+    // Spot books are fetched with at most 200 levels per side after lost updates
+    states.register(
+        OrderBookState::new(1024)
+            .with_trade_intensity(500)
+            .with_snapshot_source(Box::new(BybitBookSnapshot {
+                category: "spot".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                limit: 200,
+            })),
+    );
+    states.register(
+        PositionState::new().with_drawdown_limit(Decimal::from(1000), kill_switch.clone()),
+    );
+    states.register(PendingOrdersState::new());
+    states.register(TradeFlowState::new().with_window(10_000));
+
+    // Spot recent trades are capped at 60, too few for 1s bars, and Bybit has no klines
+    // below 1m. The last 1000 linear trades usually span minutes of 1s bars.
+    let mut price_state = PriceState::new().with_history(PriceHistory::BybitTrades {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        limit: 1000,
+    });
+
+    price_state.add_indicator(Box::new(Natr::new(14, 1000)));
+
+    states.register(price_state);
+
+    let risk_executor = RiskExecutor::new(
+        Box::new(paper_executor),
+        market_making_bot.instrument.symbol.clone(),
+        RiskLimits {
+            max_order_size: Some(Decimal::from(100)),
+            max_position: Some(Decimal::from(100)),
+            max_open_orders: Some(10),
+            price_band: Some(Decimal::from_str_unchecked("0.05")),
+            max_orders_per_second: Some(20),
+            ..Default::default()
+        },
+        states.clone(),
+    );
+    let quote_executor = QuoteExecutor::new(
+        Box::new(risk_executor),
+        states.clone(),
+        QuoteTolerance {
+            price: Decimal::from_str_unchecked("0.005"),
+            size: Decimal::ZERO,
+            amend: true,
+        },
+    );
+
+    let mut set = run_bot(
+        market_making_bot,
+        states,
+        vec![Box::new(paper_collector)],
+        vec![Box::new(quote_executor)],
+        kill_switch.clone(),
+        supervisor.clone(),
+        RunOptions::default(),
+    );
+
+    // Manual kill switch: stop quoting and flatten on SIGUSR1
+    #[cfg(unix)]
+    {
+        let kill_switch = kill_switch.clone();
+        let shutdown_signal = shutdown.clone();
+        set.spawn(async move {
+            let mut signal =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+                    .expect("Failed to listen for kill switch signal");
+            tokio::select! {
+                Some(_) = signal.recv() => {
+                    kill_switch.trigger("Manual trigger via SIGUSR1");
+                }
+                // Stop listening so that the bot can exit
+                _ = shutdown_signal.cancelled() => {}
+            }
+        });
+    }
+
+    let shutdown_signal = supervisor.stopped_token();
+    set.spawn(async move {
+        tracing::info!("Starting PaperExchange...");
+        if let Err(e) = paper_exchange
+            .run_with_shutdown(bybit_collector, msg_rx, shutdown_signal)
+            .await
+        {
+            tracing::error!("PaperExchange encountered an error: {}", e);
+        }
+        tracing::info!("PaperExchange stopped.");
+    });
+
+    // Wait for shutdown signal, or for the supervisor to stop the bot
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result.expect("Failed to listen for shutdown signal");
+            tracing::info!("Shutdown signal received, stopping bot...");
+            shutdown.cancel();
+        }
+        _ = supervisor.cancelled() => {}
+    }
+
+    while let Some(result) = set.join_next().await {
+        match result {
+            Ok(_) => {}
+            Err(e) => tracing::error!("Error in bot execution: {}", e),
+        }
+    }
+
+    if let Some(report) = supervisor.shutdown_report() {
+        tracing::info!("Bot stopped: {}", report.reason);
+    }
+}
@@ -1,28 +1,62 @@
-use std::sync::Arc;
-
 use bot::{
     collector::{bybit_collector::BybitCollector, paper_collector::PaperCollector},
-    core::market_making_with_dynamic_spread::DynamicSpreadMM,
-    executor::paper_executor::PaperExecutor,
-    models::{BotAction, Decimal, Natr, Rsi},
+    core::{
+        inventory::InventorySkew,
+        kill_policy::KillPolicy,
+        ladder::{LadderSpacing, QuoteLadder},
+        market_making_with_dynamic_spread::DynamicSpreadMM,
+    },
+    executor::{
+        paper_executor::PaperExecutor,
+        quote_executor::{QuoteExecutor, QuoteTolerance},
+        risk_executor::{RiskExecutor, RiskLimits},
+    },
+    models::{BotAction, BybitBookSnapshot, Decimal, InstrumentRegistry, Natr, PriceHistory, Rsi},
     paper_trade::{paper_exchange::PaperExchange, types::PaperExchangeMessage},
-    state::{BotState, OrderBookState, PendingOrdersState, PriceState},
+    state::{OrderBookState, PendingOrdersState, PositionState, PriceState},
+};
+use hayate_core::{
+    kill_switch::KillSwitch,
+    mappers::ExecutorMap,
+    registry::StateRegistry,
+    run::{run_bot, RunOptions},
+    supervisor::Supervisor,
 };
-use hayate_core::{mappers::ExecutorMap, run::run_bot};
-use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
+    let instruments = InstrumentRegistry::load_bybit("spot")
+        .await
+        .expect("Failed to load instruments from Bybit");
+    let instrument = instruments
+        .get("BTCUSDT")
+        .cloned()
+        .expect("Instrument BTCUSDT not found");
+
     let market_making_bot = DynamicSpreadMM {
         interval_ms: 500,
-        symbol: "BTCUSD".to_string(),
-        order_amount: Decimal::from(1.0),
-        base_spread: Decimal::from(0.01),
-        volatility_target: Decimal::from(0.02),
-        skew_strength: Decimal::from(0.05),
+        instrument,
+        order_amount: Decimal::from(1),
+        base_spread: Decimal::from_str_unchecked("0.01"),
+        volatility_target: Decimal::from_str_unchecked("0.02"),
+        skew_strength: Decimal::from_str_unchecked("0.05"),
+        inventory: InventorySkew {
+            max_inventory: Decimal::from(5),
+            price_skew: Decimal::from_str_unchecked("0.001"),
+            size_skew: Decimal::from_str_unchecked("0.5"),
+        },
+        ladder: QuoteLadder {
+            levels: 3,
+            spacing: LadderSpacing::Ticks(50),
+            size_multiplier: Decimal::from_str_unchecked("1.5"),
+        },
+        kill_policy: KillPolicy {
+            flatten: true,
+            ..Default::default()
+        },
     };
 
     // Create a channel for sending messages to the PaperExchange
@@ -30,39 +64,104 @@ async fn main() {
 
     // Shutdown
     let shutdown = CancellationToken::new();
+    let supervisor = Supervisor::new(shutdown.clone());
+    let kill_switch = KillSwitch::new();
 
-    let bybit_collector = BybitCollector::new(shutdown.clone());
-    let mut paper_exchange = PaperExchange::new();
+    // Market data and the paper exchange outlive the bot to acknowledge its cancels
+    let bybit_collector = BybitCollector::new(supervisor.stopped_token());
+    let mut paper_exchange = PaperExchange::new()
+        .with_kill_switch(kill_switch.clone())
+        .with_instruments(instruments);
     let paper_collector = PaperCollector::new(paper_exchange.subscribe());
     let paper_executor = ExecutorMap::new(
         Box::new(PaperExecutor::new(msg_tx)),
         |action: BotAction| match action {
             BotAction::PlaceOrder(order) => Some(PaperExchangeMessage::PlaceOrder(order)),
             BotAction::CancelOrder(order) => Some(PaperExchangeMessage::CancelOrder(order)),
+            BotAction::AmendOrder(order) => Some(PaperExchangeMessage::AmendOrder(order)),
+            BotAction::UpdateQuotes(_) => None,
         },
     );
-    let orderbook_state = Arc::new(RwLock::new(BotState::OrderBook(OrderBookState::new(1024))));
-    // let position_state = Arc::new(RwLock::new(BotState::Position(PositionState::new())));
-    let pending_orders_state = Arc::new(RwLock::new(BotState::PendingOrders(
-        PendingOrdersState::new(),
-    )));
+    let mut states = StateRegistry::new();
+    // Spot books are fetched with at most 200 levels per side after lost updates
+    states.register(
+        OrderBookState::new(1024).with_snapshot_source(Box::new(BybitBookSnapshot {
+            category: "spot".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            limit: 200,
+        })),
+    );
+    states.register(
+        PositionState::new().with_drawdown_limit(Decimal::from(1000), kill_switch.clone()),
+    );
+    states.register(PendingOrdersState::new());
 
-    let mut price_state = PriceState::new();
+    // Spot recent trades are capped at 60, too few for 1s bars, and Bybit has no klines
+    // below 1m. The last 1000 linear trades usually span minutes of 1s bars.
+    let mut price_state = PriceState::new().with_history(PriceHistory::BybitTrades {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        limit: 1000,
+    });
 
     price_state.add_indicator(Box::new(Rsi::new(14, 1000)));
     price_state.add_indicator(Box::new(Natr::new(14, 1000)));
 
-    let price_state = Arc::new(RwLock::new(BotState::Price(price_state)));
+    states.register(price_state);
+
+    let risk_executor = RiskExecutor::new(
+        Box::new(paper_executor),
+        market_making_bot.instrument.symbol.clone(),
+        RiskLimits {
+            max_order_size: Some(Decimal::from(100)),
+            max_position: Some(Decimal::from(100)),
+            max_open_orders: Some(10),
+            price_band: Some(Decimal::from_str_unchecked("0.05")),
+            max_orders_per_second: Some(20),
+            ..Default::default()
+        },
+        states.clone(),
+    );
+    let quote_executor = QuoteExecutor::new(
+        Box::new(risk_executor),
+        states.clone(),
+        QuoteTolerance {
+            price: Decimal::from_str_unchecked("0.005"),
+            size: Decimal::ZERO,
+            amend: true,
+        },
+    );
 
     let mut set = run_bot(
         market_making_bot,
-        vec![orderbook_state, pending_orders_state, price_state],
+        states,
         vec![Box::new(paper_collector)],
-        vec![Box::new(paper_executor)],
-        shutdown.clone(),
+        vec![Box::new(quote_executor)],
+        kill_switch.clone(),
+        supervisor.clone(),
+        RunOptions::default(),
     );
 
-    let shutdown_signal = shutdown.clone();
+    // Manual kill switch: stop quoting and flatten on SIGUSR1
+    #[cfg(unix)]
+    {
+        let kill_switch = kill_switch.clone();
+        let shutdown_signal = shutdown.clone();
+        set.spawn(async move {
+            let mut signal =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+                    .expect("Failed to listen for kill switch signal");
+            tokio::select! {
+                Some(_) = signal.recv() => {
+                    kill_switch.trigger("Manual trigger via SIGUSR1");
+                }
+                // Stop listening so that the bot can exit
+                _ = shutdown_signal.cancelled() => {}
+            }
+        });
+    }
+
+    let shutdown_signal = supervisor.stopped_token();
     set.spawn(async move {
         tracing::info!("Starting PaperExchange...");
         if let Err(e) = paper_exchange
@@ -74,12 +173,15 @@ async fn main() {
         tracing::info!("PaperExchange stopped.");
     });
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c()
-        .await
-        .expect("Failed to listen for shutdown signal");
-    tracing::info!("Shutdown signal received, stopping bot...");
-    shutdown.cancel();
+    // Wait for shutdown signal, or for the supervisor to stop the bot
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result.expect("Failed to listen for shutdown signal");
+            tracing::info!("Shutdown signal received, stopping bot...");
+            shutdown.cancel();
+        }
+        _ = supervisor.cancelled() => {}
+    }
 
     while let Some(result) = set.join_next().await {
         match result {
@@ -87,4 +189,8 @@ async fn main() {
             Err(e) => tracing::error!("Error in bot execution: {}", e),
         }
     }
+
+    if let Some(report) = supervisor.shutdown_report() {
+        tracing::info!("Bot stopped: {}", report.reason);
+    }
 }
@@ -1,12 +1,15 @@
 use std::str::FromStr;
 
-use clients::{BybitClient, BybitDataType, BybitMessage};
+use clients::{BybitClient, BybitDataType, BybitMessage, BybitTradeUpdate};
 use hayate_core::traits::{Collector, CollectorStream};
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 
-use crate::models::{InternalEvent, OrderBookEventKind, OrderBookUpdate, Side, Trade};
+use crate::models::{
+    book_levels, Candle, CandleInterval, CandleUpdate, InternalEvent, OrderBookEventKind,
+    OrderBookUpdate, Side, Trade,
+};
 
 pub struct BybitCollector {
     shutdown: CancellationToken,
@@ -27,31 +30,8 @@ impl Collector<InternalEvent> for BybitCollector {
         let stream =
             tokio_stream::wrappers::UnboundedReceiverStream::new(rx).filter_map(|msg| match msg {
                 BybitMessage::OrderBookUpdate(update) => {
-                    let bids = update
-                        .data
-                        .bids
-                        .into_iter()
-                        .filter_map(|mut entry| {
-                            // [price, size]
-                            let size = entry.pop()?.try_into().ok()?;
-                            let price = entry.pop()?.try_into().ok()?;
-
-                            Some((price, size))
-                        })
-                        .collect();
-
-                    let asks = update
-                        .data
-                        .asks
-                        .into_iter()
-                        .filter_map(|mut entry| {
-                            // [price, size]
-                            let size = entry.pop()?.try_into().ok()?;
-                            let price = entry.pop()?.try_into().ok()?;
-
-                            Some((price, size))
-                        })
-                        .collect();
+                    let bids = book_levels(update.data.bids);
+                    let asks = book_levels(update.data.asks);
 
                     let kind = match update.data_type {
                         BybitDataType::Snapshot => OrderBookEventKind::Snapshot,
@@ -71,30 +51,35 @@ impl Collector<InternalEvent> for BybitCollector {
                         BybitDataType::Delta => Some(InternalEvent::OrderBookUpdate(data)),
                     }
                 }
-                BybitMessage::TradeUpdate(update) => {
-                    let trades = update
-                        .data
-                        .into_iter()
-                        .filter_map(|trade| {
-                            let price = trade.price.try_into().ok()?;
-                            let size = trade.size.try_into().ok()?;
-                            let timestamp = update.timestamp;
-
-                            Some(Trade {
-                                symbol: update.topic.clone(),
-                                price,
-                                size,
-                                side: Side::from_str(&trade.side).ok()?,
-                                timestamp,
-                            })
-                        })
-                        .collect::<Vec<_>>();
-
-                    if trades.is_empty() {
-                        None
-                    } else {
-                        Some(InternalEvent::TradeUpdate(trades))
-                    }
+                BybitMessage::TradeUpdate(update) => trade_update(update),
+                BybitMessage::KlineUpdate(update) => {
+                    // kline.{interval}.{symbol}
+                    let symbol = update.topic.rsplit('.').next()?.to_string();
+                    // Bybit sends a single kline per message, prefer the closed one if not
+                    let mut klines = update.data;
+                    let kline = match klines.iter().position(|kline| kline.confirm) {
+                        Some(index) => klines.swap_remove(index),
+                        None => klines.pop()?,
+                    };
+                    let interval = CandleInterval::Time(kline.interval_ms()?);
+                    let candle = Candle {
+                        open_time: kline.start,
+                        close_time: kline.timestamp,
+                        open: kline.open.try_into().ok()?,
+                        high: kline.high.try_into().ok()?,
+                        low: kline.low.try_into().ok()?,
+                        close: kline.close.try_into().ok()?,
+                        volume: kline.volume.try_into().ok()?,
+                        // Not provided by Bybit
+                        trade_count: 0,
+                    };
+
+                    Some(InternalEvent::CandleUpdate(CandleUpdate {
+                        symbol,
+                        interval,
+                        candle,
+                        closed: kline.confirm,
+                    }))
                 }
                 _ => None,
             });
@@ -107,3 +92,70 @@ impl BybitCollector {
         Self { shutdown }
     }
 }
+
+/// Trades of a `publicTrade.{symbol}` message, `None` if none could be parsed.
+pub(crate) fn trade_update(update: BybitTradeUpdate) -> Option<InternalEvent> {
+    let trades = update
+        .data
+        .into_iter()
+        .filter_map(|trade| {
+            Some(Trade {
+                symbol: trade.symbol,
+                price: trade.price.try_into().ok()?,
+                size: trade.size.try_into().ok()?,
+                side: Side::from_str(&trade.side).ok()?,
+                timestamp: update.timestamp,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if trades.is_empty() {
+        None
+    } else {
+        Some(InternalEvent::TradeUpdate(trades))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hayate_core::traits::State;
+
+    use super::*;
+    use crate::state::CandleState;
+
+    #[test]
+    fn test_trade_update_symbol() {
+        let update: BybitTradeUpdate = serde_json::from_str(
+            r#"{
+                "topic": "publicTrade.BTCUSDT",
+                "ts": 1672304486868,
+                "type": "snapshot",
+                "data": [{
+                    "T": 1672304486865,
+                    "s": "BTCUSDT",
+                    "S": "Buy",
+                    "v": "0.001",
+                    "p": "16578.50",
+                    "i": "20f43950-d8dd-5b31-9112-a178eb6023af",
+                    "BT": false,
+                    "RPI": false
+                }]
+            }"#,
+        )
+        .unwrap();
+        let event = trade_update(update).unwrap();
+        let InternalEvent::TradeUpdate(trades) = &event else {
+            panic!("Expected trades, got {:?}", event);
+        };
+        assert_eq!(trades[0].symbol, "BTCUSDT");
+
+        // Candles of the symbol are built from the live trades
+        let second = CandleInterval::Time(1000);
+        let mut state = CandleState::new("BTCUSDT", 10).with_interval(second);
+        state.process_event(event).unwrap();
+        assert_eq!(
+            state.get_current_candle(second).unwrap().close,
+            "16578.50".try_into().unwrap()
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use hayate_core::traits::{Collector, CollectorStream};
+use hayate_core::traits::{Collected, CollectedStream, Collector, CollectorStream};
 use tokio::sync::broadcast;
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    StreamExt,
+};
 
 use crate::models::InternalEvent;
 
@@ -11,8 +14,25 @@ pub struct PaperCollector {
 #[async_trait::async_trait]
 impl Collector<InternalEvent> for PaperCollector {
     async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, InternalEvent>> {
+        let stream = self.get_collected_stream().await?.filter_map(|c| match c {
+            Collected::Event(event) => Some(event),
+            Collected::Lagged(_) => None,
+        });
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Events of the paper exchange, reporting those missed by lagging behind it so
+    /// that the states resync.
+    async fn get_collected_stream(&self) -> anyhow::Result<CollectedStream<'_, InternalEvent>> {
         let rx = self.rx.resubscribe();
-        let stream = BroadcastStream::new(rx).filter_map(|r| r.ok());
+        let stream = BroadcastStream::new(rx).map(|r| match r {
+            Ok(event) => Collected::Event(event),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                tracing::error!("Paper collector lagged behind, {} events dropped.", missed);
+                Collected::Lagged(missed)
+            }
+        });
 
         Ok(Box::pin(stream))
     }
@@ -23,3 +43,24 @@ impl PaperCollector {
         Self { rx }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reports_lag() {
+        let (tx, rx) = broadcast::channel(1);
+        let collector = PaperCollector::new(rx);
+        let mut stream = collector.get_collected_stream().await.unwrap();
+
+        for _ in 0..3 {
+            tx.send(InternalEvent::TradeUpdate(Vec::new())).unwrap();
+        }
+        assert!(matches!(stream.next().await, Some(Collected::Lagged(2))));
+        assert!(matches!(
+            stream.next().await,
+            Some(Collected::Event(InternalEvent::TradeUpdate(_)))
+        ));
+    }
+}
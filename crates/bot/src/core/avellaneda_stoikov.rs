@@ -0,0 +1,298 @@
+use hayate_core::{
+    registry::StateContext,
+    traits::{Bot, Input},
+};
+
+use crate::{
+    core::kill_policy::KillPolicy,
+    models::{
+        BotAction, Decimal, Indicator, Instrument, InternalEvent, Natr, Position, Quote, QuoteSet,
+        Side,
+    },
+    state::{OrderBookState, PendingOrdersState, PositionState, PriceState, TradeFlowState},
+};
+
+/// Avellaneda–Stoikov Market Making Bot
+/// This bot quotes around a reservation price shifted against the current inventory,
+/// with the optimal spread from "High-frequency trading in a limit order book" (2008):
+///
+/// - reservation price: `r = s - q * γ * σ² * τ`
+/// - spread: `δ = γ * σ² * τ + (2 / γ) * ln(1 + γ / κ)`
+///
+/// where `s` is the mid price, `q` the signed inventory, `γ` the risk aversion, `σ` the
+/// volatility in price units per NATR candle (derived from NATR), `τ` the time horizon
+/// in NATR candles and `κ` the order-arrival intensity.
+///
+/// With a `TradeFlowState` registered, the reservation price also leans towards the
+/// taker flow of its shortest window by `flow_skew` half spreads per unit of imbalance.
+pub struct AvellanedaStoikov {
+    pub interval_ms: u64,
+    pub instrument: Instrument,
+    pub order_amount: Decimal,
+    pub risk_aversion: Decimal,
+    /// Fixed order-arrival intensity, estimated from trades if `None`
+    pub order_intensity: Option<Decimal>,
+    /// Time horizon in NATR candles
+    pub time_horizon: Decimal,
+    /// Lower bound for the spread between bid and ask
+    pub min_spread: Decimal,
+    /// No orders adding to the position are placed beyond this absolute inventory
+    pub max_inventory: Decimal,
+    /// Shift of the reservation price towards the trade flow, in half spreads per unit
+    /// of trade-flow imbalance
+    pub flow_skew: Decimal,
+    pub kill_policy: KillPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptimalQuotes {
+    pub reservation_price: Decimal,
+    pub spread: Decimal,
+}
+
+impl OptimalQuotes {
+    pub fn bid_price(&self) -> Decimal {
+        self.reservation_price - self.spread / Decimal::from(2)
+    }
+
+    pub fn ask_price(&self) -> Decimal {
+        self.reservation_price + self.spread / Decimal::from(2)
+    }
+
+    /// Shifts the reservation price by `skew` half spreads per unit of `imbalance`,
+    /// upwards when buyers dominate the flow.
+    pub fn skewed(self, imbalance: Decimal, skew: Decimal) -> Self {
+        Self {
+            reservation_price: self.reservation_price
+                + imbalance * skew * self.spread / Decimal::from(2),
+            ..self
+        }
+    }
+}
+
+impl AvellanedaStoikov {
+    pub fn optimal_quotes(
+        &self,
+        mid_price: Decimal,
+        volatility: Decimal,
+        order_intensity: Decimal,
+        inventory: Decimal,
+    ) -> anyhow::Result<OptimalQuotes> {
+        let gamma = self.risk_aversion.to_f64();
+        let sigma = volatility.to_f64();
+        let kappa = order_intensity.to_f64();
+        let tau = self.time_horizon.to_f64();
+
+        let inventory_risk = gamma * sigma * sigma * tau;
+        let reservation_price = mid_price.to_f64() - inventory.to_f64() * inventory_risk;
+        let spread = inventory_risk + (2.0 / gamma) * (1.0 + gamma / kappa).ln();
+
+        Ok(OptimalQuotes {
+            reservation_price: Decimal::try_from(reservation_price)?,
+            spread: Decimal::try_from(spread)?.max(self.min_spread),
+        })
+    }
+}
+
+impl Bot<AvellanedaStoikovInput, BotAction> for AvellanedaStoikov {
+    fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    fn evaluate(&self, input: AvellanedaStoikovInput) -> anyhow::Result<Vec<BotAction>> {
+        let mut actions = Vec::new();
+
+        tracing::debug!(
+            "Evaluating AvellanedaStoikov with mid_price: {:?}, natr: {:?}, trade intensity: {:?}",
+            input.mid_price,
+            input.natr,
+            input.trade_intensity
+        );
+
+        let mid_price = match input.mid_price {
+            Some(price) => price,
+            None => {
+                tracing::info!("Mid price not available, skipping evaluation");
+                return Ok(actions);
+            }
+        };
+
+        let natr = match input.natr {
+            Some(value) => value,
+            None => {
+                tracing::info!("NATR indicator not available, skipping evaluation");
+                return Ok(actions);
+            }
+        };
+
+        let order_intensity = match self.order_intensity.or(input.trade_intensity) {
+            Some(value) if value.is_positive() => value,
+            _ => {
+                tracing::info!("Order arrival intensity not available, skipping evaluation");
+                return Ok(actions);
+            }
+        };
+
+        if !self.risk_aversion.is_positive() {
+            anyhow::bail!("Risk aversion must be positive");
+        }
+
+        // NATR is the average true range as a percentage of the price
+        let volatility = natr * mid_price / Decimal::from(100);
+        let inventory = input.position.signed_size();
+        let mut optimal = self.optimal_quotes(mid_price, volatility, order_intensity, inventory)?;
+        if let Some(imbalance) = input.flow_imbalance {
+            optimal = optimal.skewed(imbalance, self.flow_skew);
+        }
+        let bid_price = self.instrument.round_price(Side::Bid, optimal.bid_price());
+        let ask_price = self.instrument.round_price(Side::Ask, optimal.ask_price());
+        let order_amount = self.instrument.round_size(self.order_amount);
+
+        tracing::info!("AvellanedaStoikov Strategy placing order based on volatility: {}, intensity: {}, inventory: {}, mid price: {}, reservation price: {}, bid price: {}, ask price: {}", volatility, order_intensity, inventory, mid_price, optimal.reservation_price, bid_price, ask_price);
+
+        let mut quotes = QuoteSet::new(self.instrument.symbol.clone());
+        if inventory + order_amount <= self.max_inventory {
+            quotes.push(Side::Bid, Quote::new(bid_price, order_amount));
+        }
+        if inventory - order_amount >= -self.max_inventory {
+            quotes.push(Side::Ask, Quote::new(ask_price, order_amount));
+        }
+        actions.push(BotAction::UpdateQuotes(quotes));
+
+        Ok(actions)
+    }
+
+    fn on_kill(&self, input: AvellanedaStoikovInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(self.kill_policy.actions(
+            &self.instrument,
+            &input.pending_oids,
+            &input.position,
+            input.mid_price,
+        ))
+    }
+
+    fn on_shutdown(&self, input: AvellanedaStoikovInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(KillPolicy::cancel_actions(
+            &self.instrument,
+            &input.pending_oids,
+        ))
+    }
+
+    fn open_orders(&self, input: &AvellanedaStoikovInput) -> Vec<String> {
+        input.open_orders.clone()
+    }
+}
+
+#[derive(Debug)]
+pub struct AvellanedaStoikovInput {
+    mid_price: Option<Decimal>,
+    natr: Option<Decimal>,
+    trade_intensity: Option<Decimal>,
+    /// Imbalance of the shortest trade flow window, if a `TradeFlowState` is registered
+    flow_imbalance: Option<Decimal>,
+    position: Position,
+    pending_oids: Vec<usize>,
+    open_orders: Vec<String>,
+}
+
+impl Input<InternalEvent> for AvellanedaStoikovInput {
+    fn read(ctx: &StateContext<'_, InternalEvent>) -> anyhow::Result<Self> {
+        let natr = ctx.require::<PriceState>()?.find::<Natr>();
+        if natr.is_none() {
+            tracing::debug!("NATR indicator not found in price state");
+        }
+
+        let order_book_state = ctx.require::<OrderBookState>()?;
+        let mid_price = order_book_state.get_mid_price();
+        if mid_price.is_none() {
+            tracing::debug!("Mid price not available in OrderBookState");
+        }
+
+        let pending_orders = ctx.require::<PendingOrdersState>()?;
+        Ok(AvellanedaStoikovInput {
+            mid_price,
+            natr: natr.and_then(|natr| natr.value()),
+            trade_intensity: order_book_state.get_trade_intensity(),
+            flow_imbalance: ctx
+                .get::<TradeFlowState>()
+                .and_then(|trade_flow| trade_flow.get_flows().next())
+                .and_then(|flow| flow.imbalance()),
+            position: *ctx.require::<PositionState>()?.get_inner(),
+            pending_oids: pending_orders.get_inner().get_all_oids(),
+            open_orders: pending_orders.open_orders(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot() -> AvellanedaStoikov {
+        AvellanedaStoikov {
+            interval_ms: 500,
+            instrument: Instrument::new(
+                "BTCUSD",
+                Decimal::from_str_unchecked("0.01"),
+                Decimal::from_str_unchecked("0.001"),
+            ),
+            order_amount: 1.into(),
+            risk_aversion: Decimal::from_str_unchecked("0.1"),
+            order_intensity: None,
+            time_horizon: 1.into(),
+            min_spread: Decimal::ZERO,
+            max_inventory: 10.into(),
+            flow_skew: Decimal::ZERO,
+            kill_policy: KillPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn test_optimal_quotes() {
+        // γσ²τ = 0.1 * 2² * 1 = 0.4, (2 / γ) * ln(1 + γ / κ) = 20 * ln(1.1) = 1.906204
+        let flat = bot()
+            .optimal_quotes(100.into(), 2.into(), 1.into(), Decimal::ZERO)
+            .unwrap();
+        assert_eq!(flat.reservation_price, 100.into());
+        assert_eq!(flat.spread, Decimal::from_str_unchecked("2.306204"));
+
+        // Long inventory shifts the reservation price down by q * γσ²τ
+        let long = bot()
+            .optimal_quotes(100.into(), 2.into(), 1.into(), 5.into())
+            .unwrap();
+        assert_eq!(long.reservation_price, 98.into());
+        assert_eq!(long.spread, flat.spread);
+        assert!(long.ask_price() < flat.ask_price());
+
+        let mut wide = bot();
+        wide.min_spread = 5.into();
+        let quotes = wide
+            .optimal_quotes(100.into(), 2.into(), 1.into(), Decimal::ZERO)
+            .unwrap();
+        assert_eq!(quotes.bid_price(), Decimal::from_str_unchecked("97.5"));
+        assert_eq!(quotes.ask_price(), Decimal::from_str_unchecked("102.5"));
+    }
+
+    #[test]
+    fn test_flow_skew() {
+        let quotes = OptimalQuotes {
+            reservation_price: 100.into(),
+            spread: 2.into(),
+        };
+
+        // Buyers dominate: half of the half spread up
+        let skewed = quotes.skewed(Decimal::from_str_unchecked("0.5"), 1.into());
+        assert_eq!(
+            skewed.reservation_price,
+            Decimal::from_str_unchecked("100.5")
+        );
+        assert_eq!(skewed.spread, quotes.spread);
+
+        let skewed = quotes.skewed(-Decimal::from(1), Decimal::from_str_unchecked("0.5"));
+        assert_eq!(
+            skewed.reservation_price,
+            Decimal::from_str_unchecked("99.5")
+        );
+        assert_eq!(quotes.skewed(1.into(), Decimal::ZERO), quotes);
+    }
+}
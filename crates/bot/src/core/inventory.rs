@@ -0,0 +1,109 @@
+use crate::models::{Decimal, Side};
+
+/// Linear inventory skew for market making strategies.
+///
+/// With inventory `q` normalised by `max_inventory`, the reference price is shifted by
+/// `-price_skew * q` (as a fraction of the mid price), so a long position lowers both
+/// quotes to attract sells. Order sizes are scaled by `size_skew * q`, shrinking the side
+/// that adds to the position and growing the side that reduces it. A side is capped so
+/// that a full fill never takes the absolute inventory above `max_inventory`.
+#[derive(Debug, Clone)]
+pub struct InventorySkew {
+    pub max_inventory: Decimal,
+    pub price_skew: Decimal,
+    pub size_skew: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkewedQuote {
+    pub reference_price: Decimal,
+    /// `None` if the side should not be quoted
+    pub bid_size: Option<Decimal>,
+    pub ask_size: Option<Decimal>,
+}
+
+impl InventorySkew {
+    /// Computes the reference price and order sizes for the given signed inventory
+    /// (positive when long).
+    pub fn apply(&self, mid_price: Decimal, base_size: Decimal, inventory: Decimal) -> SkewedQuote {
+        let ratio = if self.max_inventory.is_positive() {
+            (inventory / self.max_inventory)
+                .max(-Decimal::ONE)
+                .min(Decimal::ONE)
+        } else {
+            Decimal::ZERO
+        };
+
+        let reference_price = mid_price - mid_price * self.price_skew * ratio;
+        let bid_size = base_size * (Decimal::ONE - self.size_skew * ratio);
+        let ask_size = base_size * (Decimal::ONE + self.size_skew * ratio);
+
+        SkewedQuote {
+            reference_price,
+            bid_size: self.cap(bid_size, Side::Bid, inventory),
+            ask_size: self.cap(ask_size, Side::Ask, inventory),
+        }
+    }
+
+    /// Total size that orders on `side` may add before the absolute inventory exceeds
+    /// `max_inventory`.
+    pub fn capacity(&self, side: Side, inventory: Decimal) -> Decimal {
+        let capacity = match side {
+            Side::Bid => self.max_inventory - inventory,
+            Side::Ask => self.max_inventory + inventory,
+        };
+        capacity.max(Decimal::ZERO)
+    }
+
+    fn cap(&self, size: Decimal, side: Side, inventory: Decimal) -> Option<Decimal> {
+        let size = size.min(self.capacity(side, inventory));
+        size.is_positive().then_some(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skew() -> InventorySkew {
+        InventorySkew {
+            max_inventory: 10.into(),
+            price_skew: Decimal::from_str_unchecked("0.01"),
+            size_skew: Decimal::from_str_unchecked("0.5"),
+        }
+    }
+
+    #[test]
+    fn test_flat_inventory() {
+        let quote = skew().apply(100.into(), 2.into(), Decimal::ZERO);
+        assert_eq!(quote.reference_price, 100.into());
+        assert_eq!(quote.bid_size, Some(2.into()));
+        assert_eq!(quote.ask_size, Some(2.into()));
+    }
+
+    #[test]
+    fn test_long_inventory_skews_down() {
+        // Half of max inventory: price shifted by 0.5%, sizes scaled by 25%
+        let quote = skew().apply(100.into(), 2.into(), 5.into());
+        assert_eq!(quote.reference_price, Decimal::from_str_unchecked("99.5"));
+        assert_eq!(quote.bid_size, Some(Decimal::from_str_unchecked("1.5")));
+        assert_eq!(quote.ask_size, Some(Decimal::from_str_unchecked("2.5")));
+
+        // Bid capped to the remaining room
+        let quote = skew().apply(100.into(), 2.into(), Decimal::from_str_unchecked("9.5"));
+        assert_eq!(quote.bid_size, Some(Decimal::from_str_unchecked("0.5")));
+    }
+
+    #[test]
+    fn test_max_inventory_stops_quoting() {
+        let quote = skew().apply(100.into(), 2.into(), 10.into());
+        assert_eq!(quote.reference_price, 99.into());
+        assert_eq!(quote.bid_size, None);
+        assert_eq!(quote.ask_size, Some(3.into()));
+
+        let quote = skew().apply(100.into(), 2.into(), -Decimal::from(12));
+        assert_eq!(quote.reference_price, 101.into());
+        assert_eq!(quote.ask_size, None);
+        assert_eq!(quote.bid_size, Some(3.into()));
+    }
+}
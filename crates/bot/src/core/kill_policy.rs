@@ -0,0 +1,119 @@
+use crate::models::{BotAction, CancelOrder, Decimal, Instrument, PlaceOrder, Position, Side};
+
+/// What a strategy does when the kill switch is triggered: open orders are always
+/// cancelled, and the position is optionally flattened with an aggressive limit order
+/// priced `max_slippage` (as a fraction of mid) through the mid price.
+#[derive(Debug, Clone)]
+pub struct KillPolicy {
+    pub flatten: bool,
+    pub max_slippage: Decimal,
+}
+
+impl Default for KillPolicy {
+    fn default() -> Self {
+        Self {
+            flatten: false,
+            max_slippage: Decimal::from_str_unchecked("0.01"),
+        }
+    }
+}
+
+impl KillPolicy {
+    pub fn actions(
+        &self,
+        instrument: &Instrument,
+        pending_oids: &[usize],
+        position: &Position,
+        mid_price: Option<Decimal>,
+    ) -> Vec<BotAction> {
+        let mut actions = Self::cancel_actions(instrument, pending_oids);
+
+        if !self.flatten || !position.is_open() {
+            return actions;
+        }
+
+        let Some(mid_price) = mid_price else {
+            tracing::error!("Mid price not available, cannot flatten position");
+            return actions;
+        };
+
+        let side = position.side.opposite();
+        let price = match side {
+            Side::Bid => mid_price + mid_price * self.max_slippage,
+            Side::Ask => mid_price - mid_price * self.max_slippage,
+        };
+        let price = instrument.round_price(side, price);
+        tracing::warn!(
+            "Flattening position of {} with {} order at {}",
+            position.size,
+            side,
+            price
+        );
+        actions.push(BotAction::PlaceOrder(PlaceOrder::new(
+            instrument.symbol.clone(),
+            side,
+            price,
+            instrument.round_size(position.size),
+        )));
+
+        actions
+    }
+
+    /// Cancels every pending order, as done on shutdown.
+    pub fn cancel_actions(instrument: &Instrument, pending_oids: &[usize]) -> Vec<BotAction> {
+        pending_oids
+            .iter()
+            .map(|&oid| {
+                BotAction::CancelOrder(CancelOrder {
+                    symbol: instrument.symbol.clone(),
+                    oid,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kill_actions() {
+        let position = Position::new(Side::Bid, 100.into(), 2.into(), 0);
+        let policy = KillPolicy {
+            flatten: true,
+            max_slippage: Decimal::from_str_unchecked("0.01"),
+        };
+
+        let instrument = Instrument::new(
+            "BTCUSD",
+            Decimal::from_str_unchecked("0.5"),
+            Decimal::from_str_unchecked("0.1"),
+        );
+        let actions = policy.actions(
+            &instrument,
+            &[1, 2],
+            &position,
+            Some(Decimal::from_str_unchecked("100.2")),
+        );
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(&actions[0], BotAction::CancelOrder(cancel) if cancel.oid == 1));
+        assert!(matches!(
+            &actions[2],
+            BotAction::PlaceOrder(order)
+                // 100.2 * 0.99 = 99.198, rounded up to the tick size
+                if order.side == Side::Ask
+                    && order.price == Decimal::from_str_unchecked("99.5")
+                    && order.size == 2.into()
+        ));
+
+        let actions = KillPolicy::default().actions(&instrument, &[1], &position, Some(100.into()));
+        assert_eq!(actions.len(), 1);
+
+        // Shutdown only cancels
+        let actions = KillPolicy::cancel_actions(&instrument, &[1, 2]);
+        assert!(actions
+            .iter()
+            .all(|action| matches!(action, BotAction::CancelOrder(_))));
+    }
+}
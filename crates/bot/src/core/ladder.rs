@@ -0,0 +1,168 @@
+use crate::models::{Decimal, Instrument, Quote, Side};
+
+/// Distance between consecutive levels of a quote ladder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LadderSpacing {
+    /// A fixed number of ticks
+    Ticks(u64),
+    /// Basis points of the first level price, rounded up to a whole number of ticks
+    Bps(Decimal),
+}
+
+/// Quote ladder configuration, turning a single quote into `levels` quotes per side.
+///
+/// Level `i` is placed `i` spacings away from the first level, further from the market,
+/// with a size of `size * size_multiplier^i`. Prices are rounded away from the market to
+/// the instrument tick size and sizes down to its lot size, and levels below the
+/// instrument minimum size or notional are dropped.
+#[derive(Debug, Clone)]
+pub struct QuoteLadder {
+    pub levels: usize,
+    pub spacing: LadderSpacing,
+    pub size_multiplier: Decimal,
+}
+
+impl Default for QuoteLadder {
+    /// A single level
+    fn default() -> Self {
+        Self {
+            levels: 1,
+            spacing: LadderSpacing::Ticks(1),
+            size_multiplier: Decimal::ONE,
+        }
+    }
+}
+
+impl QuoteLadder {
+    /// Builds the ladder for one side starting at `price` with a first level of `size`.
+    /// The total size of the ladder never exceeds `max_total_size`.
+    pub fn quotes(
+        &self,
+        instrument: &Instrument,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+        max_total_size: Decimal,
+    ) -> Vec<Quote> {
+        let mut quotes = Vec::with_capacity(self.levels);
+        let first_price = instrument.round_price(side, price);
+        let step = self.step(instrument.tick_size, first_price);
+
+        let mut level_price = first_price;
+        let mut level_size = size;
+        let mut remaining = max_total_size;
+
+        for _ in 0..self.levels {
+            let quote_size = instrument.round_size(level_size.min(remaining));
+            if !level_price.is_positive() || instrument.validate(level_price, quote_size).is_err() {
+                break;
+            }
+
+            quotes.push(Quote::new(level_price, quote_size));
+            remaining -= quote_size;
+            level_size *= self.size_multiplier;
+            level_price = match side {
+                Side::Bid => level_price - step,
+                Side::Ask => level_price + step,
+            };
+        }
+
+        quotes
+    }
+
+    fn step(&self, tick_size: Decimal, price: Decimal) -> Decimal {
+        let step = match self.spacing {
+            LadderSpacing::Ticks(ticks) => Decimal::from(ticks) * tick_size,
+            LadderSpacing::Bps(bps) => {
+                (price * bps / Decimal::from(10_000)).ceil_to_tick(tick_size)
+            }
+        };
+        step.max(tick_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ladder(spacing: LadderSpacing) -> QuoteLadder {
+        QuoteLadder {
+            levels: 3,
+            spacing,
+            size_multiplier: 2.into(),
+        }
+    }
+
+    fn instrument() -> Instrument {
+        Instrument::new(
+            "BTCUSD",
+            Decimal::from_str_unchecked("0.5"),
+            Decimal::from_str_unchecked("0.1"),
+        )
+        .with_min_size(Decimal::from_str_unchecked("0.5"))
+    }
+
+    #[test]
+    fn test_ladder_ticks() {
+        let ladder = ladder(LadderSpacing::Ticks(2));
+
+        let bids = ladder.quotes(
+            &instrument(),
+            Side::Bid,
+            Decimal::from_str_unchecked("100.3"),
+            1.into(),
+            Decimal::from(1000),
+        );
+        assert_eq!(
+            bids,
+            vec![
+                Quote::new(100.into(), 1.into()),
+                Quote::new(99.into(), 2.into()),
+                Quote::new(98.into(), 4.into()),
+            ]
+        );
+
+        let asks = ladder.quotes(
+            &instrument(),
+            Side::Ask,
+            Decimal::from_str_unchecked("100.3"),
+            1.into(),
+            Decimal::from(1000),
+        );
+        assert_eq!(asks[0].price, Decimal::from_str_unchecked("100.5"));
+        assert_eq!(asks[2].price, Decimal::from_str_unchecked("102.5"));
+    }
+
+    #[test]
+    fn test_ladder_bps_and_limits() {
+        // 30 bps of 100 is 0.3, rounded up to one tick
+        let ladder = ladder(LadderSpacing::Bps(30.into()));
+
+        // Capped to a total size of 3.25: the last level of 0.25 is floored to a lot size of 0.2,
+        // below the minimum size
+        let asks = ladder.quotes(
+            &instrument(),
+            Side::Ask,
+            100.into(),
+            1.into(),
+            Decimal::from_str_unchecked("3.25"),
+        );
+        assert_eq!(
+            asks,
+            vec![
+                Quote::new(100.into(), 1.into()),
+                Quote::new(Decimal::from_str_unchecked("100.5"), 2.into()),
+            ]
+        );
+
+        assert!(ladder
+            .quotes(
+                &instrument(),
+                Side::Bid,
+                100.into(),
+                Decimal::from_str_unchecked("0.4"),
+                Decimal::from(1000)
+            )
+            .is_empty());
+    }
+}
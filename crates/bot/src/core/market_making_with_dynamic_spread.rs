@@ -1,8 +1,15 @@
-use hayate_core::traits::{Bot, Input};
+use hayate_core::{
+    registry::StateContext,
+    traits::{Bot, Input},
+};
 
 use crate::{
-    models::{BotAction, CancelOrder, Decimal, Natr, PlaceOrder, Rsi, Side},
-    state::BotState,
+    core::{inventory::InventorySkew, kill_policy::KillPolicy, ladder::QuoteLadder},
+    models::{
+        BotAction, Decimal, Indicator, Instrument, InternalEvent, Natr, Position, QuoteSet, Rsi,
+        Side,
+    },
+    state::{OrderBookState, PendingOrdersState, PositionState, PriceState},
 };
 
 /// Dynamic Spread Market Making Bot
@@ -10,14 +17,17 @@ use crate::{
 /// at a dynamically spread.
 ///
 /// The spread is adjusted for volatility factor using NATR and reference price is
-/// skewed based on trend factor using RSI.
+/// skewed based on trend factor using RSI, and on the current inventory.
 pub struct DynamicSpreadMM {
     pub interval_ms: u64,
-    pub symbol: String,
+    pub instrument: Instrument,
     pub order_amount: Decimal,
     pub base_spread: Decimal,
     pub volatility_target: Decimal,
     pub skew_strength: Decimal,
+    pub inventory: InventorySkew,
+    pub ladder: QuoteLadder,
+    pub kill_policy: KillPolicy,
 }
 
 impl Bot<DynamicSpreadMMInput, BotAction> for DynamicSpreadMM {
@@ -59,42 +69,63 @@ impl Bot<DynamicSpreadMMInput, BotAction> for DynamicSpreadMM {
             }
         };
 
-        for oid in input.pending_oids {
-            actions.push(BotAction::CancelOrder(CancelOrder {
-                symbol: self.symbol.clone(),
-                oid,
-            }));
-        }
-
         let spread: Decimal = self.base_spread * (Decimal::ONE + natr / self.volatility_target);
 
         let skew: Decimal = match rsi {
-            r if r < 30.0.into() => -self.skew_strength,
-            r if r > 70.0.into() => self.skew_strength,
+            r if r < 30.into() => -self.skew_strength,
+            r if r > 70.into() => self.skew_strength,
             _ => Decimal::ZERO,
         };
 
         let adjusted_mid_price = mid_price + (mid_price * skew);
-        let bid_price = adjusted_mid_price - spread;
-        let ask_price = adjusted_mid_price + spread;
+        let inventory = input.position.signed_size();
+        let skewed = self
+            .inventory
+            .apply(adjusted_mid_price, self.order_amount, inventory);
+        let bid_price = skewed.reference_price - spread;
+        let ask_price = skewed.reference_price + spread;
+
+        tracing::info!("DynamicSpreadMM Strategy placing order based on rsi: {}, natr: {}, inventory: {}, mid price: {}, bid price: {}, ask price: {}", rsi, natr, inventory, mid_price, bid_price, ask_price);
+
+        let mut quotes = QuoteSet::new(self.instrument.symbol.clone());
+        for (side, price, size) in [
+            (Side::Bid, bid_price, skewed.bid_size),
+            (Side::Ask, ask_price, skewed.ask_size),
+        ] {
+            let Some(size) = size else {
+                continue;
+            };
+            let capacity = self.inventory.capacity(side, inventory);
+            for quote in self
+                .ladder
+                .quotes(&self.instrument, side, price, size, capacity)
+            {
+                quotes.push(side, quote);
+            }
+        }
+        actions.push(BotAction::UpdateQuotes(quotes));
 
-        tracing::info!("DynamicSpreadMM Strategy placing order based on rsi: {}, natr: {}, mid price: {}, bid price: {}, ask price: {}", rsi, natr, mid_price, bid_price, ask_price);
+        Ok(actions)
+    }
 
-        actions.push(BotAction::PlaceOrder(PlaceOrder {
-            symbol: "BTCUSD".to_string(),
-            price: bid_price,
-            size: self.order_amount,
-            side: Side::Bid,
-        }));
+    fn on_kill(&self, input: DynamicSpreadMMInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(self.kill_policy.actions(
+            &self.instrument,
+            &input.pending_oids,
+            &input.position,
+            input.mid_price,
+        ))
+    }
 
-        actions.push(BotAction::PlaceOrder(PlaceOrder {
-            symbol: "BTCUSD".to_string(),
-            price: ask_price,
-            size: self.order_amount,
-            side: Side::Ask,
-        }));
+    fn on_shutdown(&self, input: DynamicSpreadMMInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(KillPolicy::cancel_actions(
+            &self.instrument,
+            &input.pending_oids,
+        ))
+    }
 
-        Ok(actions)
+    fn open_orders(&self, input: &DynamicSpreadMMInput) -> Vec<String> {
+        input.open_orders.clone()
     }
 }
 
@@ -103,46 +134,36 @@ pub struct DynamicSpreadMMInput {
     mid_price: Option<Decimal>,
     rsi: Option<Decimal>,
     natr: Option<Decimal>,
+    position: Position,
     pending_oids: Vec<usize>,
+    open_orders: Vec<String>,
 }
 
-impl Input<BotState> for DynamicSpreadMMInput {
-    fn empty() -> Self {
-        DynamicSpreadMMInput {
-            mid_price: None,
-            rsi: None,
-            natr: None,
-            pending_oids: Vec::new(),
+impl Input<InternalEvent> for DynamicSpreadMMInput {
+    fn read(ctx: &StateContext<'_, InternalEvent>) -> anyhow::Result<Self> {
+        let price_state = ctx.require::<PriceState>()?;
+        let rsi = price_state.find::<Rsi>();
+        if rsi.is_none() {
+            tracing::debug!("RSI indicator not found in price state");
+        }
+        let natr = price_state.find::<Natr>();
+        if natr.is_none() {
+            tracing::debug!("NATR indicator not found in price state");
         }
-    }
 
-    fn read_state(&mut self, state: &BotState) -> anyhow::Result<()> {
-        match state {
-            BotState::Price(price_state) => {
-                if let Some(rsi) = price_state.get_indicator(Rsi::NAME) {
-                    self.rsi = rsi.value();
-                } else {
-                    tracing::debug!("RSI indicator not found in price state");
-                }
-
-                if let Some(natr) = price_state.get_indicator(Natr::NAME) {
-                    self.natr = natr.value();
-                } else {
-                    tracing::debug!("NATR indicator not found in price state");
-                }
-            }
-            BotState::OrderBook(order_book_state) => {
-                if let Some(mid_price) = order_book_state.get_mid_price() {
-                    self.mid_price = Some(mid_price);
-                } else {
-                    tracing::debug!("Mid price not available in OrderBookState");
-                }
-            }
-            BotState::PendingOrders(pending_orders) => {
-                self.pending_oids = pending_orders.get_inner().get_all_oids();
-            }
-            BotState::Position(_) => {}
+        let mid_price = ctx.require::<OrderBookState>()?.get_mid_price();
+        if mid_price.is_none() {
+            tracing::debug!("Mid price not available in OrderBookState");
         }
-        Ok(())
+
+        let pending_orders = ctx.require::<PendingOrdersState>()?;
+        Ok(DynamicSpreadMMInput {
+            mid_price,
+            rsi: rsi.and_then(|rsi| rsi.value()),
+            natr: natr.and_then(|natr| natr.value()),
+            position: *ctx.require::<PositionState>()?.get_inner(),
+            pending_oids: pending_orders.get_inner().get_all_oids(),
+            open_orders: pending_orders.open_orders(),
+        })
     }
 }
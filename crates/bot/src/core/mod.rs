@@ -1,2 +1,6 @@
+pub mod avellaneda_stoikov;
+pub mod inventory;
+pub mod kill_policy;
+pub mod ladder;
 pub mod market_making_with_dynamic_spread;
 pub mod simple_market_making;
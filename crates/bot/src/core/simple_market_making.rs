@@ -1,19 +1,27 @@
-use hayate_core::traits::{Bot, Input};
+use hayate_core::{
+    registry::StateContext,
+    traits::{Bot, Input},
+};
 
 use crate::{
-    models::{BotAction, CancelOrder, Decimal, PlaceOrder, Side},
-    state::BotState,
+    core::{inventory::InventorySkew, kill_policy::KillPolicy, ladder::QuoteLadder},
+    models::{BotAction, Decimal, Instrument, InternalEvent, Position, QuoteSet, Side},
+    state::{OrderBookState, PendingOrdersState, PositionState},
 };
 
 /// Simple Market Making Bot
 /// This bot places limit orders on both sides of the order book
-/// at a specified spread from the mid price.
+/// at a specified spread from the mid price, skewed by the current inventory,
+/// laddered over one or more price levels per side.
 pub struct SMM {
     pub interval_ms: u64,
-    pub symbol: String,
+    pub instrument: Instrument,
     pub order_amount: Decimal,
     pub bid_spread: Decimal,
     pub ask_spread: Decimal,
+    pub inventory: InventorySkew,
+    pub ladder: QuoteLadder,
+    pub kill_policy: KillPolicy,
 }
 
 impl Bot<SMMInput, BotAction> for SMM {
@@ -24,11 +32,7 @@ impl Bot<SMMInput, BotAction> for SMM {
     fn evaluate(&self, input: SMMInput) -> anyhow::Result<Vec<BotAction>> {
         let mut actions = Vec::new();
 
-        tracing::debug!(
-            "Evaluating SMM with mid_price: {:?}, pending_oids: {:?}",
-            input.mid_price,
-            input.pending_oids
-        );
+        tracing::debug!("Evaluating SMM with mid_price: {:?}", input.mid_price);
 
         let mid_price = match input.mid_price {
             Some(price) => price,
@@ -38,72 +42,86 @@ impl Bot<SMMInput, BotAction> for SMM {
             }
         };
 
-        for oid in input.pending_oids {
-            actions.push(BotAction::CancelOrder(CancelOrder {
-                symbol: self.symbol.clone(),
-                oid,
-            }));
-        }
-
-        let bid_price = mid_price - self.bid_spread;
-        let ask_price = mid_price + self.ask_spread;
+        let inventory = input.position.signed_size();
+        let skewed = self
+            .inventory
+            .apply(mid_price, self.order_amount, inventory);
+        let bid_price = skewed.reference_price - self.bid_spread;
+        let ask_price = skewed.reference_price + self.ask_spread;
 
         tracing::info!(
-            "SMM Strategy placing order based on mid price: {}, bid price: {}, ask price: {}",
+            "SMM Strategy placing order based on mid price: {}, inventory: {}, bid price: {}, ask price: {}",
             mid_price,
+            inventory,
             bid_price,
             ask_price
         );
 
-        actions.push(BotAction::PlaceOrder(PlaceOrder {
-            symbol: self.symbol.clone(),
-            price: bid_price,
-            size: self.order_amount,
-            side: Side::Bid,
-        }));
-
-        actions.push(BotAction::PlaceOrder(PlaceOrder {
-            symbol: self.symbol.clone(),
-            price: ask_price,
-            size: self.order_amount,
-            side: Side::Ask,
-        }));
+        let mut quotes = QuoteSet::new(self.instrument.symbol.clone());
+        for (side, price, size) in [
+            (Side::Bid, bid_price, skewed.bid_size),
+            (Side::Ask, ask_price, skewed.ask_size),
+        ] {
+            let Some(size) = size else {
+                continue;
+            };
+            let capacity = self.inventory.capacity(side, inventory);
+            for quote in self
+                .ladder
+                .quotes(&self.instrument, side, price, size, capacity)
+            {
+                quotes.push(side, quote);
+            }
+        }
+        actions.push(BotAction::UpdateQuotes(quotes));
 
         Ok(actions)
     }
+
+    fn on_kill(&self, input: SMMInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(self.kill_policy.actions(
+            &self.instrument,
+            &input.pending_oids,
+            &input.position,
+            input.mid_price,
+        ))
+    }
+
+    fn on_shutdown(&self, input: SMMInput) -> anyhow::Result<Vec<BotAction>> {
+        Ok(KillPolicy::cancel_actions(
+            &self.instrument,
+            &input.pending_oids,
+        ))
+    }
+
+    fn open_orders(&self, input: &SMMInput) -> Vec<String> {
+        input.open_orders.clone()
+    }
 }
 
 pub struct SMMInput {
     mid_price: Option<Decimal>,
+    position: Position,
     pending_oids: Vec<usize>,
+    open_orders: Vec<String>,
 }
 
-impl Input<BotState> for SMMInput {
-    fn empty() -> Self {
-        SMMInput {
-            mid_price: None,
-            pending_oids: Vec::new(),
+impl Input<InternalEvent> for SMMInput {
+    fn read(ctx: &StateContext<'_, InternalEvent>) -> anyhow::Result<Self> {
+        let mid_price = ctx.require::<OrderBookState>()?.get_mid_price();
+        if mid_price.is_none() {
+            tracing::debug!("Mid price not available in OrderBookState");
         }
-    }
 
-    fn read_state(&mut self, state: &BotState) -> anyhow::Result<()> {
-        match state {
-            BotState::OrderBook(order_book_state) => {
-                if let Some(mid_price) = order_book_state.get_mid_price() {
-                    self.mid_price = Some(mid_price);
-                } else {
-                    tracing::debug!("Mid price not available in OrderBookState");
-                }
-            }
-            BotState::Position(position) => {
-                // TODO: budget check
-                tracing::debug!("Reading position state: {:?}", position.get_inner());
-            }
-            BotState::PendingOrders(pending_orders) => {
-                self.pending_oids = pending_orders.get_inner().get_all_oids();
-            }
-            BotState::Price(_) => {}
-        }
-        Ok(())
+        let position = *ctx.require::<PositionState>()?.get_inner();
+        tracing::debug!("Reading position state: {:?}", position);
+
+        let pending_orders = ctx.require::<PendingOrdersState>()?;
+        Ok(SMMInput {
+            mid_price,
+            position,
+            pending_oids: pending_orders.get_inner().get_all_oids(),
+            open_orders: pending_orders.open_orders(),
+        })
     }
 }
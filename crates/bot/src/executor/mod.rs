@@ -1 +1,3 @@
 pub mod paper_executor;
+pub mod quote_executor;
+pub mod risk_executor;
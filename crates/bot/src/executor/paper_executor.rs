@@ -1,7 +1,7 @@
-use hayate_core::traits::Executor;
+use hayate_core::{events::EventSink, traits::Executor};
 use tokio::sync::mpsc;
 
-use crate::paper_trade::types::PaperExchangeMessage;
+use crate::{models::InternalEvent, paper_trade::types::PaperExchangeMessage};
 
 // TODO: add delay to simulate network latency
 pub struct PaperExecutor {
@@ -9,8 +9,13 @@ pub struct PaperExecutor {
 }
 
 #[async_trait::async_trait]
-impl Executor<PaperExchangeMessage> for PaperExecutor {
-    async fn execute(&self, action: PaperExchangeMessage) -> anyhow::Result<()> {
+impl Executor<PaperExchangeMessage, InternalEvent> for PaperExecutor {
+    /// The paper exchange publishes the outcome of every request on its own stream.
+    async fn execute(
+        &self,
+        action: PaperExchangeMessage,
+        _events: &EventSink<InternalEvent>,
+    ) -> anyhow::Result<()> {
         if let Err(e) = self.action_sender.send(action) {
             tracing::info!("Paper exchange channel closed, stopping executor: {}", e);
         }
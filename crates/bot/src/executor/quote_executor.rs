@@ -0,0 +1,486 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use hayate_core::{events::EventSink, registry::StateRegistry, traits::Executor};
+
+use crate::{
+    models::{
+        AmendOrder, BotAction, CancelOrder, Decimal, InternalEvent, Order, OrderStatus, PlaceOrder,
+        Quote, QuoteSet, Side,
+    },
+    state::PendingOrdersState,
+};
+
+/// How far an open order may drift from a desired quote before it is replaced.
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteTolerance {
+    pub price: Decimal,
+    pub size: Decimal,
+    /// Whether the venue supports amending orders in place, otherwise orders are
+    /// cancelled and placed again.
+    pub amend: bool,
+}
+
+impl Default for QuoteTolerance {
+    fn default() -> Self {
+        Self {
+            price: Decimal::ZERO,
+            size: Decimal::ZERO,
+            amend: true,
+        }
+    }
+}
+
+impl QuoteTolerance {
+    fn matches(&self, price: Decimal, size: Decimal, quote: &Quote) -> bool {
+        (price - quote.price).abs() <= self.price && (size - quote.size).abs() <= self.size
+    }
+}
+
+/// A change to an open order sent but not yet acknowledged.
+#[derive(Debug, Clone, Copy)]
+enum OrderChange {
+    Cancel,
+    Amend { price: Decimal, size: Decimal },
+}
+
+/// Requests sent but not yet acknowledged, with the time they were sent in ms.
+#[derive(Debug, Default)]
+struct InFlight {
+    /// Places keyed by client order id
+    places: HashMap<String, (PlaceOrder, u64)>,
+    /// Cancels and amends keyed by the oid of their order
+    changes: HashMap<usize, (OrderChange, u64)>,
+}
+
+impl InFlight {
+    /// Forgets the requests acknowledged, rejected or sent longer than `timeout` ago.
+    fn settle(&mut self, pending_orders: &PendingOrdersState, now: u64, timeout: u64) {
+        let rejections = pending_orders.get_rejections();
+
+        self.places.retain(|cloid, (_, sent_at)| {
+            let rejected = rejections
+                .iter()
+                .any(|rejection| rejection.cloid.as_deref() == Some(cloid.as_str()));
+            pending_orders.get_order_by_cloid(cloid).is_none()
+                && !rejected
+                && now.saturating_sub(*sent_at) < timeout
+        });
+
+        self.changes.retain(|oid, (change, sent_at)| {
+            let rejected = rejections
+                .iter()
+                .any(|rejection| rejection.oid == Some(*oid) && rejection.timestamp >= *sent_at);
+            let acknowledged = match pending_orders.get_inner().get_order(*oid) {
+                None => true,
+                Some(order) => match change {
+                    OrderChange::Cancel => order.status == OrderStatus::PendingCancel,
+                    OrderChange::Amend { price, size } => {
+                        order.price == *price && order.size == *size
+                    }
+                },
+            };
+            !rejected && !acknowledged && now.saturating_sub(*sent_at) < timeout
+        });
+    }
+
+    fn record(&mut self, action: &BotAction, now: u64) {
+        match action {
+            BotAction::PlaceOrder(order) => {
+                self.places
+                    .insert(order.cloid.clone(), (order.clone(), now));
+            }
+            BotAction::CancelOrder(cancel) => {
+                self.changes.insert(cancel.oid, (OrderChange::Cancel, now));
+            }
+            BotAction::AmendOrder(amend) => {
+                let change = OrderChange::Amend {
+                    price: amend.price,
+                    size: amend.size,
+                };
+                self.changes.insert(amend.oid, (change, now));
+            }
+            BotAction::UpdateQuotes(_) => {}
+        }
+    }
+
+    /// The order as it will be once its change in flight is acknowledged, `None` if it
+    /// is being cancelled.
+    fn expected(&self, order: &Order) -> Option<Order> {
+        let mut order = order.clone();
+        match self.changes.get(&order.oid) {
+            Some((OrderChange::Cancel, _)) => return None,
+            Some((OrderChange::Amend { price, size }, _)) => {
+                order.price = *price;
+                order.size = *size;
+            }
+            None => {}
+        }
+        Some(order)
+    }
+}
+
+/// QuoteExecutor reconciles `BotAction::UpdateQuotes` against the open orders in
+/// `PendingOrdersState`, read through the state registry, forwarding only the cancels, amends and places needed to
+/// reach the desired quotes. All other actions are forwarded unchanged. Requests not
+/// yet acknowledged are taken as done, so they are not sent again on the next update.
+pub struct QuoteExecutor {
+    executor: Box<dyn Executor<BotAction, InternalEvent>>,
+    states: StateRegistry<InternalEvent>,
+    tolerance: QuoteTolerance,
+    in_flight: Mutex<InFlight>,
+}
+
+impl QuoteExecutor {
+    /// How long an unacknowledged request is assumed to still be on its way.
+    pub const IN_FLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new(
+        executor: Box<dyn Executor<BotAction, InternalEvent>>,
+        states: StateRegistry<InternalEvent>,
+        tolerance: QuoteTolerance,
+    ) -> Self {
+        Self {
+            executor,
+            states,
+            tolerance,
+            in_flight: Mutex::new(InFlight::default()),
+        }
+    }
+
+    async fn update_quotes(
+        &self,
+        mut quotes: QuoteSet,
+        events: &EventSink<InternalEvent>,
+    ) -> anyhow::Result<()> {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let open_orders = {
+            let ctx = self.states.read().await;
+            let pending_orders = ctx.require::<PendingOrdersState>()?;
+
+            let mut in_flight = self.in_flight.lock().unwrap();
+            in_flight.settle(
+                pending_orders,
+                now,
+                Self::IN_FLIGHT_TIMEOUT.as_millis() as u64,
+            );
+
+            // Quotes already on their way to the venue need no new order
+            for (order, _) in in_flight.places.values() {
+                if order.symbol != quotes.symbol {
+                    continue;
+                }
+                let side_quotes = match order.side {
+                    Side::Bid => &mut quotes.bids,
+                    Side::Ask => &mut quotes.asks,
+                };
+                if let Some(i) = side_quotes
+                    .iter()
+                    .position(|quote| self.tolerance.matches(order.price, order.size, quote))
+                {
+                    side_quotes.remove(i);
+                }
+            }
+
+            pending_orders
+                .get_inner()
+                .iter()
+                .filter(|order| order.symbol == quotes.symbol)
+                .filter_map(|order| in_flight.expected(order))
+                .collect::<Vec<_>>()
+        };
+
+        for action in diff_quotes(&quotes, &open_orders, &self.tolerance) {
+            self.in_flight.lock().unwrap().record(&action, now);
+            self.executor.execute(action, events).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor<BotAction, InternalEvent> for QuoteExecutor {
+    async fn execute(
+        &self,
+        action: BotAction,
+        events: &EventSink<InternalEvent>,
+    ) -> anyhow::Result<()> {
+        match action {
+            BotAction::UpdateQuotes(quotes) => self.update_quotes(quotes, events).await,
+            action => self.executor.execute(action, events).await,
+        }
+    }
+}
+
+/// Computes the minimal set of actions turning `open_orders` into the desired `quotes`.
+/// Orders already within tolerance of a quote are left alone, remaining orders are
+/// amended (or cancelled and replaced) towards the remaining quotes, and any surplus
+/// is cancelled or placed. Cancels are emitted first, then amends, then places.
+pub fn diff_quotes(
+    quotes: &QuoteSet,
+    open_orders: &[Order],
+    tolerance: &QuoteTolerance,
+) -> Vec<BotAction> {
+    let mut cancels = Vec::new();
+    let mut amends = Vec::new();
+    let mut places = Vec::new();
+
+    for side in [Side::Bid, Side::Ask] {
+        let mut desired = quotes.quotes(side).to_vec();
+        let mut existing = open_orders
+            .iter()
+            .filter(|order| order.side == side && order.status != OrderStatus::PendingCancel)
+            .collect::<Vec<_>>();
+
+        // Best prices first, so that leftover orders are paired by level
+        match side {
+            Side::Bid => {
+                desired.sort_by_key(|quote| std::cmp::Reverse(quote.price));
+                existing.sort_by_key(|order| std::cmp::Reverse(order.price));
+            }
+            Side::Ask => {
+                desired.sort_by_key(|quote| quote.price);
+                existing.sort_by_key(|order| order.price);
+            }
+        }
+
+        desired.retain(|quote| {
+            match existing
+                .iter()
+                .position(|order| tolerance.matches(order.price, order.size, quote))
+            {
+                Some(i) => {
+                    existing.remove(i);
+                    false
+                }
+                None => true,
+            }
+        });
+
+        let mut desired = desired.into_iter();
+        let mut existing = existing.into_iter();
+        loop {
+            match (existing.next(), desired.next()) {
+                (Some(order), Some(quote)) if tolerance.amend => {
+                    amends.push(BotAction::AmendOrder(AmendOrder {
+                        symbol: quotes.symbol.clone(),
+                        oid: order.oid,
+                        price: quote.price,
+                        size: quote.size,
+                    }));
+                }
+                (Some(order), Some(quote)) => {
+                    cancels.push(cancel(order));
+                    places.push(place(&quotes.symbol, side, quote));
+                }
+                (Some(order), None) => cancels.push(cancel(order)),
+                (None, Some(quote)) => places.push(place(&quotes.symbol, side, quote)),
+                (None, None) => break,
+            }
+        }
+    }
+
+    cancels.into_iter().chain(amends).chain(places).collect()
+}
+
+fn cancel(order: &Order) -> BotAction {
+    BotAction::CancelOrder(CancelOrder {
+        symbol: order.symbol.clone(),
+        oid: order.oid,
+    })
+}
+
+fn place(symbol: &str, side: Side, quote: Quote) -> BotAction {
+    BotAction::PlaceOrder(PlaceOrder::new(symbol, side, quote.price, quote.size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::models::OrderRejection;
+
+    fn order(oid: usize, side: Side, price: u64, size: u64) -> Order {
+        Order::new(oid, "BTCUSD".to_string(), side, price.into(), size.into())
+    }
+
+    fn quotes(bids: &[(u64, u64)], asks: &[(u64, u64)]) -> QuoteSet {
+        let mut quotes = QuoteSet::new("BTCUSD");
+        for (price, size) in bids {
+            quotes.push(Side::Bid, Quote::new((*price).into(), (*size).into()));
+        }
+        for (price, size) in asks {
+            quotes.push(Side::Ask, Quote::new((*price).into(), (*size).into()));
+        }
+        quotes
+    }
+
+    #[test]
+    fn test_diff_unchanged_quotes() {
+        let open = vec![order(1, Side::Bid, 99, 1), order(2, Side::Ask, 101, 1)];
+        let actions = diff_quotes(
+            &quotes(&[(99, 1)], &[(101, 1)]),
+            &open,
+            &QuoteTolerance::default(),
+        );
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_diff_within_tolerance() {
+        let open = vec![order(1, Side::Bid, 99, 1)];
+        let tolerance = QuoteTolerance {
+            price: 1.into(),
+            size: Decimal::ZERO,
+            amend: true,
+        };
+        assert!(diff_quotes(&quotes(&[(100, 1)], &[]), &open, &tolerance).is_empty());
+
+        let actions = diff_quotes(&quotes(&[(101, 1)], &[]), &open, &tolerance);
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(
+            &actions[0],
+            BotAction::AmendOrder(amend) if amend.oid == 1 && amend.price == 101.into()
+        ));
+    }
+
+    #[test]
+    fn test_diff_cancel_replace_without_amend() {
+        let open = vec![order(1, Side::Bid, 99, 1), order(2, Side::Bid, 98, 1)];
+        let tolerance = QuoteTolerance {
+            amend: false,
+            ..Default::default()
+        };
+
+        let actions = diff_quotes(&quotes(&[(99, 1), (97, 1)], &[(101, 2)]), &open, &tolerance);
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(&actions[0], BotAction::CancelOrder(cancel) if cancel.oid == 2));
+        assert!(matches!(
+            &actions[1],
+            BotAction::PlaceOrder(place) if place.side == Side::Bid && place.price == 97.into()
+        ));
+        assert!(matches!(
+            &actions[2],
+            BotAction::PlaceOrder(place) if place.side == Side::Ask && place.size == 2.into()
+        ));
+    }
+
+    #[test]
+    fn test_diff_cancels_surplus_and_pending_cancel() {
+        let mut cancelling = order(3, Side::Ask, 101, 1);
+        cancelling.status = OrderStatus::PendingCancel;
+        let open = vec![
+            order(1, Side::Bid, 99, 1),
+            order(2, Side::Bid, 98, 1),
+            cancelling,
+        ];
+
+        let actions = diff_quotes(
+            &quotes(&[(99, 1)], &[(101, 1)]),
+            &open,
+            &QuoteTolerance::default(),
+        );
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], BotAction::CancelOrder(cancel) if cancel.oid == 2));
+        assert!(matches!(
+            &actions[1],
+            BotAction::PlaceOrder(place) if place.side == Side::Ask
+        ));
+    }
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<BotAction>>>);
+
+    impl Recorder {
+        fn take(&self) -> Vec<BotAction> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Executor<BotAction, InternalEvent> for Recorder {
+        async fn execute(
+            &self,
+            action: BotAction,
+            _events: &EventSink<InternalEvent>,
+        ) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(action);
+            Ok(())
+        }
+    }
+
+    fn quote_executor(open: Vec<Order>) -> (QuoteExecutor, Recorder, StateRegistry<InternalEvent>) {
+        let mut pending_orders = PendingOrdersState::new();
+        for order in open {
+            pending_orders.add_order(order, OrderStatus::New);
+        }
+        let mut states = StateRegistry::new();
+        states.register(pending_orders);
+        let recorder = Recorder::default();
+        let executor = QuoteExecutor::new(
+            Box::new(recorder.clone()),
+            states.clone(),
+            QuoteTolerance::default(),
+        );
+        (executor, recorder, states)
+    }
+
+    async fn update(executor: &QuoteExecutor, quotes: QuoteSet) {
+        executor
+            .execute(BotAction::UpdateQuotes(quotes), &EventSink::discard())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_changes_in_flight_are_not_resent() {
+        let (executor, recorder, _) = quote_executor(vec![
+            order(1, Side::Bid, 99, 1),
+            order(2, Side::Ask, 101, 1),
+        ]);
+
+        update(&executor, quotes(&[(98, 1)], &[])).await;
+        let actions = recorder.take();
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(
+            actions[0],
+            BotAction::CancelOrder(CancelOrder { oid: 2, .. })
+        ));
+        assert!(matches!(
+            actions[1],
+            BotAction::AmendOrder(AmendOrder { oid: 1, .. })
+        ));
+
+        update(&executor, quotes(&[(98, 1)], &[])).await;
+        assert!(recorder.take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_place_is_sent_again() {
+        let (executor, recorder, states) = quote_executor(vec![]);
+
+        update(&executor, quotes(&[(99, 1)], &[])).await;
+        let actions = recorder.take();
+        let BotAction::PlaceOrder(place) = &actions[0] else {
+            panic!("expected a place, got {:?}", actions);
+        };
+
+        update(&executor, quotes(&[(99, 1)], &[])).await;
+        assert!(recorder.take().is_empty());
+
+        states
+            .apply(InternalEvent::OrderRejected(OrderRejection {
+                cloid: Some(place.cloid.clone()),
+                oid: None,
+                symbol: place.symbol.clone(),
+                reason: "max order size exceeded".to_string(),
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            }))
+            .await;
+
+        update(&executor, quotes(&[(99, 1)], &[])).await;
+        let actions = recorder.take();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(actions[0], BotAction::PlaceOrder(_)));
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hayate_core::{events::EventSink, registry::StateRegistry, traits::Executor};
+
+use crate::{
+    models::{AmendOrder, BotAction, Decimal, InternalEvent, OrderRejection, PlaceOrder, Side},
+    state::{OrderBookState, PendingOrdersState, PositionState},
+};
+
+/// Pre-trade limits enforced by `RiskExecutor`. Limits left as `None` are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    pub max_order_size: Option<Decimal>,
+    pub max_order_notional: Option<Decimal>,
+    /// Maximum absolute position, counting all open orders on the same side as filled
+    pub max_position: Option<Decimal>,
+    pub max_open_orders: Option<usize>,
+    /// Maximum distance of the order price from the mid price, as a fraction of mid
+    pub price_band: Option<Decimal>,
+    pub max_orders_per_second: Option<usize>,
+}
+
+/// An order request carried by an action, as seen by the risk checks.
+#[derive(Debug, Clone, Copy)]
+pub enum OrderRequest<'a> {
+    Place(&'a PlaceOrder),
+    Amend(&'a AmendOrder),
+}
+
+impl OrderRequest<'_> {
+    pub fn symbol(&self) -> &str {
+        match self {
+            OrderRequest::Place(order) => &order.symbol,
+            OrderRequest::Amend(order) => &order.symbol,
+        }
+    }
+}
+
+/// Actions that can be inspected by `RiskExecutor`.
+pub trait RiskAction {
+    fn order_request(&self) -> Option<OrderRequest<'_>>;
+}
+
+impl RiskAction for BotAction {
+    fn order_request(&self) -> Option<OrderRequest<'_>> {
+        match self {
+            BotAction::PlaceOrder(order) => Some(OrderRequest::Place(order)),
+            BotAction::AmendOrder(order) => Some(OrderRequest::Amend(order)),
+            BotAction::CancelOrder(_) | BotAction::UpdateQuotes(_) => None,
+        }
+    }
+}
+
+/// The bot's exposure in the symbol of an order request at the time it is checked.
+#[derive(Debug, Clone, Default)]
+pub struct RiskSnapshot {
+    pub mid_price: Option<Decimal>,
+    /// Signed position, positive when long
+    pub position: Decimal,
+    pub open_orders: usize,
+    pub open_bid_size: Decimal,
+    pub open_ask_size: Decimal,
+    /// Side and remaining size of the order being amended, if any
+    pub amended_order: Option<(Side, Decimal)>,
+}
+
+impl RiskLimits {
+    /// Checks an order request against the limits, returning the rejection reason
+    /// if any limit is breached. Rate limits are checked separately by the executor.
+    pub fn check(&self, request: OrderRequest<'_>, snapshot: &RiskSnapshot) -> Result<(), String> {
+        let (side, price, size) = match request {
+            OrderRequest::Place(order) => (order.side, order.price, order.size),
+            OrderRequest::Amend(order) => {
+                let (side, _) = snapshot
+                    .amended_order
+                    .ok_or_else(|| format!("Order with OID {} not found", order.oid))?;
+                (side, order.price, order.size)
+            }
+        };
+
+        if let Some(max_size) = self.max_order_size {
+            if size > max_size {
+                return Err(format!("Order size {} exceeds limit {}", size, max_size));
+            }
+        }
+
+        if let Some(max_notional) = self.max_order_notional {
+            let notional = price * size;
+            if notional > max_notional {
+                return Err(format!(
+                    "Order notional {} exceeds limit {}",
+                    notional, max_notional
+                ));
+            }
+        }
+
+        if let Some(max_open_orders) = self.max_open_orders {
+            if matches!(request, OrderRequest::Place(_)) && snapshot.open_orders >= max_open_orders
+            {
+                return Err(format!(
+                    "Open orders {} at limit {}",
+                    snapshot.open_orders, max_open_orders
+                ));
+            }
+        }
+
+        if let Some(max_position) = self.max_position {
+            // Replace the amended order's size rather than adding to it
+            let replaced = match snapshot.amended_order {
+                Some((_, remaining)) if matches!(request, OrderRequest::Amend(_)) => remaining,
+                _ => Decimal::ZERO,
+            };
+            let projected = match side {
+                Side::Bid => snapshot.position + snapshot.open_bid_size - replaced + size,
+                Side::Ask => -snapshot.position + snapshot.open_ask_size - replaced + size,
+            };
+            if projected > max_position {
+                return Err(format!(
+                    "Projected {} position {} exceeds limit {}",
+                    side, projected, max_position
+                ));
+            }
+        }
+
+        if let Some(price_band) = self.price_band {
+            let mid_price = snapshot
+                .mid_price
+                .ok_or("Mid price not available for price band check")?;
+            if (price - mid_price).abs() > mid_price * price_band {
+                return Err(format!(
+                    "Order price {} outside of band {} around mid {}",
+                    price, price_band, mid_price
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// RiskExecutor wraps another executor and checks every order request against
+/// `RiskLimits` before forwarding it. Rejected requests are not forwarded, and are
+/// reported to the states as `InternalEvent::OrderRejected`.
+///
+/// The book and position states hold a single symbol, so the limits apply to the orders
+/// of `symbol`. Orders for other symbols, or placed without a position or pending orders
+/// state to check them against, are rejected.
+pub struct RiskExecutor<A> {
+    executor: Box<dyn Executor<A, InternalEvent>>,
+    symbol: String,
+    limits: RiskLimits,
+    states: StateRegistry<InternalEvent>,
+    sent_at: Mutex<VecDeque<Instant>>,
+}
+
+impl<A> RiskExecutor<A> {
+    pub fn new(
+        executor: Box<dyn Executor<A, InternalEvent>>,
+        symbol: impl Into<String>,
+        limits: RiskLimits,
+        states: StateRegistry<InternalEvent>,
+    ) -> Self {
+        Self {
+            executor,
+            symbol: symbol.into(),
+            limits,
+            states,
+            sent_at: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    async fn snapshot(&self, request: OrderRequest<'_>) -> anyhow::Result<RiskSnapshot> {
+        let symbol = request.symbol();
+        if symbol != self.symbol {
+            anyhow::bail!("No risk limits for symbol {}", symbol);
+        }
+
+        let ctx = self.states.read().await;
+        let position = ctx.require::<PositionState>()?.get_inner().signed_size();
+        let orders = ctx.require::<PendingOrdersState>()?.get_inner();
+        let open_size = |side: Side| {
+            orders
+                .iter()
+                .filter(|order| order.symbol == symbol && order.side == side)
+                .map(|order| order.size)
+                .sum()
+        };
+
+        Ok(RiskSnapshot {
+            mid_price: ctx
+                .get::<OrderBookState>()
+                .and_then(|orderbook| orderbook.get_mid_price()),
+            position,
+            open_orders: orders.iter().filter(|order| order.symbol == symbol).count(),
+            open_bid_size: open_size(Side::Bid),
+            open_ask_size: open_size(Side::Ask),
+            amended_order: match request {
+                OrderRequest::Amend(amend) => orders
+                    .get_order(amend.oid)
+                    .map(|order| (order.side, order.size)),
+                OrderRequest::Place(_) => None,
+            },
+        })
+    }
+
+    /// Records an order request against the rate limit, returning `false` if the
+    /// limit has been reached.
+    fn check_rate(&self) -> bool {
+        let Some(max_orders) = self.limits.max_orders_per_second else {
+            return true;
+        };
+
+        let now = Instant::now();
+        let mut sent_at = self.sent_at.lock().unwrap();
+        while sent_at
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= Duration::from_secs(1))
+        {
+            sent_at.pop_front();
+        }
+
+        if sent_at.len() >= max_orders {
+            return false;
+        }
+        sent_at.push_back(now);
+        true
+    }
+
+    async fn reject(
+        &self,
+        request: OrderRequest<'_>,
+        reason: String,
+        events: &EventSink<InternalEvent>,
+    ) {
+        let rejection = match request {
+            OrderRequest::Place(order) => OrderRejection {
+                cloid: Some(order.cloid.clone()),
+                oid: None,
+                symbol: order.symbol.clone(),
+                reason,
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            },
+            OrderRequest::Amend(order) => OrderRejection {
+                cloid: None,
+                oid: Some(order.oid),
+                symbol: order.symbol.clone(),
+                reason,
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            },
+        };
+
+        tracing::warn!("Risk check rejected order: {:?}", rejection);
+        if !events.send(InternalEvent::OrderRejected(rejection)).await {
+            tracing::debug!("States stopped, risk rejection not reported");
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<A> Executor<A, InternalEvent> for RiskExecutor<A>
+where
+    A: RiskAction + Send + Sync + 'static,
+{
+    async fn execute(&self, action: A, events: &EventSink<InternalEvent>) -> anyhow::Result<()> {
+        if let Some(request) = action.order_request() {
+            let checked = self
+                .snapshot(request)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|snapshot| self.limits.check(request, &snapshot));
+            if let Err(reason) = checked {
+                self.reject(request, reason, events).await;
+                return Ok(());
+            }
+
+            if !self.check_rate() {
+                let reason = format!(
+                    "Order rate limit of {} per second reached",
+                    self.limits.max_orders_per_second.unwrap_or_default()
+                );
+                self.reject(request, reason, events).await;
+                return Ok(());
+            }
+        }
+
+        self.executor.execute(action, events).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Order;
+
+    fn place(side: Side, price: u64, size: u64) -> PlaceOrder {
+        PlaceOrder::new("BTCUSD", side, price.into(), size.into())
+    }
+
+    fn snapshot() -> RiskSnapshot {
+        RiskSnapshot {
+            mid_price: Some(100.into()),
+            position: 2.into(),
+            open_orders: 2,
+            open_bid_size: 1.into(),
+            open_ask_size: 1.into(),
+            amended_order: None,
+        }
+    }
+
+    #[test]
+    fn test_order_size_and_notional() {
+        let limits = RiskLimits {
+            max_order_size: Some(5.into()),
+            max_order_notional: Some(300.into()),
+            ..Default::default()
+        };
+
+        let ok = place(Side::Bid, 100, 3);
+        assert!(limits.check(OrderRequest::Place(&ok), &snapshot()).is_ok());
+
+        let too_large = place(Side::Bid, 10, 6);
+        assert!(limits
+            .check(OrderRequest::Place(&too_large), &snapshot())
+            .is_err());
+
+        let too_much_notional = place(Side::Bid, 100, 4);
+        assert!(limits
+            .check(OrderRequest::Place(&too_much_notional), &snapshot())
+            .is_err());
+    }
+
+    #[test]
+    fn test_max_position() {
+        let limits = RiskLimits {
+            max_position: Some(5.into()),
+            ..Default::default()
+        };
+
+        // Long 2 with 1 open bid: buying 2 more reaches 5, buying 3 breaches it
+        let bid = place(Side::Bid, 100, 2);
+        assert!(limits.check(OrderRequest::Place(&bid), &snapshot()).is_ok());
+        let bid = place(Side::Bid, 100, 3);
+        assert!(limits
+            .check(OrderRequest::Place(&bid), &snapshot())
+            .is_err());
+
+        // Selling reduces the long first: -2 + 1 + 6 = 5
+        let ask = place(Side::Ask, 100, 6);
+        assert!(limits.check(OrderRequest::Place(&ask), &snapshot()).is_ok());
+
+        // Amending the open bid replaces its size
+        let amend = AmendOrder {
+            symbol: "BTCUSD".to_string(),
+            oid: 1,
+            price: 100.into(),
+            size: 3.into(),
+        };
+        let snapshot = RiskSnapshot {
+            amended_order: Some((Side::Bid, 1.into())),
+            ..snapshot()
+        };
+        assert!(limits.check(OrderRequest::Amend(&amend), &snapshot).is_ok());
+    }
+
+    #[test]
+    fn test_open_orders_and_price_band() {
+        let limits = RiskLimits {
+            max_open_orders: Some(2),
+            price_band: Some(Decimal::from_str_unchecked("0.05")),
+            ..Default::default()
+        };
+
+        let order = place(Side::Bid, 99, 1);
+        assert!(limits
+            .check(OrderRequest::Place(&order), &snapshot())
+            .is_err());
+
+        let snapshot = RiskSnapshot {
+            open_orders: 0,
+            ..snapshot()
+        };
+        assert!(limits.check(OrderRequest::Place(&order), &snapshot).is_ok());
+
+        let fat_finger = place(Side::Bid, 90, 1);
+        assert!(limits
+            .check(OrderRequest::Place(&fat_finger), &snapshot)
+            .is_err());
+
+        let no_mid = RiskSnapshot {
+            mid_price: None,
+            ..snapshot
+        };
+        assert!(limits.check(OrderRequest::Place(&order), &no_mid).is_err());
+    }
+
+    struct Noop;
+
+    #[async_trait::async_trait]
+    impl Executor<BotAction, InternalEvent> for Noop {
+        async fn execute(
+            &self,
+            _action: BotAction,
+            _events: &EventSink<InternalEvent>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn risk_executor(states: &StateRegistry<InternalEvent>) -> RiskExecutor<BotAction> {
+        RiskExecutor::new(
+            Box::new(Noop),
+            "BTCUSD",
+            RiskLimits::default(),
+            states.clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_of_symbol() {
+        let mut states = StateRegistry::new();
+        states.register(PendingOrdersState::new());
+
+        // Fail closed without a position to check against
+        let order = place(Side::Bid, 100, 1);
+        let request = OrderRequest::Place(&order);
+        assert!(risk_executor(&states).snapshot(request).await.is_err());
+
+        states.register(PositionState::new());
+        for (oid, symbol, side) in [
+            (1, "BTCUSD", Side::Bid),
+            (2, "ETHUSD", Side::Bid),
+            (3, "ETHUSD", Side::Ask),
+        ] {
+            let order = Order::new(oid, symbol.to_string(), side, 100.into(), 2.into());
+            states.apply(InternalEvent::OrderPlaced(order)).await;
+        }
+
+        let snapshot = risk_executor(&states).snapshot(request).await.unwrap();
+        assert_eq!(snapshot.open_orders, 1);
+        assert_eq!(snapshot.open_bid_size, 2.into());
+        assert_eq!(snapshot.open_ask_size, Decimal::ZERO);
+
+        let other = PlaceOrder::new("ETHUSD", Side::Bid, 100.into(), 1.into());
+        assert!(risk_executor(&states)
+            .snapshot(OrderRequest::Place(&other))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejection_reaches_pending_orders() {
+        let mut states = StateRegistry::new();
+        states.register(PositionState::new());
+        let pending_orders = states.register(PendingOrdersState::new());
+        let risk_executor = RiskExecutor::new(
+            Box::new(Noop),
+            "BTCUSD",
+            RiskLimits {
+                max_order_size: Some(1.into()),
+                ..Default::default()
+            },
+            states.clone(),
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let order = place(Side::Bid, 100, 2);
+        risk_executor
+            .execute(
+                BotAction::PlaceOrder(order.clone()),
+                &EventSink::from_sender(tx),
+            )
+            .await
+            .unwrap();
+        states.apply(rx.recv().await.unwrap()).await;
+
+        let pending_orders = pending_orders.read().await;
+        let rejection = pending_orders.get_rejections().back().unwrap();
+        assert_eq!(rejection.cloid.as_deref(), Some(order.cloid.as_str()));
+        assert!(pending_orders.get_inner().is_empty());
+    }
+}
@@ -1,21 +1,150 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
 use crate::models::{Decimal, Side};
 
-#[derive(Debug, Clone)]
+static NEXT_CLOID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BotAction {
     PlaceOrder(PlaceOrder),
     CancelOrder(CancelOrder),
+    AmendOrder(AmendOrder),
+    /// Desired resting quotes for a symbol, to be reconciled against the open orders
+    /// by a quote executor rather than sent to the venue as is.
+    UpdateQuotes(QuoteSet),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaceOrder {
+    /// Client order id, echoed back on the resulting `Order` so the bot can
+    /// correlate acknowledgements with its requests.
+    pub cloid: String,
     pub symbol: String,
     pub side: Side,
     pub price: Decimal,
     pub size: Decimal,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CancelOrder {
     pub symbol: String,
     pub oid: usize,
 }
+
+/// Modifies a resting order in place. `size` is the new remaining size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmendOrder {
+    pub symbol: String,
+    pub oid: usize,
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteSet {
+    pub symbol: String,
+    pub bids: Vec<Quote>,
+    pub asks: Vec<Quote>,
+}
+
+impl PlaceOrder {
+    /// Creates a new order request with a freshly generated client order id.
+    pub fn new(symbol: impl Into<String>, side: Side, price: Decimal, size: Decimal) -> Self {
+        Self {
+            cloid: next_cloid(),
+            symbol: symbol.into(),
+            side,
+            price,
+            size,
+        }
+    }
+}
+
+impl Quote {
+    pub fn new(price: Decimal, size: Decimal) -> Self {
+        Self { price, size }
+    }
+}
+
+impl QuoteSet {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            bids: Vec::new(),
+            asks: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, side: Side, quote: Quote) {
+        match side {
+            Side::Bid => self.bids.push(quote),
+            Side::Ask => self.asks.push(quote),
+        }
+    }
+
+    pub fn quotes(&self, side: Side) -> &[Quote] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+}
+
+/// Generates a client order id unique within this process.
+pub fn next_cloid() -> String {
+    format!("hayate-{}", NEXT_CLOID.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_serde_round_trip() {
+        let mut quotes = QuoteSet::new("BTCUSDT");
+        quotes.push(
+            Side::Bid,
+            Quote::new(Decimal::from_str_unchecked("99.5"), 1.into()),
+        );
+        quotes.push(
+            Side::Ask,
+            Quote::new(Decimal::from_str_unchecked("100.5"), 1.into()),
+        );
+
+        let actions = vec![
+            BotAction::PlaceOrder(PlaceOrder::new("BTCUSDT", Side::Bid, 100.into(), 1.into())),
+            BotAction::CancelOrder(CancelOrder {
+                symbol: "BTCUSDT".to_string(),
+                oid: 1,
+            }),
+            BotAction::AmendOrder(AmendOrder {
+                symbol: "BTCUSDT".to_string(),
+                oid: 1,
+                price: Decimal::from_str_unchecked("100.5"),
+                size: 2.into(),
+            }),
+            BotAction::UpdateQuotes(quotes),
+        ];
+
+        let json = serde_json::to_string(&actions).unwrap();
+        let decoded: Vec<BotAction> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+
+        let BotAction::UpdateQuotes(quotes) = &decoded[3] else {
+            panic!("Expected quotes");
+        };
+        assert_eq!(
+            quotes.quotes(Side::Ask),
+            &[Quote::new(Decimal::from_str_unchecked("100.5"), 1.into())]
+        );
+    }
+}
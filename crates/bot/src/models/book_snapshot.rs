@@ -0,0 +1,51 @@
+use std::fmt::Debug;
+
+use clients::{BybitOrderEntry, BybitRestClient};
+
+use crate::models::{Decimal, OrderBookEventKind, OrderBookUpdate};
+
+/// Where to fetch the full order book from when its updates were lost, as the venue only
+/// sends a snapshot on subscription.
+#[async_trait::async_trait]
+pub trait BookSnapshotSource: Debug + Send + Sync {
+    async fn fetch(&self) -> anyhow::Result<OrderBookUpdate>;
+}
+
+/// The Bybit `/v5/market/orderbook` endpoint, with `limit` levels per side.
+#[derive(Debug, Clone)]
+pub struct BybitBookSnapshot {
+    pub category: String,
+    pub symbol: String,
+    pub limit: usize,
+}
+
+#[async_trait::async_trait]
+impl BookSnapshotSource for BybitBookSnapshot {
+    async fn fetch(&self) -> anyhow::Result<OrderBookUpdate> {
+        let mut client = BybitRestClient::new();
+        let book = client
+            .get_orderbook(&self.category, &self.symbol, self.limit)
+            .await?;
+
+        Ok(OrderBookUpdate {
+            symbol: book.symbol,
+            kind: OrderBookEventKind::Snapshot,
+            updated_at: chrono::Utc::now().timestamp_millis() as u64,
+            bids: book_levels(book.bids),
+            asks: book_levels(book.asks),
+        })
+    }
+}
+
+/// `(price, size)` levels of Bybit `[price, size]` entries, skipping invalid ones.
+pub(crate) fn book_levels(entries: Vec<BybitOrderEntry>) -> Vec<(Decimal, Decimal)> {
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            let size = entry.pop()?.try_into().ok()?;
+            let price = entry.pop()?.try_into().ok()?;
+
+            Some((price, size))
+        })
+        .collect()
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Decimal;
+
+/// An OHLCV bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Start of the bar, aligned to the interval for time bars
+    pub open_time: u64,
+    /// Time of the last update of the bar
+    pub close_time: u64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub trade_count: u64,
+}
+
+impl Candle {
+    /// Opens a bar at `open_time` with a first trade.
+    pub fn new(open_time: u64, price: Decimal, size: Decimal, timestamp: u64) -> Self {
+        Self {
+            open_time,
+            close_time: timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: size,
+            trade_count: 1,
+        }
+    }
+
+    /// A bar without trades, flat at the previous close.
+    pub fn empty(open_time: u64, close: Decimal) -> Self {
+        Self {
+            open_time,
+            close_time: open_time,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: Decimal::ZERO,
+            trade_count: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trade_count == 0
+    }
+
+    /// The greatest of high - low, |high - previous close| and |low - previous close|,
+    /// just high - low for the first bar.
+    pub fn true_range(&self, prev_close: Option<Decimal>) -> Decimal {
+        let range = self.high - self.low;
+        match prev_close {
+            Some(prev_close) => range
+                .max((self.high - prev_close).abs())
+                .max((self.low - prev_close).abs()),
+            None => range,
+        }
+    }
+
+    pub fn update(&mut self, price: Decimal, size: Decimal, timestamp: u64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += size;
+        self.trade_count += 1;
+        self.close_time = self.close_time.max(timestamp);
+    }
+}
+
+/// When a bar closes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandleInterval {
+    /// Fixed length in milliseconds, aligned to multiples of the length
+    Time(u64),
+    /// Once the traded volume reaches the threshold
+    Volume(Decimal),
+    /// After a number of trades
+    Ticks(u64),
+}
+
+impl fmt::Display for CandleInterval {
+    /// Short name of the interval, e.g. `1s`, `5m`, `v10` or `t100`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CandleInterval::Time(ms) => {
+                const UNITS: [(u64, &str); 4] = [
+                    (86_400_000, "d"),
+                    (3_600_000, "h"),
+                    (60_000, "m"),
+                    (1_000, "s"),
+                ];
+                match UNITS.iter().find(|(unit, _)| *ms > 0 && ms % unit == 0) {
+                    Some((unit, suffix)) => write!(f, "{}{}", ms / unit, suffix),
+                    None => write!(f, "{}ms", ms),
+                }
+            }
+            CandleInterval::Volume(volume) => {
+                let volume = volume.to_string();
+                let volume = volume.trim_end_matches('0').trim_end_matches('.');
+                write!(f, "v{}", volume)
+            }
+            CandleInterval::Ticks(ticks) => write!(f, "t{}", ticks),
+        }
+    }
+}
+
+/// Aggregates trades into bars of a single interval.
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    interval: CandleInterval,
+    current: Option<Candle>,
+}
+
+impl CandleAggregator {
+    /// Maximum number of empty time bars emitted for a gap between trades, enough for
+    /// any indicator window while bounding the work after a long silence.
+    pub const MAX_GAP_CANDLES: u64 = 1000;
+
+    pub fn new(interval: CandleInterval) -> Self {
+        Self {
+            interval,
+            current: None,
+        }
+    }
+
+    pub fn interval(&self) -> CandleInterval {
+        self.interval
+    }
+
+    /// The bar still being built.
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    /// Adds a trade, returning the bars it closed, oldest first.
+    ///
+    /// A time bar closes on the first trade of a later interval, which then opens the
+    /// next bar. Intervals without trades in between are returned as empty bars, at
+    /// most [`Self::MAX_GAP_CANDLES`] of them. Volume and tick bars close on the trade
+    /// reaching the threshold, trades are not split across bars.
+    pub fn update(&mut self, price: Decimal, size: Decimal, timestamp: u64) -> Vec<Candle> {
+        match self.interval {
+            CandleInterval::Time(ms) => {
+                let ms = ms.max(1);
+                let open_time = timestamp - timestamp % ms;
+                match &mut self.current {
+                    // Trades older than the current bar are folded into it
+                    Some(candle) if open_time <= candle.open_time => {
+                        candle.update(price, size, timestamp);
+                        Vec::new()
+                    }
+                    _ => {
+                        let next = Candle::new(open_time, price, size, timestamp);
+                        let Some(closed) = self.current.replace(next) else {
+                            return Vec::new();
+                        };
+
+                        let gaps = (open_time - closed.open_time) / ms - 1;
+                        let first_gap = open_time - gaps.min(Self::MAX_GAP_CANDLES) * ms;
+                        let empty = (first_gap..open_time)
+                            .step_by(ms as usize)
+                            .map(|gap_time| Candle::empty(gap_time, closed.close));
+                        std::iter::once(closed).chain(empty).collect()
+                    }
+                }
+            }
+            CandleInterval::Volume(threshold) => {
+                let candle = self.push(price, size, timestamp);
+                if candle.volume >= threshold {
+                    return self.current.take().into_iter().collect();
+                }
+                Vec::new()
+            }
+            CandleInterval::Ticks(ticks) => {
+                let candle = self.push(price, size, timestamp);
+                if candle.trade_count >= ticks {
+                    return self.current.take().into_iter().collect();
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.current = None;
+    }
+
+    fn push(&mut self, price: Decimal, size: Decimal, timestamp: u64) -> &Candle {
+        match &mut self.current {
+            Some(candle) => candle.update(price, size, timestamp),
+            None => self.current = Some(Candle::new(timestamp, price, size, timestamp)),
+        }
+        self.current.as_ref().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_candles() {
+        let mut aggregator = CandleAggregator::new(CandleInterval::Time(1000));
+
+        assert!(aggregator.update(100.into(), 1.into(), 1200).is_empty());
+        assert!(aggregator.update(102.into(), 2.into(), 1500).is_empty());
+        assert!(aggregator.update(99.into(), 1.into(), 1999).is_empty());
+
+        // Silent until the 4th second, the next trade closes the first bar followed by
+        // two empty bars
+        let closed = aggregator.update(101.into(), 1.into(), 4100);
+        assert_eq!(closed.len(), 3);
+        assert_eq!(
+            closed[0],
+            Candle {
+                open_time: 1000,
+                close_time: 1999,
+                open: 100.into(),
+                high: 102.into(),
+                low: 99.into(),
+                close: 99.into(),
+                volume: 4.into(),
+                trade_count: 3,
+            }
+        );
+        assert_eq!(closed[1], Candle::empty(2000, 99.into()));
+        assert_eq!(closed[2], Candle::empty(3000, 99.into()));
+        assert!(closed[2].is_empty());
+
+        let current = aggregator.current().unwrap();
+        assert_eq!(current.open_time, 4000);
+        assert_eq!(current.open, 101.into());
+    }
+
+    #[test]
+    fn test_volume_and_tick_candles() {
+        let mut volume = CandleAggregator::new(CandleInterval::Volume(3.into()));
+        assert!(volume.update(100.into(), 2.into(), 1).is_empty());
+        let closed = volume.update(101.into(), 2.into(), 2)[0];
+        assert_eq!(closed.volume, 4.into());
+        assert_eq!(closed.close, 101.into());
+        assert!(volume.current().is_none());
+
+        let mut ticks = CandleAggregator::new(CandleInterval::Ticks(2));
+        assert!(ticks.update(100.into(), 1.into(), 1).is_empty());
+        let closed = ticks.update(98.into(), 1.into(), 2)[0];
+        assert_eq!(
+            (closed.open, closed.low, closed.trade_count),
+            (100.into(), 98.into(), 2)
+        );
+    }
+
+    #[test]
+    fn test_long_gap_is_bounded() {
+        let mut aggregator = CandleAggregator::new(CandleInterval::Time(1));
+        aggregator.update(100.into(), 1.into(), 0);
+
+        let closed = aggregator.update(100.into(), 1.into(), 1_000_000);
+        assert_eq!(closed.len() as u64, CandleAggregator::MAX_GAP_CANDLES + 1);
+        assert_eq!(
+            closed[1].open_time,
+            1_000_000 - CandleAggregator::MAX_GAP_CANDLES
+        );
+        assert_eq!(closed.last().unwrap().open_time, 999_999);
+    }
+
+    #[test]
+    fn test_true_range() {
+        let mut candle = Candle::new(0, 100.into(), 1.into(), 0);
+        candle.update(103.into(), 1.into(), 1);
+        candle.update(101.into(), 1.into(), 2);
+
+        assert_eq!(candle.true_range(None), 3.into());
+        assert_eq!(candle.true_range(Some(98.into())), 5.into());
+        assert_eq!(candle.true_range(Some(105.into())), 5.into());
+        assert_eq!(candle.true_range(Some(101.into())), 3.into());
+    }
+
+    #[test]
+    fn test_interval_display() {
+        assert_eq!(CandleInterval::Time(1000).to_string(), "1s");
+        assert_eq!(CandleInterval::Time(300_000).to_string(), "5m");
+        assert_eq!(CandleInterval::Time(250).to_string(), "250ms");
+        assert_eq!(
+            CandleInterval::Volume(Decimal::from_str_unchecked("2.5")).to_string(),
+            "v2.5"
+        );
+        assert_eq!(CandleInterval::Ticks(100).to_string(), "t100");
+    }
+}
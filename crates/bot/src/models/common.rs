@@ -1,23 +1,47 @@
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::models::Decimal;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Side {
     Bid,
     Ask,
 }
 
-#[derive(Debug, Clone)]
+/// Lifecycle status of an order, from the moment it is submitted until it
+/// reaches a terminal state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Accepted by the venue but not yet working in the book.
+    PendingNew,
+    New,
+    PartiallyFilled,
+    Filled,
+    /// Cancellation requested but not yet confirmed by the venue.
+    PendingCancel,
+    Cancelled,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
     pub oid: usize,
+    /// Client order id, set by the bot when the order was requested
+    pub cloid: Option<String>,
     pub symbol: String,
     pub side: Side,
     pub price: Decimal,
+    /// Remaining (unfilled) size
     pub size: Decimal,
+    pub filled_size: Decimal,
+    pub status: OrderStatus,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub symbol: String,
     pub side: Side,
@@ -58,14 +82,53 @@ impl std::fmt::Display for Side {
     }
 }
 
+impl OrderStatus {
+    /// Returns `true` if the order can still be filled.
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::PendingNew
+                | OrderStatus::New
+                | OrderStatus::PartiallyFilled
+                | OrderStatus::PendingCancel
+        )
+    }
+
+    pub fn is_terminal(self) -> bool {
+        !self.is_open()
+    }
+}
+
+impl std::fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderStatus::PendingNew => write!(f, "pending_new"),
+            OrderStatus::New => write!(f, "new"),
+            OrderStatus::PartiallyFilled => write!(f, "partially_filled"),
+            OrderStatus::Filled => write!(f, "filled"),
+            OrderStatus::PendingCancel => write!(f, "pending_cancel"),
+            OrderStatus::Cancelled => write!(f, "cancelled"),
+            OrderStatus::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
 impl Order {
     pub fn new(oid: usize, symbol: String, side: Side, price: Decimal, size: Decimal) -> Self {
         Self {
             oid,
+            cloid: None,
             symbol,
             side,
             price,
             size,
+            filled_size: Decimal::ZERO,
+            status: OrderStatus::New,
         }
     }
+
+    pub fn with_cloid(mut self, cloid: impl Into<String>) -> Self {
+        self.cloid = Some(cloid.into());
+        self
+    }
 }
@@ -5,27 +5,121 @@ use std::{
     str::FromStr,
 };
 
-#[derive(Clone, Copy)]
-pub struct Decimal {
-    sign: i8,
-    raw: u64,
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Fixed-point decimal with 6 decimal places.
+pub type Decimal = FixedDecimal<6>;
+
+/// Signed fixed-point decimal with `S` decimal places, stored as an `i128` count of
+/// `10^-S` units. `S` must be at most 18.
+///
+/// The integer part is bounded by `i64::MAX` in magnitude, leaving headroom in the
+/// `i128` representation for intermediate products. Operators panic on overflow and
+/// division by zero like integer operators do; use the `checked_*` or `saturating_*`
+/// methods where the operands are not known to be in range.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedDecimal<const S: u32> {
+    raw: i128,
 }
 
-impl Decimal {
-    pub const DECIMAL: usize = 6;
-    pub const SCALE: u64 = 10u64.pow(Self::DECIMAL as u32);
-    pub const ZERO: Self = Self { sign: 1, raw: 0 };
-    pub const ONE: Self = Self {
-        sign: 1,
-        raw: Self::SCALE,
-    };
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalError {
+    Overflow,
+    DivisionByZero,
+    /// The string is not a valid decimal number
+    Invalid(&'static str),
+    /// NaN or infinite `f64`
+    NotFinite,
+}
+
+impl std::fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecimalError::Overflow => write!(f, "Decimal overflow"),
+            DecimalError::DivisionByZero => write!(f, "Division by zero in Decimal division"),
+            DecimalError::Invalid(reason) => write!(f, "{}", reason),
+            DecimalError::NotFinite => write!(f, "Cannot convert NaN or infinite value to Decimal"),
+        }
+    }
+}
+
+impl std::error::Error for DecimalError {}
+
+/// How to round a value that cannot be represented exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Towards zero
+    #[default]
+    Down,
+    /// Away from zero
+    Up,
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceiling,
+    /// To the nearest, ties away from zero
+    HalfUp,
+    /// To the nearest, ties to the even neighbour
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// Whether an inexact magnitude is rounded away from zero, given how the discarded
+    /// part compares to half a unit and whether the kept magnitude is odd.
+    fn away_from_zero(self, negative: bool, odd: bool, half: std::cmp::Ordering) -> bool {
+        match self {
+            RoundingMode::Down => false,
+            RoundingMode::Up => true,
+            RoundingMode::Floor => negative,
+            RoundingMode::Ceiling => !negative,
+            RoundingMode::HalfUp => half != std::cmp::Ordering::Less,
+            RoundingMode::HalfEven => {
+                half == std::cmp::Ordering::Greater || (half == std::cmp::Ordering::Equal && odd)
+            }
+        }
+    }
+
+    /// Divides `numerator` by a positive or negative `denominator`, rounding the quotient.
+    fn div(self, numerator: i128, denominator: i128) -> i128 {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return quotient;
+        }
+
+        let negative = (numerator < 0) != (denominator < 0);
+        let half = (remainder.unsigned_abs() * 2).cmp(&denominator.unsigned_abs());
+        if !self.away_from_zero(negative, quotient % 2 != 0, half) {
+            quotient
+        } else if negative {
+            quotient - 1
+        } else {
+            quotient + 1
+        }
+    }
+}
+
+impl<const S: u32> FixedDecimal<S> {
+    pub const DECIMAL: usize = S as usize;
+    pub const SCALE: i128 = 10i128.pow(S);
+    pub const ZERO: Self = Self { raw: 0 };
+    pub const ONE: Self = Self { raw: Self::SCALE };
     pub const MAX: Self = Self {
-        sign: 1,
-        raw: u64::MAX / Self::SCALE,
+        raw: i64::MAX as i128 * Self::SCALE + (Self::SCALE - 1),
+    };
+    pub const MIN: Self = Self {
+        raw: -Self::MAX.raw,
     };
 
+    fn from_raw(raw: i128) -> Result<Self, DecimalError> {
+        if raw > Self::MAX.raw || raw < Self::MIN.raw {
+            return Err(DecimalError::Overflow);
+        }
+        Ok(Self { raw })
+    }
+
     pub fn from_str_unchecked(value: &str) -> Self {
-        Decimal::from_str(value).unwrap()
+        Self::from_str(value).unwrap()
     }
 
     pub fn is_zero(&self) -> bool {
@@ -33,277 +127,381 @@ impl Decimal {
     }
 
     pub fn is_positive(&self) -> bool {
-        self.sign > 0 && !self.is_zero()
+        self.raw > 0
     }
 
     pub fn is_negative(&self) -> bool {
-        self.sign < 0 && !self.is_zero()
+        self.raw < 0
     }
 
     pub fn abs(&self) -> Self {
         Self {
-            sign: 1,
-            raw: self.raw,
+            raw: self.raw.abs(),
         }
     }
 
-    pub fn to_f64(&self) -> f64 {
-        if self.is_zero() {
-            return 0.0;
+    /// Square root truncated to `S` decimal places, `None` for negative values.
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.raw < 0 {
+            return None;
         }
+        let squared = self.raw.checked_mul(Self::SCALE)?;
+        Some(Self {
+            raw: squared.isqrt(),
+        })
+    }
 
-        let value = self.raw as f64 / Self::SCALE as f64;
-        if self.sign < 0 {
-            -value
-        } else {
-            value
-        }
+    pub fn to_f64(&self) -> f64 {
+        self.raw as f64 / Self::SCALE as f64
     }
 
+    /// Truncates towards zero.
     pub fn to_i64(&self) -> i64 {
-        if self.is_zero() {
-            return 0;
-        }
-
-        let value = self.raw as i64 / Self::SCALE as i64;
-        if self.sign < 0 {
-            -value
+        (self.raw / Self::SCALE) as i64
+    }
+
+    /// Converts to another scale, rounding if the target has fewer decimal places.
+    pub fn rescale<const T: u32>(
+        &self,
+        mode: RoundingMode,
+    ) -> Result<FixedDecimal<T>, DecimalError> {
+        let raw = if T >= S {
+            self.raw
+                .checked_mul(10i128.pow(T - S))
+                .ok_or(DecimalError::Overflow)?
         } else {
-            value
-        }
+            mode.div(self.raw, 10i128.pow(S - T))
+        };
+        FixedDecimal::<T>::from_raw(raw)
     }
-}
 
-impl FromStr for Decimal {
-    type Err = &'static str;
+    pub fn checked_add(self, other: Self) -> Result<Self, DecimalError> {
+        Self::from_raw(self.raw + other.raw)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Decimal::try_from(s.to_string())
+    pub fn checked_sub(self, other: Self) -> Result<Self, DecimalError> {
+        Self::from_raw(self.raw - other.raw)
+    }
+
+    /// Multiplies, truncating the result towards zero.
+    pub fn checked_mul(self, other: Self) -> Result<Self, DecimalError> {
+        self.mul_with_rounding(other, RoundingMode::Down)
+    }
+
+    /// Divides, truncating the result towards zero.
+    pub fn checked_div(self, other: Self) -> Result<Self, DecimalError> {
+        self.div_with_rounding(other, RoundingMode::Down)
+    }
+
+    pub fn mul_with_rounding(self, other: Self, mode: RoundingMode) -> Result<Self, DecimalError> {
+        let product = self
+            .raw
+            .checked_mul(other.raw)
+            .ok_or(DecimalError::Overflow)?;
+        Self::from_raw(mode.div(product, Self::SCALE))
     }
-}
 
-impl From<f64> for Decimal {
-    fn from(value: f64) -> Self {
-        if value.is_nan() || value.is_infinite() {
-            panic!("Cannot convert NaN or infinite value to Decimal");
+    pub fn div_with_rounding(self, other: Self, mode: RoundingMode) -> Result<Self, DecimalError> {
+        if other.raw == 0 {
+            return Err(DecimalError::DivisionByZero);
         }
 
-        let sign = if value < 0.0 { -1 } else { 1 };
-        let scaled = value.abs() * (Decimal::SCALE as f64);
-        let raw = scaled.round() as u64;
+        let numerator = self
+            .raw
+            .checked_mul(Self::SCALE)
+            .ok_or(DecimalError::Overflow)?;
+        Self::from_raw(mode.div(numerator, other.raw))
+    }
 
-        Self { sign, raw }
+    pub fn saturating_add(self, other: Self) -> Self {
+        self.checked_add(other)
+            .unwrap_or_else(|_| self.saturate(other.is_negative()))
     }
-}
 
-impl From<u64> for Decimal {
-    fn from(value: u64) -> Self {
+    pub fn saturating_sub(self, other: Self) -> Self {
+        self.checked_sub(other)
+            .unwrap_or_else(|_| self.saturate(other.is_positive()))
+    }
+
+    pub fn saturating_mul(self, other: Self) -> Self {
+        self.checked_mul(other)
+            .unwrap_or_else(|_| Self::MAX.saturate(self.is_negative() != other.is_negative()))
+    }
+
+    fn saturate(self, negative: bool) -> Self {
+        if negative {
+            Self::MIN
+        } else {
+            Self::MAX
+        }
+    }
+
+    /// Rounds to `dp` decimal places.
+    pub fn round_dp(&self, dp: u32, mode: RoundingMode) -> Self {
+        if dp >= S {
+            return *self;
+        }
+        self.round_to_step(
+            Self {
+                raw: 10i128.pow(S - dp),
+            },
+            mode,
+        )
+    }
+
+    /// Rounds to a multiple of `step`. Returns the value unchanged if `step` is zero.
+    pub fn round_to_step(&self, step: Self, mode: RoundingMode) -> Self {
+        if step.raw == 0 {
+            return *self;
+        }
         Self {
-            sign: 1,
-            raw: value * Decimal::SCALE,
+            raw: mode.div(self.raw, step.raw.abs()) * step.raw.abs(),
         }
     }
-}
 
-impl TryFrom<String> for Decimal {
-    type Error = &'static str;
+    /// Rounds to the nearest multiple of `tick`, with ties away from zero.
+    /// Returns the value unchanged if `tick` is zero.
+    pub fn round_to_tick(&self, tick: Self) -> Self {
+        self.round_to_step(tick, RoundingMode::HalfUp)
+    }
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        let trimmed = value.trim();
+    /// Rounds down (towards negative infinity) to a multiple of `tick`.
+    pub fn floor_to_tick(&self, tick: Self) -> Self {
+        self.round_to_step(tick, RoundingMode::Floor)
+    }
+
+    /// Rounds up (towards positive infinity) to a multiple of `tick`.
+    pub fn ceil_to_tick(&self, tick: Self) -> Self {
+        self.round_to_step(tick, RoundingMode::Ceiling)
+    }
+
+    /// Rounds a size down to a multiple of `lot`.
+    pub fn floor_to_lot(&self, lot: Self) -> Self {
+        self.floor_to_tick(lot)
+    }
+
+    pub fn is_multiple_of(&self, step: Self) -> bool {
+        step.raw == 0 || self.raw % step.raw == 0
+    }
+}
 
+impl<const S: u32> FixedDecimal<S> {
+    /// Parses a decimal such as `"-12.5"`, `"1e-5"` or `"1.5E+3"`, rounding digits beyond
+    /// `S` decimal places with `mode`.
+    pub fn parse_with_rounding(value: &str, mode: RoundingMode) -> Result<Self, DecimalError> {
+        let trimmed = value.trim();
         if trimmed.is_empty() {
-            return Err("Empty string cannot be converted to Decimal");
+            return Err(DecimalError::Invalid(
+                "Empty string cannot be converted to Decimal",
+            ));
         }
 
-        let sign = if trimmed.starts_with('-') { -1 } else { 1 };
-        let unsigned = if sign == -1 { &trimmed[1..] } else { trimmed };
+        let (negative, unsigned) = match trimmed.as_bytes()[0] {
+            b'-' => (true, &trimmed[1..]),
+            b'+' => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
 
-        let parts: Vec<&str> = unsigned.split('.').collect();
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => {
+                let exponent = unsigned[index + 1..]
+                    .parse::<i32>()
+                    .map_err(|_| DecimalError::Invalid("Invalid exponent"))?;
+                (&unsigned[..index], exponent as i64)
+            }
+            None => (unsigned, 0),
+        };
 
-        if parts.len() > 2 {
-            return Err("Invalid Decimal format");
+        let (integer_part, fractional_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer_part.is_empty() && fractional_part.is_empty() {
+            return Err(DecimalError::Invalid("Invalid Decimal format"));
+        }
+        if !integer_part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(DecimalError::Invalid("Invalid integer part"));
+        }
+        if !fractional_part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(DecimalError::Invalid("Invalid fractional part"));
         }
 
-        let integer_part = parts[0]
-            .parse::<u64>()
-            .map_err(|_| "Invalid integer part")?;
+        // value = digits * 10^(shift - S), so raw = digits * 10^shift
+        let digits = format!("{}{}", integer_part, fractional_part);
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(Self::ZERO);
+        }
+        let shift = exponent - fractional_part.len() as i64 + S as i64;
 
-        let fractional_part = if parts.len() == 2 {
-            let fraction_str = format!("{:0<width$}", parts[1], width = Decimal::DECIMAL);
-            fraction_str[..Decimal::DECIMAL]
-                .parse::<u64>()
-                .map_err(|_| "Invalid fractional part")?
+        let raw = if shift >= 0 {
+            // Anything longer than 39 digits overflows i128
+            if digits.len() as i64 + shift > 39 {
+                return Err(DecimalError::Overflow);
+            }
+            let magnitude = digits.parse::<i128>().map_err(|_| DecimalError::Overflow)?;
+            magnitude
+                .checked_mul(10i128.pow(shift as u32))
+                .ok_or(DecimalError::Overflow)?
         } else {
-            0
+            let dropped_len = shift.unsigned_abs().min(digits.len() as u64 + 1) as usize;
+            let (kept, dropped) = if dropped_len >= digits.len() {
+                ("", digits)
+            } else {
+                digits.split_at(digits.len() - dropped_len)
+            };
+            let magnitude = if kept.is_empty() {
+                0
+            } else {
+                kept.parse::<i128>().map_err(|_| DecimalError::Overflow)?
+            };
+
+            // Compare the dropped digits, as a fraction of a unit, with one half
+            let half = if dropped_len > digits.len() {
+                // Dropped digits start with an implicit leading zero
+                std::cmp::Ordering::Less
+            } else {
+                match dropped.as_bytes()[0].cmp(&b'5') {
+                    std::cmp::Ordering::Equal if dropped[1..].bytes().any(|b| b != b'0') => {
+                        std::cmp::Ordering::Greater
+                    }
+                    ordering => ordering,
+                }
+            };
+
+            let inexact = dropped.bytes().any(|b| b != b'0');
+            if inexact && mode.away_from_zero(negative, magnitude % 2 != 0, half) {
+                magnitude + 1
+            } else {
+                magnitude
+            }
         };
 
-        let raw = integer_part
-            .checked_mul(Decimal::SCALE)
-            .and_then(|v| v.checked_add(fractional_part))
-            .ok_or("Decimal overflow")?;
+        Self::from_raw(if negative { -raw } else { raw })
+    }
+}
+
+/// Parses with [`RoundingMode::HalfEven`], see [`FixedDecimal::parse_with_rounding`].
+impl<const S: u32> FromStr for FixedDecimal<S> {
+    type Err = DecimalError;
 
-        Ok(Self { sign, raw })
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_rounding(s, RoundingMode::HalfEven)
     }
 }
 
-impl PartialEq for Decimal {
-    fn eq(&self, other: &Self) -> bool {
-        self.sign == other.sign && self.raw == other.raw
+impl<const S: u32> TryFrom<&str> for FixedDecimal<S> {
+    type Error = DecimalError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::from_str(value)
     }
 }
 
-impl Eq for Decimal {}
+impl<const S: u32> TryFrom<String> for FixedDecimal<S> {
+    type Error = DecimalError;
 
-impl PartialOrd for Decimal {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
     }
 }
 
-impl Ord for Decimal {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.sign != other.sign {
-            return self.sign.cmp(&other.sign);
+/// Converts the shortest decimal representation of the `f64`, so that e.g. `0.1` becomes
+/// exactly `0.1` rather than the nearest binary value, rounding half to even.
+impl<const S: u32> TryFrom<f64> for FixedDecimal<S> {
+    type Error = DecimalError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if !value.is_finite() {
+            return Err(DecimalError::NotFinite);
         }
-        self.raw.cmp(&other.raw)
+        Self::from_str(&value.to_string())
+    }
+}
+
+impl<const S: u32> From<u64> for FixedDecimal<S> {
+    fn from(value: u64) -> Self {
+        Self::from_raw(value as i128 * Self::SCALE).expect("Decimal overflow")
     }
 }
 
-impl Add for Decimal {
+impl<const S: u32> Add for FixedDecimal<S> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        if self.sign == other.sign {
-            Self {
-                sign: self.sign,
-                raw: self.raw + other.raw,
-            }
-        } else {
-            match self.raw.cmp(&other.raw) {
-                std::cmp::Ordering::Greater => Self {
-                    sign: self.sign,
-                    raw: self.raw - other.raw,
-                },
-                std::cmp::Ordering::Less => Self {
-                    sign: -self.sign,
-                    raw: other.raw - self.raw,
-                },
-                std::cmp::Ordering::Equal => Self::ZERO,
-            }
-        }
+        self.checked_add(other).expect("Decimal addition overflow")
     }
 }
 
-impl Sub for Decimal {
+impl<const S: u32> Sub for FixedDecimal<S> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        if self.sign != other.sign {
-            Self {
-                sign: self.sign,
-                raw: self.raw + other.raw,
-            }
-        } else {
-            match self.raw.cmp(&other.raw) {
-                std::cmp::Ordering::Greater => Self {
-                    sign: self.sign,
-                    raw: self.raw - other.raw,
-                },
-                std::cmp::Ordering::Less => Self {
-                    sign: -self.sign,
-                    raw: other.raw - self.raw,
-                },
-                std::cmp::Ordering::Equal => Self::ZERO,
-            }
-        }
+        self.checked_sub(other)
+            .expect("Decimal subtraction overflow")
     }
 }
 
-impl Div for Decimal {
+impl<const S: u32> Div for FixedDecimal<S> {
     type Output = Self;
 
     fn div(self, other: Self) -> Self {
-        if other.raw == 0 {
-            panic!("Division by zero in Decimal division");
-        }
-
-        if self.raw == 0 {
-            return Self::ZERO;
-        }
-
-        let sign = self.sign * other.sign;
-        let raw = (self.raw as u128 * Decimal::SCALE as u128) / (other.raw as u128);
-        Self {
-            sign,
-            raw: raw as u64,
+        match self.checked_div(other) {
+            Ok(value) => value,
+            Err(e) => panic!("{}", e),
         }
     }
 }
 
-impl Mul for Decimal {
+impl<const S: u32> Mul for FixedDecimal<S> {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self {
-        if self.raw == 0 || other.raw == 0 {
-            return Self::ZERO;
-        }
-
-        let sign = self.sign * other.sign;
-        let raw = (self.raw as u128 * other.raw as u128) / Decimal::SCALE as u128;
-
-        if raw as u64 > Decimal::MAX.raw {
-            panic!("Decimal multiplication overflow");
-        }
-
-        Self {
-            sign,
-            raw: raw as u64,
-        }
+        self.checked_mul(other)
+            .expect("Decimal multiplication overflow")
     }
 }
 
-impl Neg for Decimal {
+impl<const S: u32> Neg for FixedDecimal<S> {
     type Output = Self;
 
     fn neg(self) -> Self {
-        if self.is_zero() {
-            return Self::ZERO;
-        }
-        Self {
-            sign: -self.sign,
-            raw: self.raw,
-        }
+        Self { raw: -self.raw }
     }
 }
 
-impl Sum for Decimal {
+impl<const S: u32> Sum for FixedDecimal<S> {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
         iter.fold(Self::ZERO, |acc, x| acc + x)
     }
 }
 
-impl AddAssign for Decimal {
+impl<const S: u32> AddAssign for FixedDecimal<S> {
     fn add_assign(&mut self, other: Self) {
         *self = *self + other;
     }
 }
 
-impl SubAssign for Decimal {
+impl<const S: u32> SubAssign for FixedDecimal<S> {
     fn sub_assign(&mut self, other: Self) {
         *self = *self - other;
     }
 }
 
-impl MulAssign for Decimal {
+impl<const S: u32> MulAssign for FixedDecimal<S> {
     fn mul_assign(&mut self, other: Self) {
         *self = *self * other;
     }
 }
 
-impl std::fmt::Display for Decimal {
+impl<const S: u32> std::fmt::Display for FixedDecimal<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let sign_str = if self.sign < 0 { "-" } else { "" };
-        let raw_str = format!("{:0>width$}", self.raw, width = Self::DECIMAL + 1);
+        let sign_str = if self.raw < 0 { "-" } else { "" };
+        let raw_str = format!(
+            "{:0>width$}",
+            self.raw.unsigned_abs(),
+            width = Self::DECIMAL + 1
+        );
+        if S == 0 {
+            return write!(f, "{}{}", sign_str, raw_str);
+        }
         write!(
             f,
             "{}{}.{}",
@@ -314,35 +512,114 @@ impl std::fmt::Display for Decimal {
     }
 }
 
-impl std::fmt::Debug for Decimal {
+impl<const S: u32> std::fmt::Debug for FixedDecimal<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Decimal({})", self)
     }
 }
 
-impl Hash for Decimal {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.sign.hash(state);
-        self.raw.hash(state);
+impl<const S: u32> Default for FixedDecimal<S> {
+    fn default() -> Self {
+        Self::ZERO
     }
 }
 
-impl Default for Decimal {
-    fn default() -> Self {
-        Self::ZERO
+/// Serialized as an exact decimal string, e.g. `"100.500000"`.
+impl<const S: u32> Serialize for FixedDecimal<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialized from a decimal string only, as numbers may already have lost precision.
+/// Use [`decimal_lenient`] to also accept numbers.
+impl<'de, const S: u32> Deserialize<'de> for FixedDecimal<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(DecimalVisitor::<S> { lenient: false })
+    }
+}
+
+struct DecimalVisitor<const S: u32> {
+    lenient: bool,
+}
+
+impl<const S: u32> DecimalVisitor<S> {
+    fn reject<E: de::Error>(&self, unexpected: de::Unexpected) -> Result<FixedDecimal<S>, E> {
+        Err(E::invalid_type(unexpected, self))
+    }
+}
+
+impl<const S: u32> de::Visitor<'_> for DecimalVisitor<S> {
+    type Value = FixedDecimal<S>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.lenient {
+            write!(f, "a decimal string or number")
+        } else {
+            write!(f, "a decimal string")
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        FixedDecimal::from_str(value).map_err(|e| E::custom(format!("{}: {}", e, value)))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        if !self.lenient {
+            return self.reject(de::Unexpected::Unsigned(value));
+        }
+        FixedDecimal::from_raw(value as i128 * FixedDecimal::<S>::SCALE).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        if !self.lenient {
+            return self.reject(de::Unexpected::Signed(value));
+        }
+        FixedDecimal::from_raw(value as i128 * FixedDecimal::<S>::SCALE).map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        if !self.lenient {
+            return self.reject(de::Unexpected::Float(value));
+        }
+        FixedDecimal::try_from(value).map_err(E::custom)
+    }
+}
+
+/// Serde helpers for decimals that may be given as JSON numbers, e.g. in hand-written
+/// config files, for use with `#[serde(with = "decimal_lenient")]`. Serialization is
+/// the same exact string as the default.
+pub mod decimal_lenient {
+    use serde::{Deserializer, Serialize, Serializer};
+
+    use super::{DecimalVisitor, FixedDecimal};
+
+    pub fn serialize<Ser: Serializer, const S: u32>(
+        value: &FixedDecimal<S>,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error> {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const S: u32>(
+        deserializer: D,
+    ) -> Result<FixedDecimal<S>, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor::<S> { lenient: true })
     }
 }
 
 #[cfg(test)]
 mod decimal_tests {
     use super::*;
+    use bigdecimal::{BigDecimal, ToPrimitive};
+    use proptest::prelude::*;
 
     #[test]
     fn test_decimal_creation() {
-        let d1 = Decimal::from(123.456789);
+        let d1 = Decimal::try_from(123.456789).unwrap();
         assert_eq!(d1.to_string(), "123.456789");
 
-        let d2 = Decimal::from(-123.456789);
+        let d2 = Decimal::try_from(-123.456789).unwrap();
         assert_eq!(d2.to_string(), "-123.456789");
 
         let d3 = Decimal::from(0u64);
@@ -373,20 +650,20 @@ mod decimal_tests {
     #[test]
     fn test_decimal_addition() {
         let mut d1 = Decimal::from(100);
-        let d2 = Decimal::from(50.0);
+        let d2 = Decimal::try_from(50.0).unwrap();
         d1 += d2;
         assert_eq!(d1.to_string(), "150.000000");
 
-        let d3 = Decimal::from(-30.0);
+        let d3 = Decimal::try_from(-30.0).unwrap();
         d1 += d3;
         assert_eq!(d1.to_string(), "120.000000");
 
-        let mut d4 = Decimal::from(-120.0);
-        let d5 = Decimal::from(-20.0);
+        let mut d4 = Decimal::try_from(-120.0).unwrap();
+        let d5 = Decimal::try_from(-20.0).unwrap();
         d4 += d5;
         assert_eq!(d4.to_string(), "-140.000000");
 
-        let d6 = Decimal::from(140.0);
+        let d6 = Decimal::try_from(140.0).unwrap();
         d4 += d6;
         assert_eq!(d4.to_string(), "0.000000");
         assert_eq!(d4, Decimal::ZERO);
@@ -395,36 +672,36 @@ mod decimal_tests {
     #[test]
     fn test_decimal_subtraction() {
         let mut d1 = Decimal::from(100);
-        let d2 = Decimal::from(50.0);
+        let d2 = Decimal::try_from(50.0).unwrap();
         d1 -= d2;
         assert_eq!(d1.to_string(), "50.000000");
 
-        let d3 = Decimal::from(30.0);
+        let d3 = Decimal::try_from(30.0).unwrap();
         d1 -= d3;
         assert_eq!(d1.to_string(), "20.000000");
 
-        let mut d4 = Decimal::from(-20.0);
-        let d5 = Decimal::from(-10.0);
+        let mut d4 = Decimal::try_from(-20.0).unwrap();
+        let d5 = Decimal::try_from(-10.0).unwrap();
         d4 -= d5;
         assert_eq!(d4.to_string(), "-10.000000");
-        let d6 = Decimal::from(-10.0);
+        let d6 = Decimal::try_from(-10.0).unwrap();
         d4 -= d6;
         assert_eq!(d4.to_string(), "0.000000");
         assert_eq!(d4, Decimal::ZERO);
 
-        let d7 = Decimal::from(100.0);
+        let d7 = Decimal::try_from(100.0).unwrap();
         d4 -= d7;
         assert_eq!(d4.to_string(), "-100.000000");
     }
 
     #[test]
     fn test_decimal_division() {
-        let d1 = Decimal::from(100.0);
-        let d2 = Decimal::from(2.0);
+        let d1 = Decimal::try_from(100.0).unwrap();
+        let d2 = Decimal::try_from(2.0).unwrap();
         let result = d1 / d2;
         assert_eq!(result.to_string(), "50.000000");
 
-        let d3 = Decimal::from(0.5);
+        let d3 = Decimal::try_from(0.5).unwrap();
         let result = d1 / d3;
         assert_eq!(result.to_string(), "200.000000");
     }
@@ -432,46 +709,307 @@ mod decimal_tests {
     #[test]
     #[should_panic(expected = "Division by zero in Decimal division")]
     fn test_decimal_zero_division() {
-        let d1 = Decimal::from(100.0);
-        let d2 = Decimal::from(0.0);
+        let d1 = Decimal::try_from(100.0).unwrap();
+        let d2 = Decimal::try_from(0.0).unwrap();
         let _result = d1 / d2; // This should panic
     }
 
     #[test]
     fn test_decimal_multiplication() {
-        let d1 = Decimal::from(10.0);
-        let d2 = Decimal::from(5.0);
+        let d1 = Decimal::try_from(10.0).unwrap();
+        let d2 = Decimal::try_from(5.0).unwrap();
         let result = d1 * d2;
         assert_eq!(result.to_string(), "50.000000");
 
-        let d3 = Decimal::from(0.1);
+        let d3 = Decimal::try_from(0.1).unwrap();
         let result = d1 * d3;
         assert_eq!(result.to_string(), "1.000000");
 
-        let d4 = Decimal::from(0.0);
+        let d4 = Decimal::try_from(0.0).unwrap();
         let result = d1 * d4;
         assert_eq!(result.to_string(), "0.000000");
-        let d5 = Decimal::from(-2.0);
+        let d5 = Decimal::try_from(-2.0).unwrap();
         let result = d1 * d5;
         assert_eq!(result.to_string(), "-20.000000");
 
         let d6 = Decimal::from(105);
-        let d7 = Decimal::from(0.5);
+        let d7 = Decimal::try_from(0.5).unwrap();
         let result = d6 * d7;
         assert_eq!(result.to_string(), "52.500000");
     }
 
     #[test]
     fn test_decimal_op_combination() {
-        let d1 = Decimal::from(100.0);
-        let d2 = Decimal::from(1.5);
+        let d1 = Decimal::try_from(100.0).unwrap();
+        let d2 = Decimal::try_from(1.5).unwrap();
         assert_eq!((d1 * d2).to_string(), "150.000000");
         let d3 = Decimal::from(105);
-        let d4 = Decimal::from(0.5);
+        let d4 = Decimal::try_from(0.5).unwrap();
         assert_eq!((d3 * d4).to_string(), "52.500000");
         assert_eq!(((d1 * d2) + (d3 * d4)).to_string(), "202.500000");
-        let d5 = Decimal::from(2.0);
+        let d5 = Decimal::try_from(2.0).unwrap();
         let result = ((d1 * d2) + (d3 * d4)) / d5;
         assert_eq!(result.to_string(), "101.250000");
     }
+
+    #[test]
+    fn test_decimal_ordering() {
+        let a = Decimal::try_from(-1.2).unwrap();
+        let b = Decimal::try_from(-1.0).unwrap();
+        assert!(a < b);
+        assert!(b < Decimal::ZERO);
+        assert_eq!(a.max(b), b);
+        assert_eq!(a.max(-Decimal::ONE), -Decimal::ONE);
+    }
+
+    #[test]
+    fn test_decimal_tick_rounding() {
+        let tick = Decimal::try_from(0.5).unwrap();
+        let price = Decimal::try_from(100.3).unwrap();
+        assert_eq!(price.round_to_tick(tick), Decimal::try_from(100.5).unwrap());
+        assert_eq!(price.floor_to_tick(tick), 100.into());
+        assert_eq!(price.ceil_to_tick(tick), Decimal::try_from(100.5).unwrap());
+        assert_eq!(
+            Decimal::try_from(100.25).unwrap().round_to_tick(tick),
+            Decimal::try_from(100.5).unwrap()
+        );
+        assert_eq!(
+            (-price).floor_to_tick(tick),
+            Decimal::try_from(-100.5).unwrap()
+        );
+        assert_eq!(
+            (-price).ceil_to_tick(tick),
+            Decimal::try_from(-100.0).unwrap()
+        );
+
+        assert_eq!(
+            Decimal::try_from(0.0123)
+                .unwrap()
+                .floor_to_lot(Decimal::try_from(0.001).unwrap()),
+            Decimal::try_from(0.012).unwrap()
+        );
+        assert!(Decimal::try_from(1.5).unwrap().is_multiple_of(tick));
+        assert!(!price.is_multiple_of(tick));
+    }
+
+    #[test]
+    fn test_decimal_checked_ops() {
+        let max = Decimal::MAX;
+        assert_eq!(max.checked_add(Decimal::ONE), Err(DecimalError::Overflow));
+        assert_eq!(max.checked_mul(2.into()), Err(DecimalError::Overflow));
+        assert_eq!(
+            Decimal::ONE.checked_div(Decimal::ZERO),
+            Err(DecimalError::DivisionByZero)
+        );
+        assert_eq!(max.saturating_add(Decimal::ONE), max);
+        assert_eq!((-max).saturating_mul(2.into()), Decimal::MIN);
+        assert_eq!(
+            Decimal::from(1_000_000_000_000u64).checked_mul(1_000_000.into()),
+            Ok(Decimal::from(1_000_000_000_000_000_000u64))
+        );
+    }
+
+    #[test]
+    fn test_decimal_sqrt() {
+        assert_eq!(Decimal::from(16).sqrt(), Some(4.into()));
+        assert_eq!(
+            Decimal::from(2).sqrt(),
+            Some(Decimal::from_str_unchecked("1.414213"))
+        );
+        assert_eq!(
+            Decimal::from_str_unchecked("0.0001").sqrt(),
+            Some(Decimal::from_str_unchecked("0.01"))
+        );
+        assert_eq!(
+            Decimal::MAX.sqrt().map(|root| root > Decimal::ZERO),
+            Some(true)
+        );
+        assert_eq!((-Decimal::from(4)).sqrt(), None);
+    }
+
+    #[test]
+    fn test_decimal_rounding_modes() {
+        let one = Decimal::ONE;
+        let three = Decimal::from(3);
+        assert_eq!(one.checked_div(three).unwrap().to_string(), "0.333333");
+        assert_eq!(
+            one.div_with_rounding(three, RoundingMode::Up)
+                .unwrap()
+                .to_string(),
+            "0.333334"
+        );
+        assert_eq!(
+            (-Decimal::from(2)).div_with_rounding(three, RoundingMode::Floor),
+            Ok(Decimal::try_from(-0.666667).unwrap())
+        );
+
+        let value = Decimal::try_from(2.5).unwrap();
+        assert_eq!(value.round_dp(0, RoundingMode::HalfUp), 3.into());
+        assert_eq!(value.round_dp(0, RoundingMode::HalfEven), 2.into());
+        assert_eq!(
+            (-value).round_dp(0, RoundingMode::HalfUp),
+            Decimal::try_from(-3.0).unwrap()
+        );
+        assert_eq!(
+            (-value).round_dp(0, RoundingMode::Ceiling),
+            Decimal::try_from(-2.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decimal_precision() {
+        type Satoshi = FixedDecimal<8>;
+
+        let size = Satoshi::from_str("0.00000001").unwrap();
+        assert_eq!(size.to_string(), "0.00000001");
+        assert_eq!((size * Satoshi::from(3u64)).to_string(), "0.00000003");
+        assert_eq!(size.rescale::<6>(RoundingMode::Down), Ok(Decimal::ZERO));
+        assert_eq!(
+            size.rescale::<6>(RoundingMode::Up),
+            Ok(Decimal::try_from(0.000001).unwrap())
+        );
+        assert_eq!(
+            Decimal::try_from(1.5)
+                .unwrap()
+                .rescale::<8>(RoundingMode::Down),
+            Ok(Satoshi::try_from(1.5).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_decimal_parsing() {
+        let parse = |s: &str| Decimal::from_str(s).unwrap().to_string();
+        assert_eq!(parse("1e-5"), "0.000010");
+        assert_eq!(parse("1.5E+3"), "1500.000000");
+        assert_eq!(parse("-2.5e2"), "-250.000000");
+        assert_eq!(parse("+.5"), "0.500000");
+        assert_eq!(parse("12."), "12.000000");
+        assert_eq!(parse("1234567e-13"), "0.000000");
+        assert_eq!(parse("5e-7"), "0.000000");
+        assert_eq!(parse("1.5e-6"), "0.000002");
+
+        // Zero is canonical whatever the sign
+        assert_eq!(Decimal::from_str("-0").unwrap(), Decimal::ZERO);
+        assert_eq!(parse("-0.0000001"), "0.000000");
+        assert!(!Decimal::from_str("-0e10").unwrap().is_negative());
+
+        assert_eq!(
+            Decimal::parse_with_rounding("0.0000005", RoundingMode::HalfUp),
+            Ok(Decimal::from_str("0.000001").unwrap())
+        );
+        assert_eq!(
+            Decimal::parse_with_rounding("-0.0000001", RoundingMode::Floor),
+            Ok(Decimal::from_str("-0.000001").unwrap())
+        );
+        assert_eq!(
+            Decimal::parse_with_rounding("1.2345679", RoundingMode::Down),
+            Ok(Decimal::from_str("1.234567").unwrap())
+        );
+
+        for invalid in [
+            "", "-", ".", "e5", "1e", "1.2.3", "1e5.5", "abc", "1_000", "--1",
+        ] {
+            assert!(
+                matches!(Decimal::from_str(invalid), Err(DecimalError::Invalid(_))),
+                "{:?} should be invalid",
+                invalid
+            );
+        }
+        assert_eq!(Decimal::from_str("1e30"), Err(DecimalError::Overflow));
+        assert_eq!(Decimal::from_str("1e-1000000").unwrap(), Decimal::ZERO);
+
+        assert_eq!(Decimal::try_from(f64::NAN), Err(DecimalError::NotFinite));
+        assert_eq!(
+            Decimal::try_from(f64::NEG_INFINITY),
+            Err(DecimalError::NotFinite)
+        );
+        assert_eq!(Decimal::try_from(1e-7).unwrap(), Decimal::ZERO);
+        assert_eq!(Decimal::try_from(1e300), Err(DecimalError::Overflow));
+    }
+
+    #[test]
+    fn test_decimal_serde() {
+        let value = Decimal::try_from(-123.456789).unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"-123.456789\"");
+        assert_eq!(serde_json::from_str::<Decimal>(&json).unwrap(), value);
+        assert!(serde_json::from_str::<Decimal>("1.5").is_err());
+
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            #[serde(with = "decimal_lenient")]
+            value: Decimal,
+        }
+        let parse = |json: &str| serde_json::from_str::<Config>(json).map(|c| c.value);
+        assert_eq!(
+            parse(r#"{"value": 0.1}"#).unwrap(),
+            Decimal::try_from(0.1).unwrap()
+        );
+        assert_eq!(
+            parse(r#"{"value": -2}"#).unwrap(),
+            Decimal::try_from(-2.0).unwrap()
+        );
+        assert_eq!(
+            parse(r#"{"value": "2.5"}"#).unwrap(),
+            Decimal::try_from(2.5).unwrap()
+        );
+        assert!(parse(r#"{"value": true}"#).is_err());
+    }
+
+    fn reference_mode(mode: RoundingMode) -> bigdecimal::RoundingMode {
+        match mode {
+            RoundingMode::Down => bigdecimal::RoundingMode::Down,
+            RoundingMode::Up => bigdecimal::RoundingMode::Up,
+            RoundingMode::Floor => bigdecimal::RoundingMode::Floor,
+            RoundingMode::Ceiling => bigdecimal::RoundingMode::Ceiling,
+            RoundingMode::HalfUp => bigdecimal::RoundingMode::HalfUp,
+            RoundingMode::HalfEven => bigdecimal::RoundingMode::HalfEven,
+        }
+    }
+
+    fn rounding_mode() -> impl Strategy<Value = RoundingMode> {
+        prop_oneof![
+            Just(RoundingMode::Down),
+            Just(RoundingMode::Up),
+            Just(RoundingMode::Floor),
+            Just(RoundingMode::Ceiling),
+            Just(RoundingMode::HalfUp),
+            Just(RoundingMode::HalfEven),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_matches_reference(
+            value in "[+-]?[0-9]{1,24}(\\.[0-9]{0,24})?([eE][+-]?[0-9]{1,2})?",
+            mode in rounding_mode(),
+        ) {
+            let reference = BigDecimal::from_str(&value)
+                .unwrap()
+                .with_scale_round(Decimal::DECIMAL as i64, reference_mode(mode));
+            let (raw, _) = reference.into_bigint_and_exponent();
+
+            match (Decimal::parse_with_rounding(&value, mode), raw.to_i128()) {
+                (Ok(parsed), Some(raw)) => prop_assert_eq!(parsed.raw, raw),
+                (Err(DecimalError::Overflow), raw) => {
+                    prop_assert!(raw.is_none_or(|raw| raw.abs() > Decimal::MAX.raw))
+                }
+                (result, raw) => prop_assert!(false, "{:?} != {:?}", result, raw),
+            }
+        }
+
+        #[test]
+        fn prop_display_round_trip(raw in Decimal::MIN.raw..=Decimal::MAX.raw) {
+            let value = Decimal { raw };
+            prop_assert_eq!(Decimal::from_str(&value.to_string()), Ok(value));
+        }
+
+        #[test]
+        fn prop_f64_matches_reference(value in -1e12f64..1e12f64) {
+            let reference = BigDecimal::from_str(&value.to_string())
+                .unwrap()
+                .with_scale_round(Decimal::DECIMAL as i64, bigdecimal::RoundingMode::HalfEven);
+            prop_assert_eq!(Decimal::try_from(value).unwrap().to_string(), reference.to_string());
+        }
+    }
 }
@@ -1,21 +1,30 @@
-use crate::models::{Decimal, Order, Side, Trade};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::models::{Candle, CandleInterval, Decimal, Order, Side, Trade};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InternalEvent {
     OrderBookUpdate(OrderBookUpdate),
     TradeUpdate(Vec<Trade>),
+    CandleUpdate(CandleUpdate),
+    OrderPendingNew(Order),
     OrderPlaced(Order),
     OrderFilled(Fill),
+    OrderAmended(Order),
+    OrderPendingCancel(Order),
     OrderCancelled(Order),
+    OrderRejected(OrderRejection),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OrderBookEventKind {
     Snapshot,
     Delta,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookUpdate {
     pub symbol: String,
     pub kind: OrderBookEventKind,
@@ -24,7 +33,17 @@ pub struct OrderBookUpdate {
     pub asks: Vec<(Decimal, Decimal)>,
 }
 
-#[derive(Debug, Clone)]
+/// A bar from the venue, e.g. a Bybit kline, sent on every update of the bar until it
+/// is closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleUpdate {
+    pub symbol: String,
+    pub interval: CandleInterval,
+    pub candle: Candle,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fill {
     pub oid: usize,
     pub side: Side,
@@ -33,3 +52,84 @@ pub struct Fill {
     pub is_maker: bool,
     pub timestamp: u64,
 }
+
+/// Emitted when a place or cancel request could not be carried out.
+/// `oid` is only known if the request referred to an existing order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRejection {
+    pub cloid: Option<String>,
+    pub oid: Option<usize>,
+    pub symbol: String,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deserializing then serializing again must give back the same JSON.
+    fn assert_round_trip(event: &InternalEvent) {
+        let json = serde_json::to_string(event).unwrap();
+        let decoded: InternalEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_event_serde_round_trip() {
+        let order = Order::new(1, "BTCUSDT".to_string(), Side::Bid, 100.into(), 2.into())
+            .with_cloid("hayate-1");
+        let events = vec![
+            InternalEvent::OrderBookUpdate(OrderBookUpdate {
+                symbol: "BTCUSDT".to_string(),
+                kind: OrderBookEventKind::Snapshot,
+                updated_at: 1,
+                bids: vec![(Decimal::from_str_unchecked("99.5"), 1.into())],
+                asks: vec![(
+                    Decimal::from_str_unchecked("100.5"),
+                    Decimal::from_str_unchecked("0.000001"),
+                )],
+            }),
+            InternalEvent::TradeUpdate(vec![Trade {
+                symbol: "BTCUSDT".to_string(),
+                side: Side::Ask,
+                price: Decimal::from_str_unchecked("100.25"),
+                size: 1.into(),
+                timestamp: 2,
+            }]),
+            InternalEvent::CandleUpdate(CandleUpdate {
+                symbol: "BTCUSDT".to_string(),
+                interval: CandleInterval::Time(60_000),
+                candle: Candle::new(60_000, 100.into(), 1.into(), 60_001),
+                closed: false,
+            }),
+            InternalEvent::OrderPlaced(order.clone()),
+            InternalEvent::OrderFilled(Fill {
+                oid: 1,
+                side: Side::Bid,
+                price: 100.into(),
+                size: Decimal::from_str_unchecked("0.5"),
+                is_maker: true,
+                timestamp: 3,
+            }),
+            InternalEvent::OrderCancelled(order),
+            InternalEvent::OrderRejected(OrderRejection {
+                cloid: Some("hayate-2".to_string()),
+                oid: None,
+                symbol: "BTCUSDT".to_string(),
+                reason: "Order size 0.000000 must be positive".to_string(),
+                timestamp: 4,
+            }),
+        ];
+
+        for event in &events {
+            assert_round_trip(event);
+        }
+
+        let json = serde_json::to_string(&events[3]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"order_placed":{"oid":1,"cloid":"hayate-1","symbol":"BTCUSDT","side":"bid","price":"100.000000","size":"2.000000","filled_size":"0.000000","status":"new"}}"#
+        );
+    }
+}
@@ -1,16 +1,122 @@
+mod bollinger;
+mod book_feature;
+mod ema;
+mod macd;
 mod natr;
+mod realized_volatility;
 mod rsi;
+mod sma;
+mod vwap;
+mod z_score;
 
+pub use bollinger::*;
+pub use book_feature::*;
+pub use ema::*;
+pub use macd::*;
 pub use natr::*;
+pub use realized_volatility::*;
 pub use rsi::*;
+pub use sma::*;
+pub use vwap::*;
+pub use z_score::*;
 
-use std::fmt::Debug;
+use std::{any::Any, fmt::Debug};
 
-use crate::models::Decimal;
+use crate::models::{Candle, Decimal, OrderBook, Trade};
 
-pub trait Indicator: Debug + Send + Sync {
+/// Progress of an indicator towards its first value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmUpStatus {
+    /// `samples` of the `required` samples have been seen
+    WarmingUp {
+        samples: usize,
+        required: usize,
+    },
+    Ready,
+}
+
+impl WarmUpStatus {
+    /// Status after `samples` of `required` samples.
+    pub fn new(samples: usize, required: usize) -> Self {
+        if samples >= required {
+            WarmUpStatus::Ready
+        } else {
+            WarmUpStatus::WarmingUp { samples, required }
+        }
+    }
+}
+
+/// A streaming indicator. Indicators are named after their parameters (e.g. `rsi_14_1s`)
+/// so that several instances of the same kind can be registered together.
+pub trait Indicator: Any + Debug + Send + Sync {
+    /// Instance name, unique for the kind and parameters of the indicator
     fn name(&self) -> &str;
     fn value(&self) -> Option<Decimal>;
+    /// Names of the outputs of the indicator, the first being its value.
+    fn outputs(&self) -> &'static [&'static str] {
+        &["value"]
+    }
+    /// Current value of one of the `outputs`.
+    fn output(&self, output: &str) -> Option<Decimal> {
+        match output {
+            "value" => self.value(),
+            _ => None,
+        }
+    }
+    fn warm_up_status(&self) -> WarmUpStatus;
+    fn is_ready(&self) -> bool {
+        self.warm_up_status() == WarmUpStatus::Ready
+    }
     fn update(&mut self, price: Decimal, timestamp: u64);
+    /// Updates with a trade, by default with its price only.
+    fn update_trade(&mut self, trade: &Trade) {
+        self.update(trade.price, trade.timestamp);
+    }
+    /// Updates with a closed bar, by default with its close price.
+    fn update_candle(&mut self, candle: &Candle) {
+        self.update(candle.close, candle.close_time);
+    }
+    /// Updates with the order book after an update, by default with its mid price.
+    fn update_book(&mut self, book: &OrderBook, timestamp: u64) {
+        if let Some(mid_price) = book.mid_price() {
+            self.update(mid_price, timestamp);
+        }
+    }
     fn reset(&mut self);
 }
+
+impl dyn Indicator {
+    /// Downcasts to a concrete indicator type.
+    pub fn downcast_ref<T: Indicator>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+}
+
+/// Formats a decimal parameter for an indicator name, without trailing zeros.
+fn format_param(value: Decimal) -> String {
+    let value = value.to_string();
+    value
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Arithmetic mean, `None` if there are no values.
+fn mean<'a>(values: impl ExactSizeIterator<Item = &'a Decimal>) -> Option<Decimal> {
+    let len = values.len() as u64;
+    if len == 0 {
+        return None;
+    }
+    let sum: Decimal = values.copied().sum();
+    Some(sum / Decimal::from(len))
+}
+
+/// Population standard deviation around `mean`.
+fn std_dev<'a>(values: impl ExactSizeIterator<Item = &'a Decimal>, mean: Decimal) -> Decimal {
+    let squares: Vec<Decimal> = values
+        .map(|value| (*value - mean) * (*value - mean))
+        .collect();
+    self::mean(squares.iter())
+        .and_then(|variance| variance.sqrt())
+        .unwrap_or(Decimal::ZERO)
+}
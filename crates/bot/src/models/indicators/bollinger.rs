@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BollingerBands {
+    pub upper: Decimal,
+    pub middle: Decimal,
+    pub lower: Decimal,
+}
+
+/// Bollinger Bands over the last `period` closes of time bars of `update_interval` ms:
+/// the simple average, plus and minus `multiplier` population standard deviations.
+/// The indicator value is the middle band.
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    name: String,
+    period: usize,
+    multiplier: Decimal,
+    aggregator: CandleAggregator,
+    closes: VecDeque<Decimal>,
+    bands: Option<BollingerBands>,
+}
+
+impl Bollinger {
+    pub const NAME: &'static str = "bollinger";
+
+    pub fn new(period: usize, multiplier: Decimal, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}_{}",
+                Self::NAME,
+                period,
+                super::format_param(multiplier),
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            multiplier,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            closes: VecDeque::with_capacity(period),
+            bands: None,
+        }
+    }
+
+    pub fn bands(&self) -> Option<BollingerBands> {
+        self.bands
+    }
+
+    pub fn upper(&self) -> Option<Decimal> {
+        self.bands.map(|bands| bands.upper)
+    }
+
+    pub fn lower(&self) -> Option<Decimal> {
+        self.bands.map(|bands| bands.lower)
+    }
+
+    fn add_close(&mut self, close: Decimal) {
+        if self.closes.len() == self.period {
+            self.closes.pop_front();
+        }
+        self.closes.push_back(close);
+
+        if self.closes.len() < self.period {
+            self.bands = None;
+            return;
+        }
+
+        let Some(middle) = super::mean(self.closes.iter()) else {
+            return;
+        };
+        let width = self.multiplier * super::std_dev(self.closes.iter(), middle);
+        self.bands = Some(BollingerBands {
+            upper: middle + width,
+            middle,
+            lower: middle - width,
+        });
+    }
+}
+
+impl Indicator for Bollinger {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.bands.map(|bands| bands.middle)
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["middle", "upper", "lower"]
+    }
+
+    fn output(&self, output: &str) -> Option<Decimal> {
+        let bands = self.bands?;
+        match output {
+            "middle" => Some(bands.middle),
+            "upper" => Some(bands.upper),
+            "lower" => Some(bands.lower),
+            _ => None,
+        }
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.closes.len(), self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_close(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_close(candle.close);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.closes.clear();
+        self.bands = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bollinger() {
+        let mut bollinger = Bollinger::new(8, 2.into(), 1000);
+        // Mean 5, population standard deviation 2
+        for close in [2, 4, 4, 4, 5, 5, 7, 9] {
+            assert!(!bollinger.is_ready());
+            bollinger.update_candle(&Candle::new(0, close.into(), Decimal::ONE, 0));
+        }
+
+        assert_eq!(
+            bollinger.bands(),
+            Some(BollingerBands {
+                upper: 9.into(),
+                middle: 5.into(),
+                lower: 1.into(),
+            })
+        );
+        assert_eq!(bollinger.value(), Some(5.into()));
+        assert_eq!(bollinger.name(), "bollinger_8_2_1s");
+        let outputs: Vec<_> = bollinger
+            .outputs()
+            .iter()
+            .map(|output| bollinger.output(output))
+            .collect();
+        assert_eq!(
+            outputs,
+            vec![Some(5.into()), Some(9.into()), Some(1.into())]
+        );
+
+        // Window is now [4, 4, 4, 5, 5, 7, 9, 2]: unchanged
+        bollinger.update_candle(&Candle::new(0, 2.into(), Decimal::ONE, 0));
+        assert_eq!(bollinger.upper(), Some(9.into()));
+
+        // Window is now [4, 4, 5, 5, 7, 9, 2, 4]: unchanged
+        bollinger.update_candle(&Candle::new(0, 4.into(), Decimal::ONE, 0));
+        assert_eq!(bollinger.lower(), Some(1.into()));
+    }
+}
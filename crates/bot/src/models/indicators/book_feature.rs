@@ -0,0 +1,169 @@
+use std::fmt;
+
+use crate::models::{Decimal, Ema, Indicator, OrderBook, Side, WarmUpStatus};
+
+/// An order-book microstructure measure, see the matching [`OrderBook`] methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookFeatureKind {
+    /// Size imbalance over the best `levels` levels, from -1 to 1
+    Imbalance {
+        levels: usize,
+    },
+    Microprice,
+    WeightedMid {
+        levels: usize,
+    },
+    /// Size of `side` within `bps` basis points of the mid price
+    Depth {
+        side: Side,
+        bps: Decimal,
+    },
+    SpreadBps,
+    /// Size per basis point over the best `levels` levels of `side`
+    Slope {
+        side: Side,
+        levels: usize,
+    },
+}
+
+impl BookFeatureKind {
+    pub fn compute(&self, book: &OrderBook) -> Option<Decimal> {
+        match *self {
+            BookFeatureKind::Imbalance { levels } => book.imbalance(levels),
+            BookFeatureKind::Microprice => book.microprice(),
+            BookFeatureKind::WeightedMid { levels } => book.weighted_mid(levels),
+            BookFeatureKind::Depth { side, bps } => book.depth_within_bps(side, bps),
+            BookFeatureKind::SpreadBps => book.spread_bps(),
+            BookFeatureKind::Slope { side, levels } => book.slope(side, levels),
+        }
+    }
+}
+
+impl fmt::Display for BookFeatureKind {
+    /// Name of the feature with its parameters, e.g. `imbalance_5` or `bid_depth_10bps`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookFeatureKind::Imbalance { levels } => write!(f, "imbalance_{}", levels),
+            BookFeatureKind::Microprice => write!(f, "microprice"),
+            BookFeatureKind::WeightedMid { levels } => write!(f, "weighted_mid_{}", levels),
+            BookFeatureKind::Depth { side, bps } => {
+                write!(f, "{}_depth_{}bps", side, super::format_param(*bps))
+            }
+            BookFeatureKind::SpreadBps => write!(f, "spread_bps"),
+            BookFeatureKind::Slope { side, levels } => write!(f, "{}_slope_{}", side, levels),
+        }
+    }
+}
+
+/// A [`BookFeatureKind`] recomputed on every order-book update, optionally smoothed by an
+/// EMA over the updates. Book features ignore prices and trades.
+#[derive(Debug, Clone)]
+pub struct BookFeature {
+    name: String,
+    kind: BookFeatureKind,
+    ema: Option<Ema>,
+    samples: usize,
+    current_value: Option<Decimal>,
+}
+
+impl BookFeature {
+    pub fn new(kind: BookFeatureKind) -> Self {
+        Self {
+            name: kind.to_string(),
+            kind,
+            ema: None,
+            samples: 0,
+            current_value: None,
+        }
+    }
+
+    /// Smooths the feature with an EMA over `period` book updates.
+    pub fn with_ema(mut self, period: usize) -> Self {
+        self.name = format!("{}_ema_{}", self.kind, period);
+        self.ema = Some(Ema::new(period, 0));
+        self
+    }
+
+    pub fn kind(&self) -> BookFeatureKind {
+        self.kind
+    }
+}
+
+impl Indicator for BookFeature {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        match &self.ema {
+            Some(ema) => ema.warm_up_status(),
+            None => WarmUpStatus::new(self.samples, 1),
+        }
+    }
+
+    fn update(&mut self, _price: Decimal, _timestamp: u64) {}
+
+    fn update_book(&mut self, book: &OrderBook, _timestamp: u64) {
+        // One-sided books keep the last value
+        let Some(value) = self.kind.compute(book) else {
+            return;
+        };
+        self.samples += 1;
+        self.current_value = match &mut self.ema {
+            Some(ema) => ema.add_value(value),
+            None => Some(value),
+        };
+    }
+
+    fn reset(&mut self) {
+        if let Some(ema) = &mut self.ema {
+            ema.reset();
+        }
+        self.samples = 0;
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_feature() {
+        let mut book = OrderBook::new(5);
+        book.insert(Side::Bid, 100.into(), 1.into()).unwrap();
+        book.insert(Side::Ask, 101.into(), 3.into()).unwrap();
+
+        let mut imbalance = BookFeature::new(BookFeatureKind::Imbalance { levels: 1 });
+        let mut smoothed = BookFeature::new(BookFeatureKind::Imbalance { levels: 1 }).with_ema(2);
+        let depth = BookFeature::new(BookFeatureKind::Depth {
+            side: Side::Ask,
+            bps: Decimal::from_str_unchecked("12.5"),
+        });
+        assert_eq!(imbalance.name(), "imbalance_1");
+        assert_eq!(smoothed.name(), "imbalance_1_ema_2");
+        assert_eq!(depth.name(), "ask_depth_12.5bps");
+
+        // Prices are ignored
+        imbalance.update(100.into(), 0);
+        assert!(!imbalance.is_ready());
+
+        for feature in [&mut imbalance, &mut smoothed] {
+            feature.update_book(&book, 0);
+        }
+        assert_eq!(imbalance.value(), Some(Decimal::from_str_unchecked("-0.5")));
+        assert!(!smoothed.is_ready());
+
+        book.insert(Side::Bid, 100.into(), 3.into()).unwrap();
+        for feature in [&mut imbalance, &mut smoothed] {
+            feature.update_book(&book, 1);
+        }
+        assert_eq!(imbalance.value(), Some(Decimal::ZERO));
+        // Seeded with the average of -0.5 and 0
+        assert_eq!(smoothed.value(), Some(Decimal::from_str_unchecked("-0.25")));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
+
+/// Exponential moving average of the closes of time bars of `update_interval` ms, with a
+/// smoothing factor of `2 / (period + 1)` and seeded with the simple average of the first
+/// `period` closes.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    name: String,
+    period: usize,
+    aggregator: CandleAggregator,
+    samples: usize,
+    /// Sum of the closes until seeded
+    sum: Decimal,
+    current_value: Option<Decimal>,
+}
+
+impl Ema {
+    pub const NAME: &'static str = "ema";
+
+    pub fn new(period: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}",
+                Self::NAME,
+                period,
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            samples: 0,
+            sum: Decimal::ZERO,
+            current_value: None,
+        }
+    }
+
+    /// Adds a sample directly rather than a bar close, returning the new average.
+    pub fn add_value(&mut self, value: Decimal) -> Option<Decimal> {
+        let period = Decimal::from(self.period as u64);
+        self.current_value = match self.current_value {
+            // (2 * value + (period - 1) * ema) / (period + 1), in a single division
+            Some(ema) => Some(
+                (Decimal::from(2) * value + (period - Decimal::ONE) * ema)
+                    / (period + Decimal::ONE),
+            ),
+            None => {
+                self.samples += 1;
+                self.sum += value;
+                (self.samples == self.period).then(|| self.sum / period)
+            }
+        };
+        self.current_value
+    }
+}
+
+impl Indicator for Ema {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.samples, self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_value(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_value(candle.close);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.samples = 0;
+        self.sum = Decimal::ZERO;
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ema() {
+        // EMA(10) example from the StockCharts ChartSchool
+        let closes = [
+            "22.27", "22.19", "22.08", "22.17", "22.18", "22.13", "22.23", "22.43", "22.24",
+            "22.29", "22.15", "22.39", "22.38", "22.61", "23.36",
+        ];
+
+        let mut ema = Ema::new(10, 1000);
+        let values: Vec<_> = closes
+            .iter()
+            .map(|close| ema.add_value(Decimal::from_str_unchecked(close)))
+            .collect();
+
+        assert!(values[..9].iter().all(Option::is_none));
+        let expected = [
+            "22.221",
+            "22.20809",
+            "22.241164",
+            "22.266406",
+            "22.328877",
+            "22.516353",
+        ];
+        for (value, expected) in values[9..].iter().zip(expected) {
+            assert_eq!(*value, Some(Decimal::from_str_unchecked(expected)));
+        }
+        assert!(ema.is_ready());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::models::{
+    Candle, CandleAggregator, CandleInterval, Decimal, Ema, Indicator, WarmUpStatus,
+};
+
+/// Moving average convergence divergence of the closes of time bars of `update_interval`
+/// ms. The indicator value is the MACD line, `EMA(fast) - EMA(slow)`, and the signal line
+/// is its `EMA(signal)`.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    name: String,
+    aggregator: CandleAggregator,
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+    required_samples: usize,
+    samples: usize,
+    line: Option<Decimal>,
+}
+
+impl Macd {
+    pub const NAME: &'static str = "macd";
+
+    pub fn new(fast: usize, slow: usize, signal: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}_{}_{}",
+                Self::NAME,
+                fast,
+                slow,
+                signal,
+                CandleInterval::Time(update_interval)
+            ),
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            fast: Ema::new(fast, update_interval),
+            slow: Ema::new(slow, update_interval),
+            signal: Ema::new(signal, update_interval),
+            required_samples: fast.max(slow) + signal - 1,
+            samples: 0,
+            line: None,
+        }
+    }
+
+    pub fn signal(&self) -> Option<Decimal> {
+        self.signal.value()
+    }
+
+    /// MACD line minus signal line.
+    pub fn histogram(&self) -> Option<Decimal> {
+        Some(self.line? - self.signal()?)
+    }
+
+    fn add_close(&mut self, close: Decimal) {
+        self.samples = (self.samples + 1).min(self.required_samples);
+        let fast = self.fast.add_value(close);
+        let slow = self.slow.add_value(close);
+
+        if let (Some(fast), Some(slow)) = (fast, slow) {
+            let line = fast - slow;
+            self.line = Some(line);
+            self.signal.add_value(line);
+        }
+    }
+}
+
+impl Indicator for Macd {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.line
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["line", "signal", "histogram"]
+    }
+
+    fn output(&self, output: &str) -> Option<Decimal> {
+        match output {
+            "line" => self.line,
+            "signal" => self.signal(),
+            "histogram" => self.histogram(),
+            _ => None,
+        }
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.samples, self.required_samples)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_close(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_close(candle.close);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+        self.samples = 0;
+        self.line = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macd() {
+        let mut macd = Macd::new(2, 3, 2, 1000);
+        let mut outputs = Vec::new();
+        for (i, close) in [10, 12, 11, 14, 15, 13].into_iter().enumerate() {
+            let open_time = i as u64 * 1000;
+            macd.update_candle(&Candle::new(
+                open_time,
+                close.into(),
+                Decimal::ONE,
+                open_time,
+            ));
+            outputs.push((macd.value(), macd.signal(), macd.histogram()));
+        }
+
+        // EMA(2): 11, 11, 13, 14.333333, 13.444444
+        // EMA(3): 11, 12.5, 13.75, 13.375
+        let decimal = Decimal::from_str_unchecked;
+        assert_eq!(outputs[1], (None, None, None));
+        assert_eq!(outputs[2], (Some(Decimal::ZERO), None, None));
+        assert_eq!(
+            outputs[3],
+            (
+                Some(decimal("0.5")),
+                Some(decimal("0.25")),
+                Some(decimal("0.25"))
+            )
+        );
+        assert_eq!(
+            outputs[5],
+            (
+                Some(decimal("0.069444")),
+                Some(decimal("0.203703")),
+                Some(decimal("-0.134259"))
+            )
+        );
+        assert!(macd.is_ready());
+        assert_eq!(macd.name(), "macd_2_3_2_1s");
+        assert_eq!(macd.output("histogram"), Some(decimal("-0.134259")));
+    }
+}
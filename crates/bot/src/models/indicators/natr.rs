@@ -1,19 +1,36 @@
 use std::collections::VecDeque;
 
-use crate::models::{Decimal, Indicator};
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
 
+/// How the true ranges are averaged into the ATR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AtrSmoothing {
+    /// Simple moving average of the last `period` true ranges
+    #[default]
+    Simple,
+    /// Wilder's smoothing `ATR = (ATR * (period - 1) + TR) / period`, seeded with the
+    /// simple average of the first `period` true ranges
+    Wilder,
+}
+
+/// Normalized average true range, the ATR as a percentage of the close price.
+///
+/// Raw price updates are aggregated into time bars of `update_interval` ms, intervals
+/// without trades counting as flat bars. Closed bars can be fed directly with
+/// `update_candle` instead, e.g. from a `CandleState`.
 #[derive(Debug, Clone)]
 pub struct Natr {
+    name: String,
     period: usize,
+    smoothing: AtrSmoothing,
+    aggregator: CandleAggregator,
+    prev_close: Option<Decimal>,
+    /// Number of closed bars, up to `period`
+    samples: usize,
+    /// True ranges of the current window, only kept until seeded with Wilder smoothing
     true_ranges: VecDeque<Decimal>,
+    atr: Option<Decimal>,
     current_value: Option<Decimal>,
-    last_closed_at: u64,
-    update_interval: u64,
-    // TODO: move this out to a Candle struct
-    high: Decimal,
-    low: Decimal,
-    open: Decimal,
-    close: Decimal,
 }
 
 impl Natr {
@@ -21,83 +38,108 @@ impl Natr {
 
     pub fn new(period: usize, update_interval: u64) -> Self {
         Self {
+            name: Self::instance_name(
+                AtrSmoothing::default(),
+                period,
+                CandleInterval::Time(update_interval),
+            ),
             period,
+            smoothing: AtrSmoothing::default(),
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            prev_close: None,
+            samples: 0,
             true_ranges: VecDeque::new(),
+            atr: None,
             current_value: None,
-            last_closed_at: 0,
-            update_interval,
-            high: Decimal::ZERO,
-            low: Decimal::ZERO,
-            open: Decimal::ZERO,
-            close: Decimal::ZERO,
         }
     }
 
-    fn should_make_new_candle(&self, timestamp: u64) -> bool {
-        timestamp - self.last_closed_at >= self.update_interval
+    pub fn with_smoothing(mut self, smoothing: AtrSmoothing) -> Self {
+        self.smoothing = smoothing;
+        self.name = Self::instance_name(smoothing, self.period, self.aggregator.interval());
+        self
     }
 
-    fn update_true_range(&mut self) {
-        let hl = self.high - self.low;
-        let hc = (self.high - self.open).abs();
-        let lc = (self.low - self.open).abs();
-        let tr = hl.max(hc).max(lc);
-
-        if self.true_ranges.len() == self.period {
-            self.true_ranges.pop_front();
+    /// e.g. `natr_14_1s`, or `natr_wilder_14_1s` with Wilder smoothing
+    fn instance_name(smoothing: AtrSmoothing, period: usize, interval: CandleInterval) -> String {
+        match smoothing {
+            AtrSmoothing::Simple => format!("{}_{}_{}", Self::NAME, period, interval),
+            AtrSmoothing::Wilder => format!("{}_wilder_{}_{}", Self::NAME, period, interval),
         }
-        self.true_ranges.push_back(tr);
+    }
+
+    /// The average true range in price units.
+    pub fn atr(&self) -> Option<Decimal> {
+        self.atr
+    }
+
+    fn add_candle(&mut self, candle: &Candle) {
+        let tr = candle.true_range(self.prev_close);
+        self.prev_close = Some(candle.close);
+        self.samples = (self.samples + 1).min(self.period);
+        let period = Decimal::from(self.period as u64);
+
+        self.atr = match (self.smoothing, self.atr) {
+            (AtrSmoothing::Wilder, Some(atr)) => {
+                Some((atr * (period - Decimal::ONE) + tr) / period)
+            }
+            _ => {
+                if self.true_ranges.len() == self.period {
+                    self.true_ranges.pop_front();
+                }
+                self.true_ranges.push_back(tr);
+
+                if self.true_ranges.len() < self.period {
+                    None
+                } else {
+                    let tr_sum: Decimal = self.true_ranges.iter().cloned().sum();
+                    if self.smoothing == AtrSmoothing::Wilder {
+                        self.true_ranges.clear();
+                    }
+                    Some(tr_sum / period)
+                }
+            }
+        };
+
+        self.current_value = match self.atr {
+            Some(atr) if candle.close.is_positive() => {
+                Some(atr * Decimal::from(100) / candle.close)
+            }
+            _ => None,
+        };
     }
 }
 
 impl Indicator for Natr {
     fn name(&self) -> &str {
-        Self::NAME
+        &self.name
     }
 
     fn value(&self) -> Option<Decimal> {
         self.current_value
     }
 
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.samples, self.period)
+    }
+
     fn update(&mut self, price: Decimal, timestamp: u64) {
-        if self.last_closed_at == 0 {
-            self.open = price;
-            self.high = price;
-            self.low = price;
-            self.close = price;
-            self.last_closed_at = timestamp;
-
-            return;
-        } else {
-            self.high = self.high.max(price);
-            self.low = self.low.min(price);
-            self.close = price;
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_candle(&candle);
         }
+    }
 
-        if self.should_make_new_candle(timestamp) {
-            self.update_true_range();
-
-            if self.true_ranges.len() < self.period {
-                self.current_value = None;
-            } else {
-                let tr_sum: Decimal = self.true_ranges.iter().cloned().sum();
-
-                let atr = tr_sum / Decimal::from(self.period as u64);
-                self.current_value = Some((atr / self.close) * Decimal::from(100.0));
-            }
-
-            // Reset for the next candle
-            self.open = self.close;
-            self.high = price;
-            self.low = price;
-            self.last_closed_at = timestamp;
-        }
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_candle(candle);
     }
 
     fn reset(&mut self) {
+        self.aggregator.reset();
+        self.prev_close = None;
+        self.samples = 0;
         self.true_ranges.clear();
+        self.atr = None;
         self.current_value = None;
-        self.last_closed_at = 0;
     }
 }
 
@@ -105,40 +147,117 @@ impl Indicator for Natr {
 mod tests {
     use super::*;
 
+    fn candle(open_time: u64, high: u64, low: u64, close: u64) -> Candle {
+        Candle {
+            open_time,
+            close_time: open_time,
+            open: close.into(),
+            high: high.into(),
+            low: low.into(),
+            close: close.into(),
+            volume: Decimal::ONE,
+            trade_count: 1,
+        }
+    }
+
     #[test]
     fn test_natr() {
         let mut natr = Natr::new(4, 1000);
         let prices = vec![
-            Decimal::from(100.0), // Initial price
-            // Bar 1: Open 100, High 102, Low 98, Close 100
-            Decimal::from(100.0),
-            Decimal::from(102.0),
-            Decimal::from(98.0),
-            Decimal::from(100.0),
-            // Bar 2: Open 100, High 105, Low 99, Close 102
-            Decimal::from(102.0),
-            Decimal::from(105.0),
-            Decimal::from(99.0),
-            Decimal::from(102.0),
-            // Bar 3: Open 102, High 107, Low 100, Close 105
-            Decimal::from(105.0),
-            Decimal::from(107.0),
-            Decimal::from(100.0),
-            Decimal::from(105.0),
-            // Bar 4: Open 105, High 106, Low 101, Close 103
-            Decimal::from(103.0),
-            Decimal::from(106.0),
-            Decimal::from(101.0),
-            Decimal::from(103.0),
+            // Bar 1: Open 100, High 102, Low 98, Close 98
+            Decimal::from(100),
+            Decimal::from(100),
+            Decimal::from(102),
+            Decimal::from(98),
+            // Bar 2: Open 100, High 105, Low 99, Close 99
+            Decimal::from(100),
+            Decimal::from(102),
+            Decimal::from(105),
+            Decimal::from(99),
+            // Bar 3: Open 102, High 107, Low 100, Close 100
+            Decimal::from(102),
+            Decimal::from(105),
+            Decimal::from(107),
+            Decimal::from(100),
+            // Bar 4: Open 105, High 106, Low 101, Close 101
+            Decimal::from(105),
+            Decimal::from(103),
+            Decimal::from(106),
+            Decimal::from(101),
+            // Opens bar 5, closing bar 4
+            Decimal::from(103),
         ];
 
         for (i, price) in prices.iter().enumerate() {
             natr.update(*price, 1 + (i as u64) * 250);
         }
 
-        // Bar 1 TR = 4, Bar 2 TR = 6, Bar 3 TR = 7, Bar 4 TR = 5
-        // ATR = (4 + 6 + 7 + 5) / 4 = 5.5
-        // NATR = (5.5 / 103) * 100 = 5.339800
-        assert_eq!(natr.value(), Some(Decimal::from(5.339800)));
+        // Bar 1 TR = 102 - 98 = 4, Bar 2 TR = 105 - 98 = 7, Bar 3 TR = 107 - 99 = 8,
+        // Bar 4 TR = 106 - 100 = 6
+        // ATR = (4 + 7 + 8 + 6) / 4 = 6.25
+        // NATR = 6.25 * 100 / 101 = 6.188118
+        assert!(natr.is_ready());
+        assert_eq!(natr.atr(), Some(Decimal::from_str_unchecked("6.25")));
+        assert_eq!(natr.value(), Some(Decimal::from_str_unchecked("6.188118")));
+    }
+
+    #[test]
+    fn test_natr_wilder() {
+        let mut natr = Natr::new(3, 1000).with_smoothing(AtrSmoothing::Wilder);
+        let bars = [
+            candle(0, 48, 45, 46), // TR = 3
+            candle(1, 49, 46, 48), // TR = 3
+            candle(2, 50, 44, 49), // TR = 6
+            candle(3, 52, 50, 51), // TR = 52 - 49 = 3
+            candle(4, 51, 45, 45), // TR = 6
+            candle(5, 49, 48, 48), // TR = 49 - 45 = 4
+        ];
+
+        let atrs: Vec<_> = bars
+            .iter()
+            .map(|bar| {
+                natr.update_candle(bar);
+                natr.atr()
+            })
+            .collect();
+
+        // Seeded with (3 + 3 + 6) / 3 = 4, then (4 * 2 + 3) / 3 = 3.666666,
+        // (3.666666 * 2 + 6) / 3 = 4.444444 and (4.444444 * 2 + 4) / 3 = 4.296296
+        assert_eq!(
+            atrs,
+            vec![
+                None,
+                None,
+                Some(4.into()),
+                Some(Decimal::from_str_unchecked("3.666666")),
+                Some(Decimal::from_str_unchecked("4.444444")),
+                Some(Decimal::from_str_unchecked("4.296296")),
+            ]
+        );
+        // 4.296296 * 100 / 48
+        assert_eq!(natr.value(), Some(Decimal::from_str_unchecked("8.950616")));
+    }
+
+    #[test]
+    fn test_natr_gaps() {
+        let mut natr = Natr::new(2, 1000);
+
+        natr.update(100.into(), 0);
+        natr.update(104.into(), 500);
+        assert_eq!(
+            natr.warm_up_status(),
+            WarmUpStatus::WarmingUp {
+                samples: 0,
+                required: 2
+            }
+        );
+        // Nothing traded in the second interval, which is a flat bar with TR = 0
+        natr.update(100.into(), 2000);
+        assert_eq!(natr.atr(), Some(2.into()));
+
+        // The close of the empty bar is the previous close: TR = |100 - 104| = 4
+        natr.update(100.into(), 3000);
+        assert_eq!(natr.atr(), Some(2.into()));
+        assert_eq!(natr.value(), Some(2.into()));
     }
 }
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VolatilityEstimator {
+    /// Sample standard deviation of the log returns between closes
+    #[default]
+    CloseToClose,
+    /// Parkinson's estimator from the high and low of each bar,
+    /// `sqrt(sum(ln(high / low)²) / (4 * n * ln 2))`
+    Parkinson,
+}
+
+impl VolatilityEstimator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VolatilityEstimator::CloseToClose => "close_to_close",
+            VolatilityEstimator::Parkinson => "parkinson",
+        }
+    }
+}
+
+/// Realized volatility per bar (not annualized) over the last `period` time bars of
+/// `update_interval` ms, as a fraction of the price.
+#[derive(Debug, Clone)]
+pub struct RealizedVolatility {
+    name: String,
+    period: usize,
+    estimator: VolatilityEstimator,
+    aggregator: CandleAggregator,
+    prev_close: Option<Decimal>,
+    /// Log returns, or squared log ranges for Parkinson
+    samples: VecDeque<f64>,
+    current_value: Option<Decimal>,
+}
+
+impl RealizedVolatility {
+    pub const NAME: &'static str = "realized_volatility";
+
+    pub fn new(estimator: VolatilityEstimator, period: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}_{}",
+                Self::NAME,
+                estimator.name(),
+                period,
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            estimator,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            prev_close: None,
+            samples: VecDeque::with_capacity(period),
+            current_value: None,
+        }
+    }
+
+    fn add_candle(&mut self, candle: &Candle) {
+        let prev_close = self.prev_close.replace(candle.close);
+        let sample = match self.estimator {
+            VolatilityEstimator::CloseToClose => match prev_close {
+                Some(prev_close) => (candle.close.to_f64() / prev_close.to_f64()).ln(),
+                None => return,
+            },
+            VolatilityEstimator::Parkinson => {
+                (candle.high.to_f64() / candle.low.to_f64()).ln().powi(2)
+            }
+        };
+
+        if self.samples.len() == self.period {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+
+        self.current_value = None;
+        if self.samples.len() < self.period {
+            return;
+        }
+
+        let n = self.samples.len() as f64;
+        let variance = match self.estimator {
+            VolatilityEstimator::CloseToClose => {
+                let mean = self.samples.iter().sum::<f64>() / n;
+                let squares: f64 = self.samples.iter().map(|r| (r - mean).powi(2)).sum();
+                squares / (n - 1.0)
+            }
+            VolatilityEstimator::Parkinson => {
+                self.samples.iter().sum::<f64>() / (4.0 * n * std::f64::consts::LN_2)
+            }
+        };
+        // Non-finite for a single close-to-close return or non-positive prices
+        self.current_value = Decimal::try_from(variance.sqrt()).ok();
+    }
+}
+
+impl Indicator for RealizedVolatility {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.samples.len(), self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_candle(&candle);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_candle(candle);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.prev_close = None;
+        self.samples.clear();
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_to_close_volatility() {
+        let mut volatility = RealizedVolatility::new(VolatilityEstimator::CloseToClose, 2, 1000);
+        for (i, price) in [100, 110, 99, 0].into_iter().enumerate() {
+            volatility.update(price.into(), i as u64 * 1000);
+        }
+
+        // Returns ln(1.1) = 0.095310 and ln(0.9) = -0.105361, sample standard deviation
+        // 0.141896
+        assert_eq!(
+            volatility.value(),
+            Some(Decimal::from_str_unchecked("0.141896"))
+        );
+    }
+
+    #[test]
+    fn test_parkinson_volatility() {
+        let mut volatility = RealizedVolatility::new(VolatilityEstimator::Parkinson, 2, 1000);
+        for open_time in [0, 1000] {
+            let mut candle = Candle::new(open_time, 100.into(), Decimal::ONE, open_time);
+            candle.update(101.into(), Decimal::ONE, open_time);
+            candle.update(99.into(), Decimal::ONE, open_time);
+            volatility.update_candle(&candle);
+        }
+
+        // sqrt(2 * ln(101 / 99)² / (4 * 2 * ln 2)) = 0.012012
+        assert_eq!(
+            volatility.value(),
+            Some(Decimal::from_str_unchecked("0.012012"))
+        );
+    }
+}
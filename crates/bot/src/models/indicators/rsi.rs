@@ -1,14 +1,35 @@
 use std::collections::VecDeque;
 
-use crate::models::{Decimal, Indicator};
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
 
+/// How gains and losses are averaged into the relative strength.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RsiSmoothing {
+    /// Cutler's RSI: simple sums of the gains and losses between the last `period` closes
+    #[default]
+    Cutler,
+    /// Wilder's RSI: `avg = (avg * (period - 1) + change) / period` over `period`
+    /// changes, seeded with the simple average of the first `period` changes
+    Wilder,
+}
+
+/// Relative strength index sampled on the close of time bars of `update_interval` ms.
+/// Closed bars can be fed directly with `update_candle` instead.
 #[derive(Debug, Clone)]
 pub struct Rsi {
+    name: String,
     period: usize,
-    values: VecDeque<Decimal>,
+    smoothing: RsiSmoothing,
+    aggregator: CandleAggregator,
+    prev_close: Option<Decimal>,
+    /// Number of closes, up to the number required for a value
+    samples: usize,
+    /// Price changes in the window, Cutler only
+    changes: VecDeque<Decimal>,
+    /// Sums of the gains and losses, or their averages once Wilder smoothing is seeded
+    gains: Decimal,
+    losses: Decimal,
     current_value: Option<Decimal>,
-    last_updated_at: u64,
-    update_interval: u64,
 }
 
 impl Rsi {
@@ -16,84 +37,135 @@ impl Rsi {
 
     pub fn new(period: usize, update_interval: u64) -> Self {
         Self {
+            name: Self::instance_name(
+                RsiSmoothing::default(),
+                period,
+                CandleInterval::Time(update_interval),
+            ),
             period,
-            values: VecDeque::new(),
+            smoothing: RsiSmoothing::default(),
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            prev_close: None,
+            samples: 0,
+            changes: VecDeque::new(),
+            gains: Decimal::ZERO,
+            losses: Decimal::ZERO,
             current_value: None,
-            last_updated_at: 0,
-            update_interval,
         }
     }
 
-    fn should_update(&self, timestamp: u64) -> bool {
-        timestamp - self.last_updated_at >= self.update_interval
+    pub fn with_smoothing(mut self, smoothing: RsiSmoothing) -> Self {
+        self.smoothing = smoothing;
+        self.name = Self::instance_name(smoothing, self.period, self.aggregator.interval());
+        self
     }
-}
 
-impl Indicator for Rsi {
-    fn name(&self) -> &str {
-        Self::NAME
+    /// e.g. `rsi_14_1s`, or `rsi_wilder_14_1s` with Wilder smoothing
+    fn instance_name(smoothing: RsiSmoothing, period: usize, interval: CandleInterval) -> String {
+        match smoothing {
+            RsiSmoothing::Cutler => format!("{}_{}_{}", Self::NAME, period, interval),
+            RsiSmoothing::Wilder => format!("{}_wilder_{}_{}", Self::NAME, period, interval),
+        }
     }
 
-    fn value(&self) -> Option<Decimal> {
-        self.current_value
+    /// Number of closes needed for a first value.
+    fn required_samples(&self) -> usize {
+        match self.smoothing {
+            RsiSmoothing::Cutler => self.period,
+            RsiSmoothing::Wilder => self.period + 1,
+        }
     }
 
-    fn update(&mut self, price: Decimal, timestamp: u64) {
-        if !self.should_update(timestamp) {
+    fn add_close(&mut self, close: Decimal) {
+        let samples = self.samples + 1;
+        self.samples = samples.min(self.required_samples());
+        let Some(prev_close) = self.prev_close.replace(close) else {
             return;
-        }
+        };
 
-        self.last_updated_at = timestamp;
+        let change = close - prev_close;
+        let (gain, loss) = split_change(change);
 
-        if self.values.len() == self.period {
-            self.values.pop_front();
-        }
-        self.values.push_back(price);
+        match self.smoothing {
+            RsiSmoothing::Cutler => {
+                self.changes.push_back(change);
+                self.gains += gain;
+                self.losses += loss;
 
-        if self.values.len() < self.period {
-            self.current_value = None;
-            return;
-        }
-
-        let gains: Decimal = self
-            .values
-            .iter()
-            .zip(self.values.iter().skip(1))
-            .map(|(prev, curr)| {
-                if curr > prev {
-                    *curr - *prev
-                } else {
-                    Decimal::ZERO
+                if self.changes.len() >= self.period {
+                    let (gain, loss) = split_change(self.changes.pop_front().unwrap());
+                    self.gains -= gain;
+                    self.losses -= loss;
                 }
-            })
-            .sum();
-
-        let losses: Decimal = self
-            .values
-            .iter()
-            .zip(self.values.iter().skip(1))
-            .map(|(prev, curr)| {
-                if curr < prev {
-                    *prev - *curr
+            }
+            RsiSmoothing::Wilder => {
+                let period = Decimal::from(self.period as u64);
+                if samples > self.required_samples() {
+                    let weight = period - Decimal::ONE;
+                    self.gains = (self.gains * weight + gain) / period;
+                    self.losses = (self.losses * weight + loss) / period;
                 } else {
-                    Decimal::ZERO
+                    self.gains += gain;
+                    self.losses += loss;
+                    if samples == self.required_samples() {
+                        self.gains = self.gains / period;
+                        self.losses = self.losses / period;
+                    }
                 }
-            })
-            .sum();
+            }
+        }
 
-        let rs: Decimal = if losses.is_zero() {
-            self.current_value = Some(Decimal::from(100.0));
-            return;
+        if self.samples < self.required_samples() {
+            self.current_value = None;
+        } else if self.losses.is_zero() {
+            self.current_value = Some(Decimal::from(100));
         } else {
-            gains / losses
-        };
+            let rs = self.gains / self.losses;
+            self.current_value =
+                Some(Decimal::from(100) - (Decimal::from(100) / (Decimal::ONE + rs)));
+        }
+    }
+}
 
-        self.current_value =
-            Some(Decimal::from(100.0) - (Decimal::from(100.0) / (Decimal::ONE + rs)));
+/// Splits a price change into its gain and loss parts.
+fn split_change(change: Decimal) -> (Decimal, Decimal) {
+    if change.is_positive() {
+        (change, Decimal::ZERO)
+    } else {
+        (Decimal::ZERO, -change)
+    }
+}
+
+impl Indicator for Rsi {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.samples, self.required_samples())
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_close(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_close(candle.close);
     }
 
     fn reset(&mut self) {
-        self.values.clear();
+        self.aggregator.reset();
+        self.prev_close = None;
+        self.samples = 0;
+        self.changes.clear();
+        self.gains = Decimal::ZERO;
+        self.losses = Decimal::ZERO;
         self.current_value = None;
     }
 }
@@ -107,23 +179,24 @@ mod tests {
     #[test]
     fn test_rsi() {
         let prices = vec![
-            Decimal::from(44.0),
-            Decimal::from(44.15), // + 0.15, out of range
-            Decimal::from(43.9),  // - 0.25 , out of range
-            Decimal::from(44.05), // + 0.15, out of range
-            Decimal::from(44.3),  // + 0.25
-            Decimal::from(44.6),  // + 0.3
-            Decimal::from(44.9),  // + 0.3
-            Decimal::from(45.1),  // + 0.2
-            Decimal::from(45.0),  // - 0.1
-            Decimal::from(45.2),  // + 0.2
-            Decimal::from(45.4),  // + 0.2
-            Decimal::from(45.3),  // - 0.1
-            Decimal::from(45.5),  // + 0.2
-            Decimal::from(45.6),  // + 0.1
-            Decimal::from(45.3),  // - 0.3
-            Decimal::from(45.1),  // - 0.2
-            Decimal::from(45.0),  // - 0.1
+            Decimal::from(44),
+            Decimal::from_str_unchecked("44.15"), // + 0.15, out of range
+            Decimal::from_str_unchecked("43.9"),  // - 0.25 , out of range
+            Decimal::from_str_unchecked("44.05"), // + 0.15, out of range
+            Decimal::from_str_unchecked("44.3"),  // + 0.25
+            Decimal::from_str_unchecked("44.6"),  // + 0.3
+            Decimal::from_str_unchecked("44.9"),  // + 0.3
+            Decimal::from_str_unchecked("45.1"),  // + 0.2
+            Decimal::from(45),                    // - 0.1
+            Decimal::from_str_unchecked("45.2"),  // + 0.2
+            Decimal::from_str_unchecked("45.4"),  // + 0.2
+            Decimal::from_str_unchecked("45.3"),  // - 0.1
+            Decimal::from_str_unchecked("45.5"),  // + 0.2
+            Decimal::from_str_unchecked("45.6"),  // + 0.1
+            Decimal::from_str_unchecked("45.3"),  // - 0.3
+            Decimal::from_str_unchecked("45.1"),  // - 0.2
+            Decimal::from(45),                    // - 0.1
+            Decimal::from(45),                    // Closes the previous bar
         ];
 
         let mut rsi = Rsi::new(14, 100);
@@ -138,6 +211,69 @@ mod tests {
         // RS = 1.75 / 0.8 = 2.1875
         // RSI = 100 - (100 / (1 + 2.1875))
         // RSI = 100 - (100 / 3.1875) = 68.627451
-        assert_eq!(rsi.value(), Some(Decimal::from(68.627451)));
+        assert_eq!(rsi.value(), Some(Decimal::from_str_unchecked("68.627451")));
+    }
+
+    #[test]
+    fn test_rsi_wilder() {
+        // Wilder's example as used by most charting libraries, RSI(14) from the 15th close
+        let closes = [
+            "44.34", "44.09", "44.15", "43.61", "44.33", "44.83", "45.10", "45.42", "45.84",
+            "46.08", "45.89", "46.03", "45.61", "46.28", "46.28", "46.00", "46.03", "46.41",
+            "46.22", "45.64",
+        ];
+
+        let mut rsi = Rsi::new(14, 1000).with_smoothing(RsiSmoothing::Wilder);
+        let mut values = Vec::new();
+        for (i, close) in closes.iter().enumerate() {
+            rsi.update_candle(&Candle::new(
+                i as u64 * 1000,
+                Decimal::from_str_unchecked(close),
+                Decimal::ONE,
+                i as u64 * 1000,
+            ));
+            values.push(rsi.value());
+
+            if i == 13 {
+                assert_eq!(
+                    rsi.warm_up_status(),
+                    WarmUpStatus::WarmingUp {
+                        samples: 14,
+                        required: 15
+                    }
+                );
+            }
+        }
+
+        // Average gain 0.238571, average loss 0.1, then smoothed
+        assert!(values[..14].iter().all(Option::is_none));
+        let expected = [
+            "70.464098",
+            "66.249579",
+            "66.481039",
+            "69.347053",
+            "66.295002",
+            "57.915115",
+        ];
+        for (value, expected) in values[14..].iter().zip(expected) {
+            assert_eq!(*value, Some(Decimal::from_str_unchecked(expected)));
+        }
+        assert!(rsi.is_ready());
+    }
+
+    #[test]
+    fn test_rsi_samples_on_bar_close() {
+        let mut rsi = Rsi::new(2, 1000);
+
+        rsi.update(100.into(), 0);
+        rsi.update(110.into(), 999);
+        // The close of the first bar is 110, not the first price
+        rsi.update(105.into(), 1000);
+        rsi.update(90.into(), 1500);
+        assert!(!rsi.is_ready());
+
+        // Bar closes 110 then 90: all losses
+        rsi.update(95.into(), 2000);
+        assert_eq!(rsi.value(), Some(Decimal::ZERO));
     }
 }
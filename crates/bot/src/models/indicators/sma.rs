@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
+
+/// Simple moving average of the last `period` closes of time bars of `update_interval` ms.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    name: String,
+    period: usize,
+    aggregator: CandleAggregator,
+    closes: VecDeque<Decimal>,
+    /// Sum of `closes`, updated incrementally
+    sum: Decimal,
+    current_value: Option<Decimal>,
+}
+
+impl Sma {
+    pub const NAME: &'static str = "sma";
+
+    pub fn new(period: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}",
+                Self::NAME,
+                period,
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            closes: VecDeque::with_capacity(period),
+            sum: Decimal::ZERO,
+            current_value: None,
+        }
+    }
+
+    fn add_close(&mut self, close: Decimal) {
+        if self.closes.len() == self.period {
+            self.sum -= self.closes.pop_front().unwrap();
+        }
+        self.closes.push_back(close);
+        self.sum += close;
+
+        self.current_value = if self.closes.len() < self.period {
+            None
+        } else {
+            Some(self.sum / Decimal::from(self.period as u64))
+        };
+    }
+}
+
+impl Indicator for Sma {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.closes.len(), self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_close(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_close(candle.close);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.closes.clear();
+        self.sum = Decimal::ZERO;
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sma() {
+        let mut sma = Sma::new(3, 1000);
+        // 1 price per bar, the last one only closing the previous bar
+        for (i, price) in [10, 11, 12, 13, 17, 0].into_iter().enumerate() {
+            sma.update(Decimal::from(price), i as u64 * 1000);
+            if i == 2 {
+                assert!(!sma.is_ready());
+            }
+        }
+
+        // (12 + 13 + 17) / 3 = 14
+        assert_eq!(sma.value(), Some(14.into()));
+    }
+}
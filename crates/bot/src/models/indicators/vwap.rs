@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use crate::models::{
+    Candle, CandleAggregator, CandleInterval, Decimal, Indicator, Trade, WarmUpStatus,
+};
+
+/// Volume-weighted average of the typical price `(high + low + close) / 3` over the last
+/// `period` time bars of `update_interval` ms.
+///
+/// Bars are built from trades with their sizes, prices given through `update` do not
+/// add any volume.
+#[derive(Debug, Clone)]
+pub struct Vwap {
+    name: String,
+    period: usize,
+    aggregator: CandleAggregator,
+    /// Typical price * volume and volume of each bar
+    bars: VecDeque<(Decimal, Decimal)>,
+    current_value: Option<Decimal>,
+}
+
+impl Vwap {
+    pub const NAME: &'static str = "vwap";
+
+    pub fn new(period: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}",
+                Self::NAME,
+                period,
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            bars: VecDeque::with_capacity(period),
+            current_value: None,
+        }
+    }
+
+    fn add_candle(&mut self, candle: &Candle) {
+        let typical_price = (candle.high + candle.low + candle.close) / Decimal::from(3);
+        if self.bars.len() == self.period {
+            self.bars.pop_front();
+        }
+        self.bars
+            .push_back((typical_price * candle.volume, candle.volume));
+
+        self.current_value = None;
+        if self.bars.len() < self.period {
+            return;
+        }
+
+        let notional: Decimal = self.bars.iter().map(|(notional, _)| *notional).sum();
+        let volume: Decimal = self.bars.iter().map(|(_, volume)| *volume).sum();
+        if volume.is_positive() {
+            self.current_value = Some(notional / volume);
+        }
+    }
+}
+
+impl Indicator for Vwap {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.bars.len(), self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_candle(&candle);
+        }
+    }
+
+    fn update_trade(&mut self, trade: &Trade) {
+        for candle in self
+            .aggregator
+            .update(trade.price, trade.size, trade.timestamp)
+        {
+            self.add_candle(&candle);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_candle(candle);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.bars.clear();
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Side;
+
+    fn trade(price: u64, size: u64, timestamp: u64) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Bid,
+            price: price.into(),
+            size: size.into(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_vwap() {
+        let mut vwap = Vwap::new(2, 1000);
+        let mut candle = Candle::new(0, 100.into(), 1.into(), 0);
+        candle.update(102.into(), Decimal::ZERO, 1);
+        candle.update(98.into(), Decimal::ZERO, 2);
+        candle.update(100.into(), Decimal::ZERO, 3);
+        vwap.update_candle(&candle);
+        assert!(!vwap.is_ready());
+
+        let mut candle = Candle::new(1000, 100.into(), 3.into(), 1000);
+        candle.update(106.into(), Decimal::ZERO, 1001);
+        candle.update(103.into(), Decimal::ZERO, 1002);
+        vwap.update_candle(&candle);
+
+        // Typical prices 100 and 103: (100 * 1 + 103 * 3) / 4 = 102.25
+        assert_eq!(vwap.value(), Some(Decimal::from_str_unchecked("102.25")));
+    }
+
+    #[test]
+    fn test_vwap_from_trades() {
+        let mut vwap = Vwap::new(1, 1000);
+        vwap.update_trade(&trade(100, 1, 0));
+        vwap.update_trade(&trade(104, 3, 500));
+        assert_eq!(vwap.value(), None);
+
+        // Closes the first bar with a typical price of (104 + 100 + 104) / 3 = 102.666666
+        vwap.update_trade(&trade(104, 1, 1000));
+        assert_eq!(
+            vwap.value(),
+            Some(Decimal::from_str_unchecked("102.666666"))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use crate::models::{Candle, CandleAggregator, CandleInterval, Decimal, Indicator, WarmUpStatus};
+
+/// Number of population standard deviations between the last close and the average of
+/// the last `period` closes of time bars of `update_interval` ms. `None` while the
+/// closes are all equal.
+#[derive(Debug, Clone)]
+pub struct ZScore {
+    name: String,
+    period: usize,
+    aggregator: CandleAggregator,
+    closes: VecDeque<Decimal>,
+    current_value: Option<Decimal>,
+}
+
+impl ZScore {
+    pub const NAME: &'static str = "z_score";
+
+    pub fn new(period: usize, update_interval: u64) -> Self {
+        Self {
+            name: format!(
+                "{}_{}_{}",
+                Self::NAME,
+                period,
+                CandleInterval::Time(update_interval)
+            ),
+            period,
+            aggregator: CandleAggregator::new(CandleInterval::Time(update_interval)),
+            closes: VecDeque::with_capacity(period),
+            current_value: None,
+        }
+    }
+
+    fn add_close(&mut self, close: Decimal) {
+        if self.closes.len() == self.period {
+            self.closes.pop_front();
+        }
+        self.closes.push_back(close);
+
+        self.current_value = None;
+        if self.closes.len() < self.period {
+            return;
+        }
+
+        if let Some(mean) = super::mean(self.closes.iter()) {
+            let std_dev = super::std_dev(self.closes.iter(), mean);
+            if std_dev.is_positive() {
+                self.current_value = Some((close - mean) / std_dev);
+            }
+        }
+    }
+}
+
+impl Indicator for ZScore {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        self.current_value
+    }
+
+    fn warm_up_status(&self) -> WarmUpStatus {
+        WarmUpStatus::new(self.closes.len(), self.period)
+    }
+
+    fn update(&mut self, price: Decimal, timestamp: u64) {
+        for candle in self.aggregator.update(price, Decimal::ZERO, timestamp) {
+            self.add_close(candle.close);
+        }
+    }
+
+    fn update_candle(&mut self, candle: &Candle) {
+        self.add_close(candle.close);
+    }
+
+    fn reset(&mut self) {
+        self.aggregator.reset();
+        self.closes.clear();
+        self.current_value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_z_score() {
+        let mut z_score = ZScore::new(8, 1000);
+        for close in [5, 5, 5, 5, 5, 5, 5, 5] {
+            z_score.update_candle(&Candle::new(0, close.into(), Decimal::ONE, 0));
+        }
+        assert!(z_score.is_ready());
+        assert_eq!(z_score.value(), None);
+
+        let mut z_score = ZScore::new(8, 1000);
+        // Mean 5, population standard deviation 2
+        for close in [2, 4, 4, 4, 5, 5, 7, 9] {
+            z_score.update_candle(&Candle::new(0, close.into(), Decimal::ONE, 0));
+        }
+        assert_eq!(z_score.value(), Some(2.into()));
+
+        // Window [4, 4, 4, 5, 5, 7, 9, 2]: (2 - 5) / 2
+        z_score.update_candle(&Candle::new(0, 2.into(), Decimal::ONE, 0));
+        assert_eq!(z_score.value(), Some(-Decimal::from_str_unchecked("1.5")));
+    }
+}
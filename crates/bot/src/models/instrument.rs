@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use clients::{BybitInstrumentInfo, BybitRestClient};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{decimal_lenient, Decimal, Side};
+
+/// Trading constraints of an instrument as enforced by the venue. Decimals may be given
+/// as numbers when deserializing, so that instruments can be written by hand in config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instrument {
+    pub symbol: String,
+    /// Prices must be a multiple of the tick size
+    #[serde(with = "decimal_lenient")]
+    pub tick_size: Decimal,
+    /// Sizes must be a multiple of the lot size
+    #[serde(with = "decimal_lenient")]
+    pub lot_size: Decimal,
+    #[serde(with = "decimal_lenient")]
+    pub min_size: Decimal,
+    /// Minimum price * size of an order
+    #[serde(with = "decimal_lenient", default)]
+    pub min_notional: Decimal,
+}
+
+impl Instrument {
+    /// Creates an instrument with a minimum size of one lot and no minimum notional.
+    pub fn new(symbol: impl Into<String>, tick_size: Decimal, lot_size: Decimal) -> Self {
+        Self {
+            symbol: symbol.into(),
+            tick_size,
+            lot_size,
+            min_size: lot_size,
+            min_notional: Decimal::ZERO,
+        }
+    }
+
+    pub fn with_min_size(mut self, min_size: Decimal) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    pub fn with_min_notional(mut self, min_notional: Decimal) -> Self {
+        self.min_notional = min_notional;
+        self
+    }
+
+    /// Rounds a quote price to the tick size, away from the market so that rounding
+    /// never makes the quote more aggressive.
+    pub fn round_price(&self, side: Side, price: Decimal) -> Decimal {
+        match side {
+            Side::Bid => price.floor_to_tick(self.tick_size),
+            Side::Ask => price.ceil_to_tick(self.tick_size),
+        }
+    }
+
+    pub fn round_size(&self, size: Decimal) -> Decimal {
+        size.floor_to_lot(self.lot_size)
+    }
+
+    /// Checks an order against the instrument constraints, returning the reason it
+    /// would be rejected by the venue.
+    pub fn validate(&self, price: Decimal, size: Decimal) -> Result<(), String> {
+        if !price.is_multiple_of(self.tick_size) {
+            return Err(format!(
+                "Order price {} is not a multiple of tick size {}",
+                price, self.tick_size
+            ));
+        }
+
+        if !size.is_multiple_of(self.lot_size) {
+            return Err(format!(
+                "Order size {} is not a multiple of lot size {}",
+                size, self.lot_size
+            ));
+        }
+
+        if size < self.min_size {
+            return Err(format!(
+                "Order size {} is below minimum size {}",
+                size, self.min_size
+            ));
+        }
+
+        let notional = price * size;
+        if notional < self.min_notional {
+            return Err(format!(
+                "Order notional {} is below minimum notional {}",
+                notional, self.min_notional
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&BybitInstrumentInfo> for Instrument {
+    type Error = anyhow::Error;
+
+    fn try_from(info: &BybitInstrumentInfo) -> Result<Self, Self::Error> {
+        let parse = |field: &str, value: Option<&str>| -> anyhow::Result<Decimal> {
+            let value = value.ok_or_else(|| {
+                anyhow::anyhow!("Missing {} for instrument {}", field, info.symbol)
+            })?;
+            value.parse::<Decimal>().map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid {} {} for instrument {}: {}",
+                    field,
+                    value,
+                    info.symbol,
+                    e
+                )
+            })
+        };
+
+        let filter = &info.lot_size_filter;
+        Ok(Self {
+            symbol: info.symbol.clone(),
+            tick_size: parse("tick size", Some(&info.price_filter.tick_size))?,
+            lot_size: parse("lot size", filter.lot_size())?,
+            min_size: parse("min order size", Some(&filter.min_order_qty))?,
+            min_notional: match filter.min_notional() {
+                Some(value) => parse("min notional", Some(value))?,
+                None => Decimal::ZERO,
+            },
+        })
+    }
+}
+
+/// Instruments by symbol, (de)serialized as a list of instruments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Instrument>", into = "Vec<Instrument>")]
+pub struct InstrumentRegistry {
+    instruments: HashMap<String, Instrument>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the instruments of a Bybit `category` (e.g. spot or linear) from the
+    /// `instruments-info` REST endpoint.
+    pub async fn load_bybit(category: &str) -> anyhow::Result<Self> {
+        let mut client = BybitRestClient::new();
+        let instruments = client.get_instruments_info(category, None).await?;
+
+        let mut registry = Self::new();
+        for info in &instruments {
+            match Instrument::try_from(info) {
+                Ok(instrument) => registry.insert(instrument),
+                Err(e) => tracing::warn!("Skipping instrument {}: {}", info.symbol, e),
+            }
+        }
+
+        tracing::info!(
+            "Loaded {} {} instruments from Bybit",
+            registry.instruments.len(),
+            category
+        );
+        Ok(registry)
+    }
+
+    pub fn insert(&mut self, instrument: Instrument) {
+        self.instruments
+            .insert(instrument.symbol.clone(), instrument);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+}
+
+impl From<Vec<Instrument>> for InstrumentRegistry {
+    fn from(instruments: Vec<Instrument>) -> Self {
+        instruments.into_iter().collect()
+    }
+}
+
+impl From<InstrumentRegistry> for Vec<Instrument> {
+    fn from(registry: InstrumentRegistry) -> Self {
+        registry.instruments.into_values().collect()
+    }
+}
+
+impl FromIterator<Instrument> for InstrumentRegistry {
+    fn from_iter<T: IntoIterator<Item = Instrument>>(iter: T) -> Self {
+        let mut registry = Self::new();
+        for instrument in iter {
+            registry.insert(instrument);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clients::{BybitLotSizeFilter, BybitPriceFilter};
+
+    #[test]
+    fn test_instrument_validate() {
+        let instrument = Instrument::new(
+            "BTCUSDT",
+            Decimal::from_str_unchecked("0.01"),
+            Decimal::from_str_unchecked("0.001"),
+        )
+        .with_min_notional(5.into());
+
+        assert!(instrument
+            .validate(Decimal::from_str_unchecked("100.01"), 1.into())
+            .is_ok());
+        assert!(instrument
+            .validate(Decimal::from_str_unchecked("100.005"), 1.into())
+            .unwrap_err()
+            .contains("tick size"));
+        assert!(instrument
+            .validate(100.into(), Decimal::from_str_unchecked("0.0015"))
+            .unwrap_err()
+            .contains("lot size"));
+        assert!(instrument
+            .validate(100.into(), Decimal::from_str_unchecked("0.01"))
+            .unwrap_err()
+            .contains("notional"));
+
+        assert_eq!(
+            instrument.round_price(Side::Bid, Decimal::from_str_unchecked("100.019")),
+            Decimal::from_str_unchecked("100.01")
+        );
+        assert_eq!(
+            instrument.round_price(Side::Ask, Decimal::from_str_unchecked("100.011")),
+            Decimal::from_str_unchecked("100.02")
+        );
+    }
+
+    #[test]
+    fn test_instrument_from_bybit() {
+        let info = BybitInstrumentInfo {
+            symbol: "BTCUSDT".to_string(),
+            status: "Trading".to_string(),
+            price_filter: BybitPriceFilter {
+                tick_size: "0.01".to_string(),
+            },
+            lot_size_filter: BybitLotSizeFilter {
+                base_precision: Some("0.000001".to_string()),
+                qty_step: None,
+                min_order_qty: "0.000048".to_string(),
+                max_order_qty: "71.73956243".to_string(),
+                min_order_amt: Some("1".to_string()),
+                min_notional_value: None,
+            },
+        };
+
+        let instrument = Instrument::try_from(&info).unwrap();
+        assert_eq!(instrument.tick_size, Decimal::from_str_unchecked("0.01"));
+        assert_eq!(instrument.lot_size, Decimal::from_str_unchecked("0.000001"));
+        assert_eq!(instrument.min_size, Decimal::from_str_unchecked("0.000048"));
+        assert_eq!(instrument.min_notional, Decimal::ONE);
+
+        let registry = InstrumentRegistry::from_iter([instrument]);
+        assert!(registry.get("BTCUSDT").is_some());
+        assert!(registry.get("ETHUSDT").is_none());
+    }
+
+    #[test]
+    fn test_registry_from_config() {
+        let config = r#"[
+            {"symbol": "BTCUSDT", "tick_size": 0.01, "lot_size": "0.000001", "min_size": 0.000048, "min_notional": 1},
+            {"symbol": "ETHUSDT", "tick_size": 0.01, "lot_size": 0.00001, "min_size": 0.00001}
+        ]"#;
+
+        let registry: InstrumentRegistry = serde_json::from_str(config).unwrap();
+        let btc = registry.get("BTCUSDT").unwrap();
+        assert_eq!(btc.lot_size, Decimal::from_str_unchecked("0.000001"));
+        assert_eq!(btc.min_notional, Decimal::ONE);
+        assert_eq!(registry.get("ETHUSDT").unwrap().min_notional, Decimal::ZERO);
+
+        let json = serde_json::to_string(btc).unwrap();
+        assert!(json.contains(r#""tick_size":"0.010000""#));
+        assert_eq!(&serde_json::from_str::<Instrument>(&json).unwrap(), btc);
+    }
+}
@@ -1,17 +1,29 @@
 mod action;
+mod book_snapshot;
+mod candle;
 mod common;
 mod decimal;
 mod event;
 mod indicators;
+mod instrument;
 mod order_collection;
 mod orderbook;
 mod position;
+mod price_history;
+mod trade_flow;
+mod trade_intensity;
 
 pub use action::*;
+pub use book_snapshot::*;
+pub use candle::*;
 pub use common::*;
 pub use decimal::*;
 pub use event::*;
 pub use indicators::*;
+pub use instrument::*;
 pub use order_collection::*;
 pub use orderbook::*;
 pub use position::*;
+pub use price_history::*;
+pub use trade_flow::*;
+pub use trade_intensity::*;
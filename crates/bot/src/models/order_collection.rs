@@ -9,10 +9,17 @@ pub struct OrderCollection {
     bids: BTreeMap<Decimal, BTreeSet<usize>>,
     asks: BTreeMap<Decimal, BTreeSet<usize>>,
     registry: HashMap<usize, Order>,
+    cloids: HashMap<String, usize>,
 }
 
 impl OrderCollection {
+    /// Inserts an order, replacing any existing order with the same OID.
     pub fn insert(&mut self, order: Order) {
+        self.remove_by_oid(order.oid);
+
+        if let Some(cloid) = &order.cloid {
+            self.cloids.insert(cloid.clone(), order.oid);
+        }
         match order.side {
             Side::Bid => self.bids.entry(order.price).or_default().insert(order.oid),
             Side::Ask => self.asks.entry(order.price).or_default().insert(order.oid),
@@ -22,6 +29,9 @@ impl OrderCollection {
 
     pub fn remove_by_oid(&mut self, oid: usize) -> Option<Order> {
         if let Some(order) = self.registry.remove(&oid) {
+            if let Some(cloid) = &order.cloid {
+                self.cloids.remove(cloid);
+            }
             match order.side {
                 Side::Bid => {
                     if let Some(orders) = self.bids.get_mut(&order.price) {
@@ -69,6 +79,11 @@ impl OrderCollection {
         self.registry.get(&oid)
     }
 
+    pub fn get_order_by_cloid(&self, cloid: &str) -> Option<&Order> {
+        let oid = self.cloids.get(cloid)?;
+        self.registry.get(oid)
+    }
+
     pub fn get_all_oids(&self) -> Vec<usize> {
         self.registry.keys().cloned().collect()
     }
@@ -135,6 +150,7 @@ impl OrderCollection {
         self.bids.clear();
         self.asks.clear();
         self.registry.clear();
+        self.cloids.clear();
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Order> {
@@ -142,15 +158,23 @@ impl OrderCollection {
     }
 
     pub fn bids_iter(&self) -> impl Iterator<Item = &Order> {
-        self.bids.iter().flat_map(move |(_, orders)| {
-            orders.iter().filter_map(move |oid| self.registry.get(oid))
-        })
+        self.bids
+            .values()
+            .flat_map(move |orders| orders.iter().filter_map(move |oid| self.registry.get(oid)))
+    }
+
+    /// Iterates over bids from the highest price, keeping time priority within a level.
+    pub fn bids_iter_desc(&self) -> impl Iterator<Item = &Order> {
+        self.bids
+            .values()
+            .rev()
+            .flat_map(move |orders| orders.iter().filter_map(move |oid| self.registry.get(oid)))
     }
 
     pub fn asks_iter(&self) -> impl Iterator<Item = &Order> {
-        self.asks.iter().flat_map(move |(_, orders)| {
-            orders.iter().filter_map(move |oid| self.registry.get(oid))
-        })
+        self.asks
+            .values()
+            .flat_map(move |orders| orders.iter().filter_map(move |oid| self.registry.get(oid)))
     }
 
     pub fn for_each_bid_mut<F>(&mut self, mut f: F)
@@ -2,6 +2,9 @@ use std::collections::BTreeMap;
 
 use crate::models::{Decimal, Side};
 
+/// Basis points in a unit.
+const BPS: u64 = 10_000;
+
 /// Represents an order book snapshot, allowing for price querying
 /// and buy sell simulation.
 #[derive(Debug)]
@@ -49,6 +52,94 @@ impl OrderBook {
         &self.asks
     }
 
+    /// Levels of `side`, best price first.
+    pub fn levels(&self, side: Side) -> Box<dyn Iterator<Item = (&Decimal, &Decimal)> + '_> {
+        match side {
+            Side::Bid => Box::new(self.bids.iter().rev()),
+            Side::Ask => Box::new(self.asks.iter()),
+        }
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    /// Spread relative to the mid price, in basis points.
+    pub fn spread_bps(&self) -> Option<Decimal> {
+        (self.spread()? * Decimal::from(BPS))
+            .checked_div(self.mid_price()?)
+            .ok()
+    }
+
+    /// `(bid size - ask size) / (bid size + ask size)` over the best `levels` levels of
+    /// each side, from -1 (only asks) to 1 (only bids).
+    pub fn imbalance(&self, levels: usize) -> Option<Decimal> {
+        let bid_size = self.size(Side::Bid, levels);
+        let ask_size = self.size(Side::Ask, levels);
+        (bid_size - ask_size).checked_div(bid_size + ask_size).ok()
+    }
+
+    /// Mid price of the best bid and ask weighted by the size on the opposite side,
+    /// leaning towards the side more likely to be taken.
+    pub fn microprice(&self) -> Option<Decimal> {
+        self.weighted_mid(1)
+    }
+
+    /// Generalization of the microprice to the best `levels` levels: the size-weighted
+    /// average price of each side, weighted by the total size on the opposite side.
+    pub fn weighted_mid(&self, levels: usize) -> Option<Decimal> {
+        let (bid_price, bid_size) = self.average_price(Side::Bid, levels)?;
+        let (ask_price, ask_size) = self.average_price(Side::Ask, levels)?;
+        (bid_price * ask_size + ask_price * bid_size)
+            .checked_div(bid_size + ask_size)
+            .ok()
+    }
+
+    /// Total size of `side` priced within `bps` basis points of the mid price.
+    pub fn depth_within_bps(&self, side: Side, bps: Decimal) -> Option<Decimal> {
+        let mid_price = self.mid_price()?;
+        let distance = mid_price * bps / Decimal::from(BPS);
+        let depth = match side {
+            Side::Bid => self
+                .bids
+                .range(mid_price - distance..)
+                .map(|(_, size)| *size)
+                .sum(),
+            Side::Ask => self
+                .asks
+                .range(..=mid_price + distance)
+                .map(|(_, size)| *size)
+                .sum(),
+        };
+        Some(depth)
+    }
+
+    /// Steepness of `side` over its best `levels` levels: the cumulative size per basis
+    /// point between the best price and the last of those levels. A steep book holds
+    /// much liquidity close to the touch.
+    pub fn slope(&self, side: Side, levels: usize) -> Option<Decimal> {
+        let best_price = self.best_price(&side)?;
+        let (last_price, _) = self.levels(side).take(levels).last()?;
+        let distance_bps = ((*last_price - best_price).abs() * Decimal::from(BPS))
+            .checked_div(best_price)
+            .ok()?;
+        self.size(side, levels).checked_div(distance_bps).ok()
+    }
+
+    /// Total size of the best `levels` levels of `side`.
+    fn size(&self, side: Side, levels: usize) -> Decimal {
+        self.levels(side).take(levels).map(|(_, size)| *size).sum()
+    }
+
+    /// Size-weighted average price and total size of the best `levels` levels of `side`.
+    fn average_price(&self, side: Side, levels: usize) -> Option<(Decimal, Decimal)> {
+        let (notional, size) = self.levels(side).take(levels).fold(
+            (Decimal::ZERO, Decimal::ZERO),
+            |(notional, total), (price, size)| (notional + *price * *size, total + *size),
+        );
+        Some((notional.checked_div(size).ok()?, size))
+    }
+
     pub fn bids_depth(&self) -> usize {
         self.bids.len()
     }
@@ -62,24 +153,7 @@ impl OrderBook {
         price: Decimal,
         size: Decimal,
     ) -> (Vec<(Decimal, Decimal)>, Decimal) {
-        let mut fills = Vec::new();
-        let mut remaining_size = size;
-
-        for (opposite_price, opposite_size) in self.asks.range(..=price) {
-            if remaining_size.is_zero() {
-                break;
-            }
-
-            if *opposite_size > remaining_size {
-                fills.push((*opposite_price, remaining_size));
-                remaining_size = Decimal::ZERO;
-            } else {
-                fills.push((*opposite_price, *opposite_size));
-                remaining_size -= *opposite_size;
-            }
-        }
-
-        (fills, remaining_size)
+        self.simulate_with_consumed(Side::Bid, price, size, &mut BTreeMap::new())
     }
 
     pub fn simulate_sell(
@@ -87,21 +161,43 @@ impl OrderBook {
         price: Decimal,
         size: Decimal,
     ) -> (Vec<(Decimal, Decimal)>, Decimal) {
+        self.simulate_with_consumed(Side::Ask, price, size, &mut BTreeMap::new())
+    }
+
+    /// Simulates an order of `side` crossing the opposite side of the book up to `price`,
+    /// skipping liquidity already taken by earlier simulated orders as recorded in
+    /// `consumed` (price -> size), which is updated with the fills of this order.
+    /// Returns the fills and the remaining unfilled size.
+    pub fn simulate_with_consumed(
+        &self,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+        consumed: &mut BTreeMap<Decimal, Decimal>,
+    ) -> (Vec<(Decimal, Decimal)>, Decimal) {
+        let levels: Box<dyn Iterator<Item = (&Decimal, &Decimal)>> = match side {
+            Side::Bid => Box::new(self.asks.range(..=price)),
+            Side::Ask => Box::new(self.bids.range(price..).rev()),
+        };
+
         let mut fills = Vec::new();
         let mut remaining_size = size;
 
-        for (opposite_price, opposite_size) in self.bids.range(price..).rev() {
+        for (opposite_price, opposite_size) in levels {
             if remaining_size.is_zero() {
                 break;
             }
 
-            if *opposite_size > remaining_size {
-                fills.push((*opposite_price, remaining_size));
-                remaining_size = Decimal::ZERO;
-            } else {
-                fills.push((*opposite_price, *opposite_size));
-                remaining_size -= *opposite_size;
+            let taken = consumed.entry(*opposite_price).or_insert(Decimal::ZERO);
+            let available = *opposite_size - *taken;
+            if !available.is_positive() {
+                continue;
             }
+
+            let fill_size = available.min(remaining_size);
+            fills.push((*opposite_price, fill_size));
+            *taken += fill_size;
+            remaining_size -= fill_size;
         }
 
         (fills, remaining_size)
@@ -228,19 +324,84 @@ mod orderbook_tests {
         orderbook.insert(Side::Ask, 102.into(), 2.into()).unwrap();
 
         orderbook
-            .adjust(Side::Bid, 100.into(), Decimal::from(0.5))
+            .adjust(Side::Bid, 100.into(), Decimal::from_str_unchecked("0.5"))
             .unwrap();
-        assert_eq!(orderbook.bids.get(&100.into()), Some(&Decimal::from(1.5)));
+        assert_eq!(
+            orderbook.bids.get(&100.into()),
+            Some(&Decimal::from_str_unchecked("1.5"))
+        );
 
         orderbook
-            .adjust(Side::Ask, 101.into(), Decimal::from(-0.5))
+            .adjust(Side::Ask, 101.into(), Decimal::from_str_unchecked("-0.5"))
             .unwrap();
-        assert_eq!(orderbook.asks.get(&101.into()), Some(&Decimal::from(2.5)));
+        assert_eq!(
+            orderbook.asks.get(&101.into()),
+            Some(&Decimal::from_str_unchecked("2.5"))
+        );
 
         orderbook
-            .adjust(Side::Bid, 99.into(), Decimal::from(-2.0))
+            .adjust(Side::Bid, 99.into(), -Decimal::from(2))
             .unwrap();
-        assert!(orderbook.bids.get(&99.into()).is_none());
+        assert!(!orderbook.bids.contains_key(&99.into()));
+    }
+
+    #[test]
+    fn test_microstructure_features() {
+        let mut orderbook = OrderBook::new(5);
+        assert!(orderbook.microprice().is_none());
+        assert!(orderbook.imbalance(1).is_none());
+
+        orderbook.insert(Side::Bid, 100.into(), 1.into()).unwrap();
+        orderbook.insert(Side::Bid, 99.into(), 2.into()).unwrap();
+        orderbook.insert(Side::Bid, 98.into(), 3.into()).unwrap();
+        orderbook.insert(Side::Ask, 101.into(), 3.into()).unwrap();
+        orderbook.insert(Side::Ask, 102.into(), 2.into()).unwrap();
+        orderbook.insert(Side::Ask, 103.into(), 1.into()).unwrap();
+
+        assert_eq!(orderbook.spread(), Some(1.into()));
+        // 1 / 100.5
+        assert_eq!(
+            orderbook.spread_bps(),
+            Some(Decimal::from_str_unchecked("99.502487"))
+        );
+
+        assert_eq!(
+            orderbook.imbalance(1),
+            Some(Decimal::from_str_unchecked("-0.5"))
+        );
+        assert_eq!(orderbook.imbalance(3), Some(Decimal::ZERO));
+
+        // (100 * 3 + 101 * 1) / 4, closer to the bid as the ask is larger
+        assert_eq!(
+            orderbook.microprice(),
+            Some(Decimal::from_str_unchecked("100.25"))
+        );
+        // Bids average 99.333333 for 3, asks 101.4 for 5
+        assert_eq!(
+            orderbook.weighted_mid(2),
+            Some(Decimal::from_str_unchecked("100.108333"))
+        );
+
+        // 100 bps of 100.5 is 1.005 on each side of the mid
+        assert_eq!(
+            orderbook.depth_within_bps(Side::Bid, 100.into()),
+            Some(1.into())
+        );
+        assert_eq!(
+            orderbook.depth_within_bps(Side::Ask, 100.into()),
+            Some(3.into())
+        );
+        assert_eq!(
+            orderbook.depth_within_bps(Side::Bid, 200.into()),
+            Some(3.into())
+        );
+
+        // 6 over 200 bps from 100 to 98
+        assert_eq!(
+            orderbook.slope(Side::Bid, 3),
+            Some(Decimal::from_str_unchecked("0.03"))
+        );
+        assert!(orderbook.slope(Side::Ask, 1).is_none());
     }
 
     #[test]
@@ -319,4 +480,24 @@ mod orderbook_tests {
         assert_eq!(fills.len(), 0);
         assert_eq!(remaining_size.to_string(), "4.000000");
     }
+
+    #[test]
+    fn test_simulate_with_consumed() {
+        let mut orderbook = OrderBook::new(5);
+
+        orderbook.insert(Side::Ask, 101.into(), 1.into()).unwrap();
+        orderbook.insert(Side::Ask, 102.into(), 2.into()).unwrap();
+
+        // Two orders crossing the same levels share their liquidity
+        let mut consumed = BTreeMap::new();
+        let (fills, remaining_size) =
+            orderbook.simulate_with_consumed(Side::Bid, 102.into(), 2.into(), &mut consumed);
+        assert_eq!(fills, vec![(101.into(), 1.into()), (102.into(), 1.into())]);
+        assert!(remaining_size.is_zero());
+
+        let (fills, remaining_size) =
+            orderbook.simulate_with_consumed(Side::Bid, 102.into(), 2.into(), &mut consumed);
+        assert_eq!(fills, vec![(102.into(), 1.into())]);
+        assert_eq!(remaining_size, 1.into());
+    }
 }
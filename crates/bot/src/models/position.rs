@@ -1,12 +1,16 @@
+use serde::{Deserialize, Serialize};
+
 use crate::models::{Decimal, Side};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub side: Side,
     pub entry_price: Decimal,
     pub size: Decimal,
     pub opened_at: u64,
     pub updated_at: u64,
+    /// PnL realized by reducing or closing the position, accumulated over its lifetime
+    pub realized_pnl: Decimal,
 }
 
 impl Position {
@@ -17,6 +21,7 @@ impl Position {
             size,
             opened_at: timestamp,
             updated_at: timestamp,
+            realized_pnl: Decimal::ZERO,
         }
     }
 
@@ -26,7 +31,9 @@ impl Position {
 
     pub fn update(&mut self, side: Side, price: Decimal, size: Decimal, timestamp: u64) {
         if !self.is_open() {
+            let realized_pnl = self.realized_pnl;
             *self = Position::new(side, price, size, timestamp);
+            self.realized_pnl = realized_pnl;
             return;
         }
 
@@ -37,6 +44,9 @@ impl Position {
             self.size = new_size;
         } else {
             // Opposite side: reduce position or flip
+            let closed_size = self.size.min(size);
+            self.realized_pnl += self.pnl_per_unit(price) * closed_size;
+
             match self.size.cmp(&size) {
                 std::cmp::Ordering::Greater => {
                     // Reduce position
@@ -59,6 +69,14 @@ impl Position {
         self.updated_at = timestamp;
     }
 
+    /// Position size signed by side, positive when long.
+    pub fn signed_size(&self) -> Decimal {
+        match self.side {
+            Side::Bid => self.size,
+            Side::Ask => -self.size,
+        }
+    }
+
     pub fn current_value(&self, current_price: Decimal) -> Decimal {
         if self.is_open() {
             current_price * self.size
@@ -72,12 +90,19 @@ impl Position {
             return Decimal::ZERO;
         }
 
-        let pnl_per_unit = match self.side {
+        self.pnl_per_unit(current_price) * self.size
+    }
+
+    /// Realized plus unrealized PnL at the given price.
+    pub fn total_pnl(&self, current_price: Decimal) -> Decimal {
+        self.realized_pnl + self.unrealized_pnl(current_price)
+    }
+
+    fn pnl_per_unit(&self, current_price: Decimal) -> Decimal {
+        match self.side {
             Side::Bid => current_price - self.entry_price,
             Side::Ask => self.entry_price - current_price,
-        };
-
-        pnl_per_unit * self.size
+        }
     }
 }
 
@@ -89,6 +114,7 @@ impl Default for Position {
             entry_price: Decimal::ZERO,
             opened_at: 0,
             updated_at: 0,
+            realized_pnl: Decimal::ZERO,
         }
     }
 }
@@ -165,4 +191,39 @@ mod position_tests {
         let pnl = position.unrealized_pnl(100.into());
         assert_eq!(pnl.to_string(), "0.000000"); // (100 - 100) * 2.0 = 0.0
     }
+
+    #[test]
+    fn test_position_realized_pnl() {
+        let mut position =
+            Position::new(Side::Bid, 100.into(), 2.0.try_into().unwrap(), 1622547800);
+
+        position.update(Side::Ask, 110.into(), 1.0.try_into().unwrap(), 1622547801);
+        assert_eq!(position.realized_pnl.to_string(), "10.000000"); // (110 - 100) * 1.0
+
+        // Flip: close the remaining 1.0 at 90, then open a 1.0 short at 90
+        position.update(Side::Ask, 90.into(), 2.0.try_into().unwrap(), 1622547802);
+        assert_eq!(position.realized_pnl.to_string(), "0.000000"); // 10 + (90 - 100) * 1.0
+        assert_eq!(position.side, Side::Ask);
+
+        position.update(Side::Bid, 80.into(), 1.0.try_into().unwrap(), 1622547803);
+        assert!(!position.is_open());
+        assert_eq!(position.realized_pnl.to_string(), "10.000000"); // 0 + (90 - 80) * 1.0
+
+        // Realized PnL is kept when a new position is opened
+        position.update(Side::Bid, 100.into(), 1.0.try_into().unwrap(), 1622547804);
+        assert_eq!(position.total_pnl(95.into()).to_string(), "5.000000");
+    }
+
+    #[test]
+    fn test_position_serde_round_trip() {
+        let mut position = Position::new(Side::Ask, 100.into(), 2.into(), 1622547800);
+        position.update(Side::Bid, 90.into(), 1.into(), 1622547801);
+
+        let json = serde_json::to_string(&position).unwrap();
+        let decoded: Position = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.side, Side::Ask);
+        assert_eq!(decoded.size, position.size);
+        assert_eq!(decoded.realized_pnl.to_string(), "10.000000");
+        assert_eq!(decoded.updated_at, 1622547801);
+    }
 }
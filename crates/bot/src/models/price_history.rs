@@ -0,0 +1,207 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clients::{BybitKline, BybitRecentTrade, BybitRestClient};
+
+use crate::models::{Candle, CandleInterval, Decimal, Side, Trade};
+
+/// Where to load past market data from to warm up indicators on startup.
+#[derive(Debug, Clone)]
+pub enum PriceHistory {
+    /// The last `limit` closed klines of the Bybit `/v5/market/kline` endpoint, fed to
+    /// the indicators as bars: `interval` should match the interval of the indicators.
+    BybitKlines {
+        category: String,
+        symbol: String,
+        interval: CandleInterval,
+        limit: usize,
+    },
+    /// The last `limit` trades of the Bybit `/v5/market/recent-trade` endpoint, fed to
+    /// the indicators as live trades.
+    BybitTrades {
+        category: String,
+        symbol: String,
+        limit: usize,
+    },
+    /// Trades of a file with one JSON-serialized [`Trade`] per line, oldest first.
+    TradesFile(PathBuf),
+}
+
+/// Market data loaded from a [`PriceHistory`], oldest first.
+#[derive(Debug, Clone)]
+pub enum HistoricalData {
+    Candles(Vec<Candle>),
+    Trades(Vec<Trade>),
+}
+
+impl HistoricalData {
+    /// Time of the latest data point.
+    pub fn last_timestamp(&self) -> Option<u64> {
+        match self {
+            HistoricalData::Candles(candles) => candles.last().map(|c| c.close_time),
+            HistoricalData::Trades(trades) => trades.last().map(|t| t.timestamp),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            HistoricalData::Candles(candles) => candles.len(),
+            HistoricalData::Trades(trades) => trades.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl PriceHistory {
+    pub async fn load(&self) -> anyhow::Result<HistoricalData> {
+        match self {
+            PriceHistory::BybitKlines {
+                category,
+                symbol,
+                interval,
+                limit,
+            } => {
+                let CandleInterval::Time(interval_ms) = *interval else {
+                    return Err(anyhow::anyhow!("Bybit has no {} klines", interval));
+                };
+                let bybit_interval = bybit_interval(interval_ms)
+                    .ok_or_else(|| anyhow::anyhow!("Bybit has no {} klines", interval))?;
+
+                let mut client = BybitRestClient::new();
+                // One more kline as the last one is still open
+                let klines = client
+                    .get_klines(category, symbol, &bybit_interval, limit + 1)
+                    .await?;
+
+                let now = chrono::Utc::now().timestamp_millis() as u64;
+                let candles = klines
+                    .iter()
+                    .map(|kline| candle_from_bybit(kline, interval_ms))
+                    .collect::<anyhow::Result<Vec<_>>>()?
+                    .into_iter()
+                    .filter(|candle| candle.open_time + interval_ms <= now)
+                    .collect();
+                Ok(HistoricalData::Candles(candles))
+            }
+            PriceHistory::BybitTrades {
+                category,
+                symbol,
+                limit,
+            } => {
+                let mut client = BybitRestClient::new();
+                let trades = client
+                    .get_recent_trades(category, symbol, *limit)
+                    .await?
+                    .iter()
+                    .map(trade_from_bybit)
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok(HistoricalData::Trades(trades))
+            }
+            PriceHistory::TradesFile(path) => {
+                let content = tokio::fs::read_to_string(path).await?;
+                let trades = content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        serde_json::from_str(line).map_err(|e| {
+                            anyhow::anyhow!("Invalid trade in {}: {}", path.display(), e)
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<Trade>>>()?;
+                Ok(HistoricalData::Trades(trades))
+            }
+        }
+    }
+}
+
+/// The Bybit name of a kline interval of `interval_ms` ms, if Bybit provides it.
+fn bybit_interval(interval_ms: u64) -> Option<String> {
+    const MINUTES: [u64; 10] = [1, 3, 5, 15, 30, 60, 120, 240, 360, 720];
+    match interval_ms {
+        86_400_000 => Some("D".to_string()),
+        604_800_000 => Some("W".to_string()),
+        ms if ms % 60_000 == 0 && MINUTES.contains(&(ms / 60_000)) => {
+            Some((ms / 60_000).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A closed bar from a Bybit kline of `interval_ms` ms, without a trade count.
+fn candle_from_bybit(kline: &BybitKline, interval_ms: u64) -> anyhow::Result<Candle> {
+    let parse = |value: &str| {
+        Decimal::from_str(value).map_err(|e| anyhow::anyhow!("Invalid kline {:?}: {}", kline, e))
+    };
+    let open_time = kline
+        .start
+        .parse::<u64>()
+        .map_err(|e| anyhow::anyhow!("Invalid kline start {}: {}", kline.start, e))?;
+
+    Ok(Candle {
+        open_time,
+        close_time: open_time + interval_ms - 1,
+        open: parse(&kline.open)?,
+        high: parse(&kline.high)?,
+        low: parse(&kline.low)?,
+        close: parse(&kline.close)?,
+        volume: parse(&kline.volume)?,
+        trade_count: 0,
+    })
+}
+
+fn trade_from_bybit(trade: &BybitRecentTrade) -> anyhow::Result<Trade> {
+    let invalid = |e: &dyn std::fmt::Display| anyhow::anyhow!("Invalid trade {:?}: {}", trade, e);
+    Ok(Trade {
+        symbol: trade.symbol.clone(),
+        side: Side::from_str(&trade.side).map_err(|e| invalid(&e))?,
+        price: Decimal::from_str(&trade.price).map_err(|e| invalid(&e))?,
+        size: Decimal::from_str(&trade.size).map_err(|e| invalid(&e))?,
+        timestamp: trade.time.parse().map_err(|e| invalid(&e))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bybit_interval() {
+        assert_eq!(bybit_interval(60_000).as_deref(), Some("1"));
+        assert_eq!(bybit_interval(3_600_000).as_deref(), Some("60"));
+        assert_eq!(bybit_interval(86_400_000).as_deref(), Some("D"));
+        assert_eq!(bybit_interval(1000), None);
+        assert_eq!(bybit_interval(120_000), None);
+    }
+
+    #[test]
+    fn test_bybit_conversions() {
+        let kline = BybitKline {
+            start: "1670601600000".to_string(),
+            open: "17071".to_string(),
+            high: "17073".to_string(),
+            low: "17027".to_string(),
+            close: "17055.5".to_string(),
+            volume: "268611".to_string(),
+            turnover: "15.74462667".to_string(),
+        };
+        let candle = candle_from_bybit(&kline, 60_000).unwrap();
+        assert_eq!(candle.open_time, 1670601600000);
+        assert_eq!(candle.close_time, 1670601659999);
+        assert_eq!(candle.close, Decimal::from_str_unchecked("17055.5"));
+
+        let trade = BybitRecentTrade {
+            exec_id: "2100000000007764263".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            price: "16618.49".to_string(),
+            size: "0.00012".to_string(),
+            side: "Sell".to_string(),
+            time: "1672052955758".to_string(),
+            is_block_trade: false,
+        };
+        let trade = trade_from_bybit(&trade).unwrap();
+        assert_eq!(trade.side, Side::Ask);
+        assert_eq!(trade.timestamp, 1672052955758);
+    }
+}
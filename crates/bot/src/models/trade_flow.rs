@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+use crate::models::{Decimal, Side, Trade};
+
+/// Rolling statistics of the market trades of the last `window` ms.
+///
+/// The window is measured in trade time, ending at the latest trade or at the time
+/// given to [`TradeFlow::expire`], so that replays and live runs agree.
+#[derive(Debug, Clone)]
+pub struct TradeFlow {
+    window: u64,
+    trades: VecDeque<Trade>,
+    /// Taker buy volume of `trades`, updated incrementally
+    buy_volume: Decimal,
+    /// Taker sell volume of `trades`, updated incrementally
+    sell_volume: Decimal,
+    /// Sum of price * size of `trades`, updated incrementally
+    notional: Decimal,
+    /// Timestamp of the latest trade, trades may arrive slightly out of order
+    latest_timestamp: u64,
+}
+
+impl TradeFlow {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            trades: VecDeque::new(),
+            buy_volume: Decimal::ZERO,
+            sell_volume: Decimal::ZERO,
+            notional: Decimal::ZERO,
+            latest_timestamp: 0,
+        }
+    }
+
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Adds a trade, then drops the trades that fell out of the window.
+    pub fn update(&mut self, trade: &Trade) {
+        match trade.side {
+            Side::Bid => self.buy_volume += trade.size,
+            Side::Ask => self.sell_volume += trade.size,
+        }
+        self.notional += trade.price * trade.size;
+        self.trades.push_back(trade.clone());
+
+        self.latest_timestamp = self.latest_timestamp.max(trade.timestamp);
+        self.expire(self.latest_timestamp);
+    }
+
+    /// Drops the trades older than `window` ms before `now`.
+    pub fn expire(&mut self, now: u64) {
+        let start = now.saturating_sub(self.window);
+        while self.trades.front().is_some_and(|t| t.timestamp < start) {
+            let trade = self.trades.pop_front().unwrap();
+            match trade.side {
+                Side::Bid => self.buy_volume -= trade.size,
+                Side::Ask => self.sell_volume -= trade.size,
+            }
+            self.notional -= trade.price * trade.size;
+        }
+    }
+
+    /// Trades of the window, oldest first.
+    pub fn trades(&self) -> &VecDeque<Trade> {
+        &self.trades
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn buy_volume(&self) -> Decimal {
+        self.buy_volume
+    }
+
+    pub fn sell_volume(&self) -> Decimal {
+        self.sell_volume
+    }
+
+    pub fn volume(&self) -> Decimal {
+        self.buy_volume + self.sell_volume
+    }
+
+    /// `(buy volume - sell volume) / volume`, from -1 (only sells) to 1 (only buys).
+    pub fn imbalance(&self) -> Option<Decimal> {
+        (self.buy_volume - self.sell_volume)
+            .checked_div(self.volume())
+            .ok()
+    }
+
+    /// Trades per second over the window.
+    pub fn arrival_rate(&self) -> Decimal {
+        Decimal::from(self.trades.len() as u64 * 1000) / Decimal::from(self.window.max(1))
+    }
+
+    /// Volume-weighted average price of the window.
+    pub fn vwap(&self) -> Option<Decimal> {
+        self.notional.checked_div(self.volume()).ok()
+    }
+
+    /// Trades of the window of at least `size`, oldest first.
+    pub fn large_trades(&self, size: Decimal) -> impl Iterator<Item = &Trade> {
+        self.trades.iter().filter(move |trade| trade.size >= size)
+    }
+
+    pub fn reset(&mut self) {
+        self.trades.clear();
+        self.buy_volume = Decimal::ZERO;
+        self.sell_volume = Decimal::ZERO;
+        self.notional = Decimal::ZERO;
+        self.latest_timestamp = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(side: Side, price: u64, size: u64, timestamp: u64) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            side,
+            price: price.into(),
+            size: size.into(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_trade_flow() {
+        let mut flow = TradeFlow::new(2000);
+        assert!(flow.imbalance().is_none());
+        assert!(flow.vwap().is_none());
+
+        flow.update(&trade(Side::Bid, 100, 1, 0));
+        flow.update(&trade(Side::Bid, 101, 3, 1000));
+        flow.update(&trade(Side::Ask, 99, 1, 2000));
+
+        assert_eq!(flow.buy_volume(), 4.into());
+        assert_eq!(flow.sell_volume(), 1.into());
+        // (4 - 1) / 5
+        assert_eq!(flow.imbalance(), Some(Decimal::from_str_unchecked("0.6")));
+        // (100 + 303 + 99) / 5
+        assert_eq!(flow.vwap(), Some(Decimal::from_str_unchecked("100.4")));
+        assert_eq!(flow.arrival_rate(), Decimal::from_str_unchecked("1.5"));
+        assert_eq!(flow.large_trades(3.into()).count(), 1);
+
+        // The first trade leaves the window
+        flow.update(&trade(Side::Ask, 98, 2, 2500));
+        assert_eq!(flow.trade_count(), 3);
+        assert_eq!(flow.buy_volume(), 3.into());
+        assert_eq!(flow.imbalance(), Some(Decimal::ZERO));
+
+        flow.expire(10_000);
+        assert_eq!(flow.trade_count(), 0);
+        assert_eq!(flow.volume(), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+
+use crate::models::Decimal;
+
+/// Estimates the order-arrival intensity `κ` used by Avellaneda–Stoikov style strategies.
+///
+/// Assuming the probability of a fill decays as `e^(-κδ)` with the distance `δ` from the
+/// mid price, the distances of market trades from the mid are exponentially distributed
+/// with rate `κ`, whose maximum likelihood estimate is `1 / mean(δ)` over a rolling window.
+#[derive(Debug, Clone)]
+pub struct TradeIntensity {
+    window: usize,
+    distances: VecDeque<Decimal>,
+    sum: Decimal,
+}
+
+impl TradeIntensity {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            distances: VecDeque::with_capacity(window),
+            sum: Decimal::ZERO,
+        }
+    }
+
+    pub fn update(&mut self, trade_price: Decimal, mid_price: Decimal) {
+        if self.distances.len() == self.window {
+            if let Some(oldest) = self.distances.pop_front() {
+                self.sum -= oldest;
+            }
+        }
+
+        let distance = (trade_price - mid_price).abs();
+        self.distances.push_back(distance);
+        self.sum += distance;
+    }
+
+    /// Returns `None` until the window is full, or if every trade in the window
+    /// happened at the mid price.
+    pub fn value(&self) -> Option<Decimal> {
+        if self.distances.len() < self.window || !self.sum.is_positive() {
+            return None;
+        }
+
+        let mean = self.sum / Decimal::from(self.window as u64);
+        if mean.is_zero() {
+            return None;
+        }
+        Some(Decimal::ONE / mean)
+    }
+
+    pub fn reset(&mut self) {
+        self.distances.clear();
+        self.sum = Decimal::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_intensity() {
+        let mut intensity = TradeIntensity::new(4);
+        let mid = Decimal::from(100);
+
+        intensity.update(Decimal::from_str_unchecked("100.5"), mid);
+        intensity.update(Decimal::from_str_unchecked("99.5"), mid);
+        intensity.update(Decimal::from(101), mid);
+        assert_eq!(intensity.value(), None);
+
+        // Mean distance of (0.5 + 0.5 + 1 + 0) / 4 = 0.5
+        intensity.update(Decimal::from(100), mid);
+        assert_eq!(intensity.value(), Some(Decimal::from(2)));
+
+        // Oldest distance of 0.5 replaced by 1.5: mean of 0.75
+        intensity.update(Decimal::from_str_unchecked("98.5"), mid);
+        assert_eq!(
+            intensity.value(),
+            Some(Decimal::from_str_unchecked("1.333333"))
+        );
+    }
+}
@@ -1,11 +1,19 @@
-use hayate_core::traits::{Collector, State};
+use std::collections::BTreeMap;
+
+use hayate_core::{
+    kill_switch::KillSwitch,
+    traits::{Collector, State},
+};
 use tokio::sync::{broadcast, mpsc};
 use tokio_stream::StreamExt;
 
 use crate::{
-    models::{Fill, InternalEvent, Order, PlaceOrder, Side},
+    models::{
+        AmendOrder, CancelOrder, Decimal, Fill, InstrumentRegistry, InternalEvent, Order,
+        OrderRejection, OrderStatus, PlaceOrder, Side, Trade,
+    },
     paper_trade::types::PaperExchangeMessage,
-    state::{OrderBookState, PendingOrdersState, PositionState},
+    state::{OrderBookState, PendingOrdersState, PositionState, TradeFlowState},
 };
 
 /// PaperExchange simulates an exchange for paper trading.
@@ -20,6 +28,12 @@ pub struct PaperExchange {
     bot_position: PositionState,
     pending_orders: PendingOrdersState,
     next_oid: usize, // Order ID counter
+    /// Kill switch whose trigger is reported in the summary
+    kill_switch: Option<KillSwitch>,
+    /// If set, orders for unknown symbols or violating instrument constraints are rejected
+    instruments: Option<InstrumentRegistry>,
+    /// Market trade flow, available to fill models and reported in the summary
+    trade_flow: Option<TradeFlowState>,
 }
 
 impl PaperExchange {
@@ -32,9 +46,27 @@ impl PaperExchange {
             bot_position: PositionState::new(),
             pending_orders: PendingOrdersState::new(),
             next_oid: 1,
+            kill_switch: None,
+            instruments: None,
+            trade_flow: None,
         }
     }
 
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    pub fn with_instruments(mut self, instruments: InstrumentRegistry) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    pub fn with_trade_flow(mut self, trade_flow: TradeFlowState) -> Self {
+        self.trade_flow = Some(trade_flow);
+        self
+    }
+
     pub fn subscribe(&self) -> broadcast::Receiver<InternalEvent> {
         self.broadcaster.subscribe()
     }
@@ -48,11 +80,12 @@ impl PaperExchange {
 
         loop {
             tokio::select! {
-                Some(event) = source_stream.next() => {
-                    self.process_event(event)?;
-                }
+                event = source_stream.next() => match event {
+                    Some(event) => self.process_event(event),
+                    None => anyhow::bail!("Market data stream ended"),
+                },
                 Some(msg) = msg_rx.recv() => {
-                    self.process_msg(msg)?;
+                    self.process_msg(msg);
                 }
             }
         }
@@ -66,131 +99,345 @@ impl PaperExchange {
     ) -> anyhow::Result<()> {
         let mut source_stream = collector.get_event_stream().await?;
 
-        loop {
+        // The exchange stops without market data rather than filling on a frozen book.
+        // Dropping its broadcaster ends the streams of the paper collectors, which the
+        // supervisor of the bot then handles as failed collectors
+        let result = loop {
             tokio::select! {
-                Some(event) = source_stream.next() => {
-                    self.process_event(event)?;
-                }
+                event = source_stream.next() => match event {
+                    Some(event) => self.process_event(event),
+                    None => break Err(anyhow::anyhow!("Market data stream ended")),
+                },
                 Some(msg) = msg_rx.recv() => {
-                    self.process_msg(msg)?;
+                    self.process_msg(msg);
                 }
                 _ = shutdown.cancelled() => {
                     tracing::info!("Shutdown signal received, stopping PaperExchange.");
-                    break;
+                    break Ok(());
                 }
             }
-        }
+        };
 
         tracing::info!("PaperExchange has finished running.");
-        let summary = self.produce_summary()?;
-        tracing::info!("PaperTrade Summary:\n{}", summary);
-        Ok(())
+        match self.produce_summary() {
+            Ok(summary) => tracing::info!("PaperTrade Summary:\n{}", summary),
+            Err(e) => tracing::warn!("{}", e),
+        }
+        result
     }
 
-    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
-        self.orderbook.process_event(event.clone())?;
-        self.broadcaster.send(event)?;
+    /// Applies a market data event and fills the orders it reaches. Failures are logged,
+    /// an event the exchange cannot apply must not stop it.
+    fn process_event(&mut self, event: InternalEvent) {
+        if let Err(e) = self.orderbook.process_event(event.clone()) {
+            tracing::error!(
+                "PaperExchange failed to apply {:?} to its book: {}",
+                event,
+                e
+            );
+        }
+        // Size filled per order by this event, so an order both traded through and
+        // crossed by the book is not filled beyond its size
+        let mut filled = BTreeMap::new();
+        let mut pending_order_fills = Vec::new();
+        if let Some(trade_flow) = &mut self.trade_flow {
+            if let Err(e) = trade_flow.process_event(event.clone()) {
+                tracing::error!(
+                    "PaperExchange failed to apply {:?} to its trade flow: {}",
+                    event,
+                    e
+                );
+            }
+            if let InternalEvent::TradeUpdate(trades) = &event {
+                pending_order_fills = self.simulate_trade_fills(trades, &mut filled);
+            }
+        }
+        self.broadcast(event);
 
-        let pending_order_fills = self.simulate_pending_order_fills();
+        pending_order_fills.extend(self.simulate_pending_order_fills(&mut filled));
         for fill in pending_order_fills {
-            let fill_event = InternalEvent::OrderFilled(fill);
-            self.bot_position.process_event(fill_event.clone())?;
-            self.pending_orders.process_event(fill_event.clone())?;
-            self.broadcaster.send(fill_event)?;
+            self.publish(InternalEvent::OrderFilled(fill));
         }
+    }
 
-        Ok(())
+    /// Applies an order event to the exchange's own view of the bot's orders and
+    /// position, then broadcasts it.
+    fn publish(&mut self, event: InternalEvent) {
+        if let Err(e) = self.bot_position.process_event(event.clone()) {
+            tracing::error!(
+                "PaperExchange failed to apply {:?} to the position: {}",
+                event,
+                e
+            );
+        }
+        if let Err(e) = self.pending_orders.process_event(event.clone()) {
+            tracing::error!(
+                "PaperExchange failed to apply {:?} to the orders: {}",
+                event,
+                e
+            );
+        }
+        self.broadcast(event);
+    }
+
+    /// Sends an event to the paper collectors, which may not be subscribed yet or
+    /// may have stopped.
+    fn broadcast(&self, event: InternalEvent) {
+        if let Err(e) = self.broadcaster.send(event) {
+            tracing::debug!("No paper collector subscribed for {:?}", e.0);
+        }
     }
 
-    fn process_msg(&mut self, msg: PaperExchangeMessage) -> anyhow::Result<()> {
+    fn process_msg(&mut self, msg: PaperExchangeMessage) {
         match msg {
             PaperExchangeMessage::PlaceOrder(action) => {
                 tracing::info!("Bot order received: {:?}", action);
-                self.process_place_order(action)?;
+                self.process_place_order(action);
             }
             PaperExchangeMessage::CancelOrder(cancel) => {
                 tracing::info!("Bot order received: {:?}", cancel);
-                if let Some(order) = self.pending_orders.cancel_order(cancel.oid) {
-                    self.broadcaster
-                        .send(InternalEvent::OrderCancelled(order))?;
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "Order with OID {} not found for cancellation",
-                        cancel.oid
-                    ));
-                }
+                self.process_cancel_order(cancel);
+            }
+            PaperExchangeMessage::AmendOrder(amend) => {
+                tracing::info!("Bot order received: {:?}", amend);
+                self.process_amend_order(amend);
             }
             PaperExchangeMessage::Close => {
                 // TODO: shutdown
             }
         }
-
-        Ok(())
     }
 
-    fn process_place_order(&mut self, action: PlaceOrder) -> anyhow::Result<()> {
-        let order = Order {
-            oid: self.next_oid,
-            symbol: action.symbol.clone(),
-            price: action.price,
-            size: action.size,
-            side: action.side,
-        };
+    fn process_place_order(&mut self, action: PlaceOrder) {
+        if let Some(reason) = self.validate_order(&action.symbol, action.price, action.size) {
+            tracing::warn!("Rejecting order {}: {}", action.cloid, reason);
+            return self.publish(InternalEvent::OrderRejected(OrderRejection {
+                cloid: Some(action.cloid),
+                oid: None,
+                symbol: action.symbol,
+                reason,
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            }));
+        }
+
+        let mut order = Order::new(
+            self.next_oid,
+            action.symbol,
+            action.side,
+            action.price,
+            action.size,
+        )
+        .with_cloid(action.cloid);
         self.next_oid += 1;
 
+        order.status = OrderStatus::PendingNew;
+        self.publish(InternalEvent::OrderPendingNew(order.clone()));
+
         // Simulate the fills
-        let fills = self.simulate_fills(&order, false);
+        let fills = self.simulate_fills(&order, order.size, false, &mut BTreeMap::new());
 
-        // Update the pending orders state with the new order and broadcast the event
-        let place_order_event = InternalEvent::OrderPlaced(order);
-        self.pending_orders
-            .process_event(place_order_event.clone())?;
-        self.broadcaster.send(place_order_event)?;
+        // Acknowledge the order, then apply the fills
+        order.status = OrderStatus::New;
+        self.publish(InternalEvent::OrderPlaced(order));
 
-        // Update the bot position state and pending order state with the fills and broadcast the events
         for fill in fills {
-            let fill_event = InternalEvent::OrderFilled(fill);
-            self.bot_position.process_event(fill_event.clone())?;
-            self.pending_orders.process_event(fill_event.clone())?;
-            self.broadcaster.send(fill_event)?;
+            self.publish(InternalEvent::OrderFilled(fill));
         }
+    }
 
-        Ok(())
+    fn process_cancel_order(&mut self, cancel: CancelOrder) {
+        let Some(order) = self
+            .pending_orders
+            .get_inner()
+            .get_order(cancel.oid)
+            .cloned()
+        else {
+            tracing::warn!("Order with OID {} not found for cancellation", cancel.oid);
+            return self.publish(InternalEvent::OrderRejected(OrderRejection {
+                cloid: None,
+                oid: Some(cancel.oid),
+                symbol: cancel.symbol,
+                reason: format!("Order with OID {} not found", cancel.oid),
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            }));
+        };
+
+        self.publish(InternalEvent::OrderPendingCancel(order.clone()));
+        self.publish(InternalEvent::OrderCancelled(order))
     }
 
-    fn simulate_pending_order_fills(&self) -> Vec<Fill> {
+    fn process_amend_order(&mut self, amend: AmendOrder) {
+        let order = match self.pending_orders.get_inner().get_order(amend.oid) {
+            None => Err(format!("Order with OID {} not found", amend.oid)),
+            Some(order) => match self.validate_order(&amend.symbol, amend.price, amend.size) {
+                Some(reason) => Err(reason),
+                None => Ok(order.clone()),
+            },
+        };
+
+        let mut order = match order {
+            Ok(order) => order,
+            Err(reason) => {
+                tracing::warn!("Rejecting amend for OID {}: {}", amend.oid, reason);
+                return self.publish(InternalEvent::OrderRejected(OrderRejection {
+                    cloid: None,
+                    oid: Some(amend.oid),
+                    symbol: amend.symbol,
+                    reason,
+                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
+                }));
+            }
+        };
+        order.price = amend.price;
+        order.size = amend.size;
+
+        // An amended order may now cross the book
+        let fills = self.simulate_fills(&order, order.size, false, &mut BTreeMap::new());
+        self.publish(InternalEvent::OrderAmended(order));
+
+        for fill in fills {
+            self.publish(InternalEvent::OrderFilled(fill));
+        }
+    }
+
+    fn validate_order(&self, symbol: &str, price: Decimal, size: Decimal) -> Option<String> {
+        if !size.is_positive() {
+            return Some(format!("Order size {} must be positive", size));
+        }
+
+        if !price.is_positive() {
+            return Some(format!("Order price {} must be positive", price));
+        }
+
+        if let Some(instruments) = &self.instruments {
+            let Some(instrument) = instruments.get(symbol) else {
+                return Some(format!("Unknown symbol {}", symbol));
+            };
+            return instrument.validate(price, size).err();
+        }
+
+        None
+    }
+
+    /// Simulates fills of resting orders against the current book, best priced orders
+    /// first. Book liquidity taken by one order is not available to the next, so a ladder
+    /// of orders crossing the same levels is not filled more than the book allows. Orders
+    /// are filled on top of the sizes in `filled`, which are updated.
+    fn simulate_pending_order_fills(&self, filled: &mut BTreeMap<usize, Decimal>) -> Vec<Fill> {
         let mut fills = Vec::new();
         let pending_orders = self.pending_orders.get_inner();
 
         if let Some(best_bid) = self.orderbook.get_inner().best_bid() {
+            let mut consumed = BTreeMap::new();
             for pending_ask in pending_orders.asks_iter() {
                 if pending_ask.price > best_bid {
                     break; // No more pending asks can be filled
                 }
 
-                fills.extend(self.simulate_fills(pending_ask, true));
+                fills.extend(self.simulate_remaining_fills(pending_ask, &mut consumed, filled));
             }
         }
 
         if let Some(best_ask) = self.orderbook.get_inner().best_ask() {
-            for pending_bid in pending_orders.bids_iter() {
+            let mut consumed = BTreeMap::new();
+            for pending_bid in pending_orders.bids_iter_desc() {
                 if pending_bid.price < best_ask {
                     break; // No more pending bids can be filled
                 }
 
-                fills.extend(self.simulate_fills(pending_bid, true));
+                fills.extend(self.simulate_remaining_fills(pending_bid, &mut consumed, filled));
             }
         }
 
         fills
     }
 
-    fn simulate_fills(&self, order: &Order, is_maker: bool) -> Vec<Fill> {
-        let inner = self.orderbook.get_inner();
-        let (fills, _) = match order.side {
-            Side::Bid => inner.simulate_buy(order.price, order.size),
-            Side::Ask => inner.simulate_sell(order.price, order.size),
-        };
+    /// Simulates maker fills of resting orders that market trades traded through: a taker
+    /// buy at or above an ask, or a taker sell at or below a bid, fills it with up to the
+    /// trade size, best priced orders first. Used with a trade flow, so that passive quotes
+    /// are filled by the flow hitting them and not only when the book crosses them.
+    /// Sizes filled per order are added to `filled`.
+    fn simulate_trade_fills(
+        &self,
+        trades: &[Trade],
+        filled: &mut BTreeMap<usize, Decimal>,
+    ) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        let pending_orders = self.pending_orders.get_inner();
+
+        for trade in trades {
+            let resting: Box<dyn Iterator<Item = &Order>> = match trade.side {
+                Side::Bid => Box::new(
+                    pending_orders
+                        .asks_iter()
+                        .take_while(|ask| ask.price <= trade.price),
+                ),
+                Side::Ask => Box::new(
+                    pending_orders
+                        .bids_iter_desc()
+                        .take_while(|bid| bid.price >= trade.price),
+                ),
+            };
+
+            let mut remaining = trade.size;
+            for order in resting.filter(|order| order.symbol == trade.symbol) {
+                if !remaining.is_positive() {
+                    break;
+                }
+
+                let already_filled = filled.entry(order.oid).or_insert(Decimal::ZERO);
+                let size = (order.size - *already_filled).min(remaining);
+                if !size.is_positive() {
+                    continue;
+                }
+
+                *already_filled += size;
+                remaining -= size;
+                fills.push(Fill {
+                    oid: order.oid,
+                    side: order.side,
+                    price: order.price,
+                    size,
+                    is_maker: true,
+                    timestamp: trade.timestamp,
+                });
+            }
+        }
+
+        fills
+    }
+
+    /// Simulates maker fills of the size of a resting order not yet in `filled`.
+    fn simulate_remaining_fills(
+        &self,
+        order: &Order,
+        consumed: &mut BTreeMap<Decimal, Decimal>,
+        filled: &mut BTreeMap<usize, Decimal>,
+    ) -> Vec<Fill> {
+        let already_filled = filled.entry(order.oid).or_insert(Decimal::ZERO);
+        let remaining = order.size - *already_filled;
+        if !remaining.is_positive() {
+            return Vec::new();
+        }
+
+        let fills = self.simulate_fills(order, remaining, true, consumed);
+        *already_filled += fills.iter().map(|fill| fill.size).sum::<Decimal>();
+        fills
+    }
+
+    fn simulate_fills(
+        &self,
+        order: &Order,
+        size: Decimal,
+        is_maker: bool,
+        consumed: &mut BTreeMap<Decimal, Decimal>,
+    ) -> Vec<Fill> {
+        let (fills, _) = self.orderbook.get_inner().simulate_with_consumed(
+            order.side,
+            order.price,
+            size,
+            consumed,
+        );
 
         let timestamp = chrono::Utc::now().timestamp_millis() as u64;
 
@@ -219,8 +466,8 @@ impl PaperExchange {
         summary.push_str("🟢 Pending Orders:\n");
         for order in self.pending_orders.get_inner().iter() {
             summary.push_str(&format!(
-                "=> Side: {}, Price: {}, Size: {:?}\n",
-                order.side, order.price, order.size
+                "=> Side: {}, Price: {}, Size: {:?}, Status: {}\n",
+                order.side, order.price, order.size, order.status
             ));
         }
         summary.push_str("🎯 Bot Position:\n");
@@ -238,6 +485,32 @@ impl PaperExchange {
             "=> Unrealized PnL: {}\n",
             self.bot_position.get_inner().unrealized_pnl(final_price)
         ));
+        summary.push_str(&format!(
+            "=> Realized PnL: {}\n",
+            self.bot_position.get_inner().realized_pnl
+        ));
+        if let Some(trade_flow) = &self.trade_flow {
+            summary.push_str("🌊 Market Trade Flow:\n");
+            for flow in trade_flow.get_flows() {
+                summary.push_str(&format!(
+                    "=> Window: {}ms, Buy Volume: {}, Sell Volume: {}, Trades/s: {}\n",
+                    flow.window(),
+                    flow.buy_volume(),
+                    flow.sell_volume(),
+                    flow.arrival_rate()
+                ));
+            }
+        }
+        if let Some(trigger) = self.kill_switch.as_ref().and_then(|k| k.trigger_info()) {
+            let triggered_at = chrono::DateTime::from_timestamp_millis(trigger.triggered_at as i64)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| trigger.triggered_at.to_string());
+            summary.push_str("🛑 Kill Switch:\n");
+            summary.push_str(&format!(
+                "=> Triggered At: {}, Reason: {}\n",
+                triggered_at, trigger.reason
+            ));
+        }
 
         Ok(summary)
     }
@@ -248,3 +521,161 @@ impl Default for PaperExchange {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clients::BybitTradeUpdate;
+    use hayate_core::traits::CollectorStream;
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::{
+        collector::{bybit_collector::trade_update, paper_collector::PaperCollector},
+        models::{OrderBookEventKind, OrderBookUpdate},
+    };
+
+    struct Disconnected;
+
+    #[async_trait::async_trait]
+    impl Collector<InternalEvent> for Disconnected {
+        async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, InternalEvent>> {
+            Ok(Box::pin(tokio_stream::empty()))
+        }
+    }
+
+    fn place(cloid: &str) -> PaperExchangeMessage {
+        PaperExchangeMessage::PlaceOrder(PlaceOrder {
+            cloid: cloid.to_string(),
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Bid,
+            price: 100.into(),
+            size: 1.into(),
+        })
+    }
+
+    #[test]
+    fn test_orders_without_subscribers() {
+        // No paper collector listens, and the cancel is rejected
+        let mut paper_exchange = PaperExchange::new();
+        paper_exchange.process_msg(place("first"));
+        paper_exchange.process_msg(PaperExchangeMessage::CancelOrder(CancelOrder {
+            symbol: "BTCUSDT".to_string(),
+            oid: 42,
+        }));
+        paper_exchange.process_msg(place("second"));
+
+        assert_eq!(paper_exchange.pending_orders.get_inner().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stops_without_market_data() {
+        let mut paper_exchange = PaperExchange::new();
+        let paper_collector = PaperCollector::new(paper_exchange.subscribe());
+        let (_msg_tx, msg_rx) = mpsc::unbounded_channel();
+
+        let result = paper_exchange
+            .run_with_shutdown(Disconnected, msg_rx, CancellationToken::new())
+            .await;
+        assert!(result.is_err());
+
+        // Collectors of the bot see the exchange go away
+        drop(paper_exchange);
+        let mut stream = paper_collector.get_event_stream().await.unwrap();
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn test_trade_through_fills() {
+        let mut paper_exchange =
+            PaperExchange::new().with_trade_flow(TradeFlowState::new().with_window(1000));
+        for (cloid, side, price) in [("ask", Side::Ask, 101), ("bid", Side::Bid, 99)] {
+            paper_exchange.process_msg(PaperExchangeMessage::PlaceOrder(PlaceOrder {
+                cloid: cloid.to_string(),
+                symbol: "BTCUSDT".to_string(),
+                side,
+                price: price.into(),
+                size: 2.into(),
+            }));
+        }
+
+        let trades = [(Side::Bid, 102, "1.5"), (Side::Ask, 100, "3")]
+            .into_iter()
+            .map(|(side, price, size)| Trade {
+                symbol: "BTCUSDT".to_string(),
+                side,
+                price: price.into(),
+                size: Decimal::from_str_unchecked(size),
+                timestamp: 0,
+            })
+            .collect();
+        paper_exchange.process_event(InternalEvent::TradeUpdate(trades));
+
+        // The taker buy traded through the ask, the taker sell stayed above the bid
+        let pending_orders = paper_exchange.pending_orders.get_inner();
+        assert_eq!(
+            pending_orders.get_order(1).unwrap().size,
+            Decimal::from_str_unchecked("0.5")
+        );
+        assert_eq!(pending_orders.get_order(2).unwrap().size, 2.into());
+        assert_eq!(
+            paper_exchange
+                .trade_flow
+                .as_ref()
+                .unwrap()
+                .get_flow(1000)
+                .unwrap()
+                .trade_count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_trade_and_book_fills_share_the_order() {
+        let mut paper_exchange =
+            PaperExchange::new().with_trade_flow(TradeFlowState::new().with_window(1000));
+        paper_exchange.process_event(InternalEvent::OrderBookUpdate(OrderBookUpdate {
+            symbol: "BTCUSDT".to_string(),
+            kind: OrderBookEventKind::Snapshot,
+            updated_at: 0,
+            bids: vec![(101.into(), Decimal::from_str_unchecked("0.5"))],
+            asks: vec![(102.into(), 1.into())],
+        }));
+
+        // The ask crosses the bid, 0.5 is filled on placement
+        paper_exchange.process_msg(PaperExchangeMessage::PlaceOrder(PlaceOrder {
+            cloid: "ask".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Ask,
+            price: 100.into(),
+            size: 2.into(),
+        }));
+
+        // A taker buy as sent by the Bybit collector trades through the remaining 1.5,
+        // while the bid still crosses the ask
+        let update: BybitTradeUpdate = serde_json::from_str(
+            r#"{
+                "topic": "publicTrade.BTCUSDT",
+                "ts": 1672304486868,
+                "type": "snapshot",
+                "data": [{
+                    "T": 1672304486865,
+                    "s": "BTCUSDT",
+                    "S": "Buy",
+                    "v": "1.5",
+                    "p": "101",
+                    "i": "20f43950-d8dd-5b31-9112-a178eb6023af",
+                    "BT": false,
+                    "RPI": false
+                }]
+            }"#,
+        )
+        .unwrap();
+        paper_exchange.process_event(trade_update(update).unwrap());
+
+        assert_eq!(
+            paper_exchange.pending_orders.get_status(1),
+            Some(OrderStatus::Filled)
+        );
+        assert_eq!(paper_exchange.bot_position.get_inner().size, 2.into());
+    }
+}
@@ -1,7 +1,21 @@
-use crate::models::{CancelOrder, PlaceOrder};
+use crate::{
+    executor::risk_executor::{OrderRequest, RiskAction},
+    models::{AmendOrder, CancelOrder, PlaceOrder},
+};
 
 pub enum PaperExchangeMessage {
     PlaceOrder(PlaceOrder),
     CancelOrder(CancelOrder),
+    AmendOrder(AmendOrder),
     Close,
 }
+
+impl RiskAction for PaperExchangeMessage {
+    fn order_request(&self) -> Option<OrderRequest<'_>> {
+        match self {
+            PaperExchangeMessage::PlaceOrder(order) => Some(OrderRequest::Place(order)),
+            PaperExchangeMessage::AmendOrder(order) => Some(OrderRequest::Amend(order)),
+            PaperExchangeMessage::CancelOrder(_) | PaperExchangeMessage::Close => None,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::{HashMap, VecDeque};
+
+use hayate_core::traits::State;
+
+use crate::models::{Candle, CandleAggregator, CandleInterval, Indicator, InternalEvent};
+
+/// Where the bars of a series come from.
+#[derive(Debug)]
+enum CandleSource {
+    /// Aggregated locally from trades
+    Trades(CandleAggregator),
+    /// Provided by the venue (e.g. Bybit klines), with the bar still being built
+    Venue(Option<Candle>),
+}
+
+#[derive(Debug)]
+struct CandleSeries {
+    source: CandleSource,
+    history: VecDeque<Candle>,
+    /// Indicators updated on every closed bar
+    indicators: HashMap<String, Box<dyn Indicator>>,
+}
+
+impl CandleSeries {
+    fn new(source: CandleSource) -> Self {
+        Self {
+            source,
+            history: VecDeque::new(),
+            indicators: HashMap::new(),
+        }
+    }
+
+    fn current(&self) -> Option<&Candle> {
+        match &self.source {
+            CandleSource::Trades(aggregator) => aggregator.current(),
+            CandleSource::Venue(current) => current.as_ref(),
+        }
+    }
+
+    fn close(&mut self, candle: Candle, history_len: usize) {
+        for indicator in self.indicators.values_mut() {
+            indicator.update_candle(&candle);
+        }
+
+        if self.history.len() == history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(candle);
+    }
+}
+
+/// OHLCV bars of `symbol` at several intervals, keeping the last `history_len` closed
+/// bars of each. Trades and candle updates of other symbols are ignored.
+#[derive(Debug)]
+pub struct CandleState {
+    symbol: String,
+    series: HashMap<CandleInterval, CandleSeries>,
+    history_len: usize,
+}
+
+#[async_trait::async_trait]
+impl State<InternalEvent> for CandleState {
+    fn name(&self) -> &str {
+        "candle"
+    }
+
+    async fn sync(&mut self) -> anyhow::Result<()> {
+        // Bars are only built from live data, indicators warm up as they close
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        match event {
+            InternalEvent::TradeUpdate(trades) => {
+                for series in self.series.values_mut() {
+                    let CandleSource::Trades(aggregator) = &mut series.source else {
+                        continue;
+                    };
+                    let closed: Vec<Candle> = trades
+                        .iter()
+                        .filter(|trade| trade.symbol == self.symbol)
+                        .flat_map(|trade| {
+                            aggregator.update(trade.price, trade.size, trade.timestamp)
+                        })
+                        .collect();
+                    for candle in closed {
+                        series.close(candle, self.history_len);
+                    }
+                }
+            }
+            InternalEvent::CandleUpdate(update) if update.symbol == self.symbol => {
+                if let Some(series) = self.series.get_mut(&update.interval) {
+                    if let CandleSource::Venue(current) = &mut series.source {
+                        if update.closed {
+                            *current = None;
+                            series.close(update.candle, self.history_len);
+                        } else {
+                            *current = Some(update.candle);
+                        }
+                    }
+                }
+            }
+            InternalEvent::CandleUpdate(_)
+            | InternalEvent::OrderBookUpdate(_)
+            | InternalEvent::OrderPendingNew(_)
+            | InternalEvent::OrderPlaced(_)
+            | InternalEvent::OrderFilled(_)
+            | InternalEvent::OrderAmended(_)
+            | InternalEvent::OrderPendingCancel(_)
+            | InternalEvent::OrderCancelled(_)
+            | InternalEvent::OrderRejected(_) => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl CandleState {
+    pub fn new(symbol: impl Into<String>, history_len: usize) -> Self {
+        Self {
+            symbol: symbol.into(),
+            series: HashMap::new(),
+            history_len,
+        }
+    }
+
+    /// Aggregates trades into bars of `interval`.
+    pub fn with_interval(mut self, interval: CandleInterval) -> Self {
+        let aggregator = CandleAggregator::new(interval);
+        self.series.insert(
+            interval,
+            CandleSeries::new(CandleSource::Trades(aggregator)),
+        );
+        self
+    }
+
+    /// Takes bars of `interval` from the venue candle updates rather than from trades.
+    pub fn with_venue_interval(mut self, interval: CandleInterval) -> Self {
+        self.series
+            .insert(interval, CandleSeries::new(CandleSource::Venue(None)));
+        self
+    }
+
+    /// Subscribes an indicator to the closed bars of `interval`.
+    pub fn add_indicator(
+        &mut self,
+        interval: CandleInterval,
+        indicator: Box<dyn Indicator>,
+    ) -> anyhow::Result<()> {
+        let series = self
+            .series
+            .get_mut(&interval)
+            .ok_or_else(|| anyhow::anyhow!("No {} candles in candle state", interval))?;
+        series
+            .indicators
+            .insert(indicator.name().to_string(), indicator);
+        Ok(())
+    }
+
+    pub fn get_indicator(&self, interval: CandleInterval, name: &str) -> Option<&dyn Indicator> {
+        self.series
+            .get(&interval)?
+            .indicators
+            .get(name)
+            .map(|ind| ind.as_ref())
+    }
+
+    /// The first indicator of type `T` subscribed to `interval`, by name.
+    pub fn find<T: Indicator>(&self, interval: CandleInterval) -> Option<&T> {
+        let indicators = &self.series.get(&interval)?.indicators;
+        let mut names: Vec<&String> = indicators.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .find_map(|name| indicators[name].downcast_ref())
+    }
+
+    /// Closed bars, oldest first.
+    pub fn get_candles(&self, interval: CandleInterval) -> Option<&VecDeque<Candle>> {
+        self.series.get(&interval).map(|series| &series.history)
+    }
+
+    pub fn get_last_candle(&self, interval: CandleInterval) -> Option<&Candle> {
+        self.get_candles(interval)?.back()
+    }
+
+    /// The bar still being built.
+    pub fn get_current_candle(&self, interval: CandleInterval) -> Option<&Candle> {
+        self.series.get(&interval)?.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CandleUpdate, Decimal, Natr, Side, Trade};
+
+    fn trade(price: u64, timestamp: u64) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Bid,
+            price: price.into(),
+            size: 1.into(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_candle_state_aggregates_trades() {
+        let second = CandleInterval::Time(1000);
+        let ticks = CandleInterval::Ticks(2);
+        let mut state = CandleState::new("BTCUSDT", 2)
+            .with_interval(second)
+            .with_interval(ticks);
+        state
+            .add_indicator(ticks, Box::new(Natr::new(1, 1)))
+            .unwrap();
+        assert!(state
+            .add_indicator(CandleInterval::Time(60_000), Box::new(Natr::new(1, 1)))
+            .is_err());
+
+        // Trades of another symbol are not aggregated
+        let mut trades: Vec<Trade> = (0..7).map(|i| trade(100 + i, i * 1000)).collect();
+        trades.push(Trade {
+            symbol: "ETHUSDT".to_string(),
+            ..trade(1, 6500)
+        });
+        state
+            .process_event(InternalEvent::TradeUpdate(trades))
+            .unwrap();
+
+        // Only the last two closed bars are kept
+        let candles = state.get_candles(second).unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].open_time, 4000);
+        assert_eq!(state.get_last_candle(second).unwrap().close, 105.into());
+        assert_eq!(state.get_current_candle(second).unwrap().open, 106.into());
+        assert_eq!(state.get_current_candle(second).unwrap().low, 106.into());
+
+        assert_eq!(state.get_last_candle(ticks).unwrap().close, 105.into());
+        assert!(state
+            .get_indicator(ticks, "natr_1_1ms")
+            .unwrap()
+            .value()
+            .is_some());
+        assert!(state.find::<Natr>(ticks).unwrap().is_ready());
+        assert!(state.find::<Natr>(second).is_none());
+    }
+
+    #[test]
+    fn test_candle_state_venue_candles() {
+        let minute = CandleInterval::Time(60_000);
+        let mut state = CandleState::new("BTCUSDT", 10).with_venue_interval(minute);
+
+        let mut candle = Candle::new(0, 100.into(), 1.into(), 10);
+        let mut update = |symbol: &str, candle: Candle, closed: bool| {
+            state
+                .process_event(InternalEvent::CandleUpdate(CandleUpdate {
+                    symbol: symbol.to_string(),
+                    interval: minute,
+                    candle,
+                    closed,
+                }))
+                .unwrap();
+        };
+        update("BTCUSDT", candle, false);
+        candle.update(Decimal::from(101), 1.into(), 59_999);
+        update("BTCUSDT", candle, true);
+        update("ETHUSDT", Candle::new(60_000, 1.into(), 1.into(), 10), true);
+
+        // Trades are not aggregated into venue candles
+        state
+            .process_event(InternalEvent::TradeUpdate(vec![trade(102, 60_000)]))
+            .unwrap();
+
+        assert_eq!(state.get_candles(minute).unwrap().len(), 1);
+        assert_eq!(state.get_last_candle(minute).unwrap().high, 101.into());
+        assert!(state.get_current_candle(minute).is_none());
+    }
+}
@@ -1,10 +1,23 @@
+use std::collections::BTreeMap;
+
 use hayate_core::traits::State;
 
-use crate::models::{Decimal, InternalEvent, OrderBook, OrderBookEventKind, Side};
+use crate::models::{
+    BookFeature, BookFeatureKind, BookSnapshotSource, Decimal, Indicator, InternalEvent, OrderBook,
+    OrderBookEventKind, Side, TradeIntensity,
+};
 
 #[derive(Debug)]
 pub struct OrderBookState {
     inner: OrderBook,
+    /// Order-arrival intensity estimated from trades against the current mid price
+    trade_intensity: Option<TradeIntensity>,
+    /// Indicators updated after every order-book update, keyed by their instance name
+    features: BTreeMap<String, Box<dyn Indicator>>,
+    /// Set when updates were lost, the book stays empty until the next snapshot
+    stale: bool,
+    /// Where to fetch a snapshot from when updates were lost
+    snapshot_source: Option<Box<dyn BookSnapshotSource>>,
 }
 
 #[async_trait::async_trait]
@@ -14,24 +27,71 @@ impl State<InternalEvent> for OrderBookState {
     }
 
     async fn sync(&mut self) -> anyhow::Result<()> {
-        // TODO: Implement sync logic
+        // The book is built from the snapshot the collector sends on subscription
+        Ok(())
+    }
+
+    async fn resync(&mut self) -> anyhow::Result<()> {
+        // Deltas only apply to the book they follow, drop it until the next snapshot
+        self.inner.reset();
+        self.stale = true;
+
+        let Some(source) = &self.snapshot_source else {
+            tracing::warn!("Order book updates lost, waiting for a new snapshot");
+            return Ok(());
+        };
+        // Bybit deltas carry absolute level sizes, so deltas older than the fetched
+        // snapshot that are still queued only briefly revert the levels they touch
+        match source.fetch().await {
+            Ok(snapshot) => {
+                tracing::info!("Order book updates lost, rebuilt from a fetched snapshot");
+                self.process_event(InternalEvent::OrderBookUpdate(snapshot))?;
+            }
+            Err(e) => tracing::warn!(
+                "Order book updates lost, waiting for a new snapshot as none could be fetched: {}",
+                e
+            ),
+        }
         Ok(())
     }
 
     fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
         match event {
-            InternalEvent::OrderBookUpdate(event) => match event.kind {
-                OrderBookEventKind::Snapshot => {
-                    self.update_snapshot(event.symbol, event.bids, event.asks)?;
+            InternalEvent::OrderBookUpdate(event) => {
+                match event.kind {
+                    OrderBookEventKind::Snapshot => {
+                        self.update_snapshot(event.symbol, event.bids, event.asks)?;
+                        self.stale = false;
+                    }
+                    OrderBookEventKind::Delta if self.stale => {
+                        tracing::debug!("Skipping order book delta until the next snapshot");
+                        return Ok(());
+                    }
+                    OrderBookEventKind::Delta => {
+                        self.update_delta(event.symbol, event.bids, event.asks)?;
+                    }
+                }
+                for feature in self.features.values_mut() {
+                    feature.update_book(&self.inner, event.updated_at);
                 }
-                OrderBookEventKind::Delta => {
-                    self.update_delta(event.symbol, event.bids, event.asks)?;
+            }
+            InternalEvent::TradeUpdate(trades) => {
+                if let (Some(intensity), Some(mid_price)) =
+                    (&mut self.trade_intensity, self.inner.mid_price())
+                {
+                    for trade in trades {
+                        intensity.update(trade.price, mid_price);
+                    }
                 }
-            },
+            }
             InternalEvent::OrderFilled(_)
+            | InternalEvent::OrderPendingNew(_)
             | InternalEvent::OrderPlaced(_)
+            | InternalEvent::OrderAmended(_)
+            | InternalEvent::OrderPendingCancel(_)
             | InternalEvent::OrderCancelled(_)
-            | InternalEvent::TradeUpdate(_) => {}
+            | InternalEvent::OrderRejected(_)
+            | InternalEvent::CandleUpdate(_) => {}
         }
 
         Ok(())
@@ -42,7 +102,58 @@ impl OrderBookState {
     pub fn new(max_depth: usize) -> Self {
         Self {
             inner: OrderBook::new(max_depth),
+            trade_intensity: None,
+            features: BTreeMap::new(),
+            stale: false,
+            snapshot_source: None,
+        }
+    }
+
+    /// Fetches a snapshot from `source` when updates were lost, rather than waiting for
+    /// the venue to send one.
+    pub fn with_snapshot_source(mut self, source: Box<dyn BookSnapshotSource>) -> Self {
+        self.snapshot_source = Some(source);
+        self
+    }
+
+    /// Adds a feature, e.g. a [`BookFeature`], updated with the book after every update.
+    pub fn with_feature(mut self, feature: Box<dyn Indicator>) -> Self {
+        let name = feature.name().to_string();
+        if self.features.insert(name.clone(), feature).is_some() {
+            tracing::warn!("Replaced feature {} in order book state", name);
         }
+        self
+    }
+
+    pub fn get_feature(&self, name: &str) -> Option<&dyn Indicator> {
+        self.features.get(name).map(|feature| feature.as_ref())
+    }
+
+    /// The first book feature of `kind` by name, the unsmoothed one if registered.
+    pub fn find_feature(&self, kind: BookFeatureKind) -> Option<&BookFeature> {
+        self.features
+            .values()
+            .filter_map(|feature| feature.downcast_ref::<BookFeature>())
+            .find(|feature| feature.kind() == kind)
+    }
+
+    pub fn get_features(&self) -> &BTreeMap<String, Box<dyn Indicator>> {
+        &self.features
+    }
+
+    /// Enables estimating the order-arrival intensity over the last `window` trades.
+    pub fn with_trade_intensity(mut self, window: usize) -> Self {
+        self.trade_intensity = Some(TradeIntensity::new(window));
+        self
+    }
+
+    pub fn get_trade_intensity(&self) -> Option<Decimal> {
+        self.trade_intensity.as_ref().and_then(|i| i.value())
+    }
+
+    /// Whether updates were lost since the last snapshot, the book is then empty.
+    pub fn is_stale(&self) -> bool {
+        self.stale
     }
 
     pub fn get_mid_price(&self) -> Option<Decimal> {
@@ -96,3 +207,157 @@ impl OrderBookState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hayate_core::registry::StateRegistry;
+
+    use crate::models::{Ema, OrderBookUpdate};
+
+    fn update(kind: OrderBookEventKind, bid: (u64, u64), ask: (u64, u64)) -> OrderBookUpdate {
+        OrderBookUpdate {
+            symbol: "BTCUSDT".to_string(),
+            kind,
+            updated_at: 0,
+            bids: vec![(bid.0.into(), bid.1.into())],
+            asks: vec![(ask.0.into(), ask.1.into())],
+        }
+    }
+
+    fn book_update(kind: OrderBookEventKind, bid: (u64, u64), ask: (u64, u64)) -> InternalEvent {
+        InternalEvent::OrderBookUpdate(update(kind, bid, ask))
+    }
+
+    #[tokio::test]
+    async fn test_lost_updates_wait_for_snapshot() {
+        let mut states = StateRegistry::new();
+        let book = states.register(OrderBookState::new(5));
+
+        states
+            .apply(book_update(
+                OrderBookEventKind::Snapshot,
+                (100, 1),
+                (102, 1),
+            ))
+            .await;
+        assert_eq!(book.read().await.get_mid_price(), Some(101.into()));
+
+        // Deltas were dropped, the next ones would apply to a stale book
+        states.resync(2).await.unwrap();
+        states
+            .apply(book_update(OrderBookEventKind::Delta, (101, 1), (103, 1)))
+            .await;
+        {
+            let book = book.read().await;
+            assert!(book.is_stale());
+            assert_eq!(book.get_mid_price(), None);
+            assert_eq!(book.get_inner().bids_depth(), 0);
+        }
+        assert_eq!(states.stats()[0].dropped, 2);
+
+        states
+            .apply(book_update(
+                OrderBookEventKind::Snapshot,
+                (104, 1),
+                (106, 1),
+            ))
+            .await;
+        let book = book.read().await;
+        assert!(!book.is_stale());
+        assert_eq!(book.get_mid_price(), Some(105.into()));
+    }
+
+    #[derive(Debug)]
+    struct FixedSnapshot;
+
+    #[async_trait::async_trait]
+    impl BookSnapshotSource for FixedSnapshot {
+        async fn fetch(&self) -> anyhow::Result<OrderBookUpdate> {
+            Ok(update(OrderBookEventKind::Snapshot, (110, 1), (112, 1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lost_updates_fetch_snapshot() {
+        let mut states = StateRegistry::new();
+        let book =
+            states.register(OrderBookState::new(5).with_snapshot_source(Box::new(FixedSnapshot)));
+
+        states
+            .apply(book_update(
+                OrderBookEventKind::Snapshot,
+                (100, 1),
+                (102, 1),
+            ))
+            .await;
+
+        // The book recovers without waiting for the venue, and deltas apply again
+        states.resync(2).await.unwrap();
+        assert!(!book.read().await.is_stale());
+        assert_eq!(book.read().await.get_mid_price(), Some(111.into()));
+
+        states
+            .apply(book_update(OrderBookEventKind::Delta, (111, 1), (113, 1)))
+            .await;
+        assert_eq!(
+            book.read().await.get_mid_price(),
+            Some(Decimal::from_str_unchecked("111.5"))
+        );
+    }
+
+    #[test]
+    fn test_features_follow_book_updates() {
+        let imbalance = BookFeatureKind::Imbalance { levels: 2 };
+        let mut state = OrderBookState::new(5)
+            .with_feature(Box::new(BookFeature::new(imbalance).with_ema(3)))
+            .with_feature(Box::new(BookFeature::new(imbalance)))
+            .with_feature(Box::new(BookFeature::new(BookFeatureKind::Microprice)))
+            .with_feature(Box::new(Ema::new(1, 1)));
+
+        let mut update = |kind, bids: Vec<(u64, u64)>, asks: Vec<(u64, u64)>, updated_at| {
+            let levels = |levels: Vec<(u64, u64)>| {
+                levels
+                    .into_iter()
+                    .map(|(price, size)| (price.into(), size.into()))
+                    .collect()
+            };
+            state
+                .process_event(InternalEvent::OrderBookUpdate(OrderBookUpdate {
+                    symbol: "BTCUSDT".to_string(),
+                    kind,
+                    updated_at,
+                    bids: levels(bids),
+                    asks: levels(asks),
+                }))
+                .unwrap();
+        };
+        update(
+            OrderBookEventKind::Snapshot,
+            vec![(100, 1), (99, 1)],
+            vec![(101, 2)],
+            0,
+        );
+        update(OrderBookEventKind::Delta, vec![(100, 0)], vec![(102, 1)], 1);
+        update(OrderBookEventKind::Delta, vec![(98, 2)], vec![], 2);
+
+        // (1 + 2 - 2 - 1) / 6 after the last update
+        let feature = state.find_feature(imbalance).unwrap();
+        assert_eq!(feature.name(), "imbalance_2");
+        assert_eq!(feature.value(), Some(Decimal::ZERO));
+        assert!(state.get_feature("imbalance_2_ema_3").unwrap().is_ready());
+        // (99 * 2 + 101 * 1) / 3
+        assert_eq!(
+            state.get_feature("microprice").unwrap().value(),
+            Some(Decimal::from_str_unchecked("99.666666"))
+        );
+        // Price indicators follow the mid price
+        assert_eq!(
+            state.get_feature("ema_1_1ms").unwrap().value(),
+            Some(100.into())
+        );
+        assert!(state
+            .find_feature(BookFeatureKind::Imbalance { levels: 1 })
+            .is_none());
+    }
+}
@@ -1,10 +1,18 @@
+use std::collections::VecDeque;
+
 use hayate_core::traits::State;
 
-use crate::models::{Fill, InternalEvent, Order, OrderCollection};
+use crate::models::{Fill, InternalEvent, Order, OrderCollection, OrderRejection, OrderStatus};
 
+/// Tracks the bot's open orders through their lifecycle. Orders that reach a
+/// terminal status are moved out of the open collection into a bounded history,
+/// so their final status can still be looked up.
 #[derive(Debug, Default)]
 pub struct PendingOrdersState {
     inner: OrderCollection,
+    closed: VecDeque<Order>,
+    /// Latest rejections, including those of orders rejected before reaching the venue
+    rejections: VecDeque<OrderRejection>,
 }
 
 #[async_trait::async_trait]
@@ -20,16 +28,30 @@ impl State<InternalEvent> for PendingOrdersState {
 
     fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
         match event {
+            InternalEvent::OrderPendingNew(order) => {
+                self.add_order(order, OrderStatus::PendingNew);
+            }
             InternalEvent::OrderPlaced(order) => {
-                self.add_order(order);
+                self.add_order(order, OrderStatus::New);
             }
             InternalEvent::OrderFilled(fill) => {
                 self.apply_fill(&fill)?;
             }
+            InternalEvent::OrderAmended(order) => {
+                self.amend_order(order);
+            }
+            InternalEvent::OrderPendingCancel(order) => {
+                self.mark_pending_cancel(order.oid);
+            }
             InternalEvent::OrderCancelled(order) => {
                 self.cancel_order(order.oid);
             }
-            InternalEvent::OrderBookUpdate(_) | InternalEvent::TradeUpdate(_) => {}
+            InternalEvent::OrderRejected(rejection) => {
+                self.apply_rejection(&rejection);
+            }
+            InternalEvent::OrderBookUpdate(_)
+            | InternalEvent::TradeUpdate(_)
+            | InternalEvent::CandleUpdate(_) => {}
         }
 
         Ok(())
@@ -37,9 +59,14 @@ impl State<InternalEvent> for PendingOrdersState {
 }
 
 impl PendingOrdersState {
+    /// Maximum number of closed orders, and of rejections, kept for lookups.
+    pub const MAX_CLOSED_ORDERS: usize = 1024;
+
     pub fn new() -> Self {
         Self {
             inner: OrderCollection::default(),
+            closed: VecDeque::new(),
+            rejections: VecDeque::new(),
         }
     }
 
@@ -51,22 +78,269 @@ impl PendingOrdersState {
         &mut self.inner
     }
 
-    pub fn add_order(&mut self, order: Order) {
+    pub fn get_closed_orders(&self) -> &VecDeque<Order> {
+        &self.closed
+    }
+
+    pub fn get_rejections(&self) -> &VecDeque<OrderRejection> {
+        &self.rejections
+    }
+
+    /// Looks up an order by client order id, in the open orders first and then
+    /// in the closed order history.
+    pub fn get_order_by_cloid(&self, cloid: &str) -> Option<&Order> {
+        self.inner.get_order_by_cloid(cloid).or_else(|| {
+            self.closed
+                .iter()
+                .rev()
+                .find(|order| order.cloid.as_deref() == Some(cloid))
+        })
+    }
+
+    /// Open orders as reported by `Bot::open_orders` when shutdown could not cancel
+    /// them.
+    pub fn open_orders(&self) -> Vec<String> {
+        self.inner
+            .iter()
+            .map(|order| order.oid.to_string())
+            .collect()
+    }
+
+    pub fn get_status(&self, oid: usize) -> Option<OrderStatus> {
+        if let Some(order) = self.inner.get_order(oid) {
+            return Some(order.status);
+        }
+
+        self.closed
+            .iter()
+            .rev()
+            .find(|order| order.oid == oid)
+            .map(|order| order.status)
+    }
+
+    /// Adds an order with `status`. If the order is already known past `PendingNew`, as
+    /// when a late `OrderPlaced` arrives after a fill, amend or cancel request, it keeps
+    /// the sizes and status set by those later events.
+    pub fn add_order(&mut self, mut order: Order, status: OrderStatus) {
+        // Later events may have been applied before the acknowledgement arrived, their
+        // sizes and status are newer than the acknowledged ones
+        if let Some(existing) = self.inner.get_order(order.oid) {
+            order.size = existing.size;
+            order.filled_size = existing.filled_size;
+            if existing.status != OrderStatus::PendingNew {
+                order.status = existing.status;
+                self.inner.insert(order);
+                return;
+            }
+        }
+
+        order.status = status;
         self.inner.insert(order);
     }
 
     pub fn apply_fill(&mut self, fill: &Fill) -> anyhow::Result<()> {
-        if !self.inner.reduce_order_size(fill.oid, fill.size) {
+        if !fill.size.is_positive() {
             return Err(anyhow::anyhow!(
-                "Failed to reduce order size for OID: {}",
+                "Fill size must be positive for OID: {}",
                 fill.oid
             ));
         }
 
+        let order = self
+            .inner
+            .get_order_mut(fill.oid)
+            .ok_or_else(|| anyhow::anyhow!("Failed to reduce order size for OID: {}", fill.oid))?;
+
+        let fill_size = fill.size.min(order.size);
+        order.size -= fill_size;
+        order.filled_size += fill_size;
+
+        if order.size.is_zero() {
+            self.close_order(fill.oid, OrderStatus::Filled);
+        } else if order.status != OrderStatus::PendingCancel {
+            order.status = OrderStatus::PartiallyFilled;
+        }
+
         Ok(())
     }
 
+    /// Updates the price and remaining size of an open order, keeping its fill progress.
+    pub fn amend_order(&mut self, order: Order) -> bool {
+        let Some(existing) = self.inner.get_order(order.oid) else {
+            return false;
+        };
+
+        let amended = Order {
+            price: order.price,
+            size: order.size,
+            ..existing.clone()
+        };
+        self.inner.insert(amended);
+        true
+    }
+
+    pub fn mark_pending_cancel(&mut self, oid: usize) -> bool {
+        match self.inner.get_order_mut(oid) {
+            Some(order) => {
+                order.status = OrderStatus::PendingCancel;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn cancel_order(&mut self, oid: usize) -> Option<Order> {
-        self.inner.remove_by_oid(oid)
+        self.close_order(oid, OrderStatus::Cancelled)
+    }
+
+    /// Applies a rejection to the order it refers to. A rejected new order is closed,
+    /// while a rejected cancel puts the order back into its working status.
+    pub fn apply_rejection(&mut self, rejection: &OrderRejection) {
+        if self.rejections.len() == Self::MAX_CLOSED_ORDERS {
+            self.rejections.pop_front();
+        }
+        self.rejections.push_back(rejection.clone());
+
+        let oid = rejection.oid.or_else(|| {
+            let cloid = rejection.cloid.as_deref()?;
+            self.inner.get_order_by_cloid(cloid).map(|order| order.oid)
+        });
+
+        let Some(oid) = oid else {
+            tracing::debug!("Rejection does not refer to an open order: {:?}", rejection);
+            return;
+        };
+
+        let Some(order) = self.inner.get_order_mut(oid) else {
+            return;
+        };
+
+        match order.status {
+            OrderStatus::PendingNew => {
+                self.close_order(oid, OrderStatus::Rejected);
+            }
+            OrderStatus::PendingCancel => {
+                order.status = if order.filled_size.is_zero() {
+                    OrderStatus::New
+                } else {
+                    OrderStatus::PartiallyFilled
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn close_order(&mut self, oid: usize, status: OrderStatus) -> Option<Order> {
+        let mut order = self.inner.remove_by_oid(oid)?;
+        order.status = status;
+
+        if self.closed.len() == Self::MAX_CLOSED_ORDERS {
+            self.closed.pop_front();
+        }
+        self.closed.push_back(order.clone());
+
+        Some(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Side;
+
+    fn order(oid: usize, cloid: &str) -> Order {
+        Order::new(oid, "BTCUSD".to_string(), Side::Bid, 100.into(), 2.into()).with_cloid(cloid)
+    }
+
+    fn fill(oid: usize, size: u64) -> Fill {
+        Fill {
+            oid,
+            side: Side::Bid,
+            price: 100.into(),
+            size: size.into(),
+            is_maker: true,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_order_lifecycle() {
+        let mut state = PendingOrdersState::new();
+
+        state
+            .process_event(InternalEvent::OrderPendingNew(order(1, "a")))
+            .unwrap();
+        assert_eq!(state.get_status(1), Some(OrderStatus::PendingNew));
+
+        state
+            .process_event(InternalEvent::OrderPlaced(order(1, "a")))
+            .unwrap();
+        assert_eq!(state.get_status(1), Some(OrderStatus::New));
+
+        state
+            .process_event(InternalEvent::OrderFilled(fill(1, 1)))
+            .unwrap();
+        let open = state.get_order_by_cloid("a").unwrap();
+        assert_eq!(open.status, OrderStatus::PartiallyFilled);
+        assert_eq!(open.size, 1.into());
+        assert_eq!(open.filled_size, 1.into());
+
+        state
+            .process_event(InternalEvent::OrderFilled(fill(1, 1)))
+            .unwrap();
+        assert!(state.get_inner().is_empty());
+        assert_eq!(state.get_status(1), Some(OrderStatus::Filled));
+        assert_eq!(
+            state.get_order_by_cloid("a").unwrap().status,
+            OrderStatus::Filled
+        );
+    }
+
+    #[test]
+    fn test_cancel_lifecycle() {
+        let mut state = PendingOrdersState::new();
+        state.add_order(order(1, "a"), OrderStatus::New);
+
+        state
+            .process_event(InternalEvent::OrderPendingCancel(order(1, "a")))
+            .unwrap();
+        assert_eq!(state.get_status(1), Some(OrderStatus::PendingCancel));
+        assert_eq!(state.open_orders(), ["1"]);
+
+        state
+            .process_event(InternalEvent::OrderCancelled(order(1, "a")))
+            .unwrap();
+        assert!(state.get_inner().is_empty());
+        assert!(state.open_orders().is_empty());
+        assert_eq!(state.get_status(1), Some(OrderStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_rejections() {
+        let mut state = PendingOrdersState::new();
+        state.add_order(order(1, "a"), OrderStatus::PendingNew);
+        state.add_order(order(2, "b"), OrderStatus::New);
+        state.mark_pending_cancel(2);
+
+        let rejection = |oid: Option<usize>, cloid: Option<&str>| OrderRejection {
+            cloid: cloid.map(str::to_string),
+            oid,
+            symbol: "BTCUSD".to_string(),
+            reason: "test".to_string(),
+            timestamp: 0,
+        };
+
+        // Rejected new order is closed
+        state
+            .process_event(InternalEvent::OrderRejected(rejection(None, Some("a"))))
+            .unwrap();
+        assert_eq!(state.get_status(1), Some(OrderStatus::Rejected));
+
+        // Rejected cancel reverts the order to working
+        state
+            .process_event(InternalEvent::OrderRejected(rejection(Some(2), None)))
+            .unwrap();
+        assert_eq!(state.get_status(2), Some(OrderStatus::New));
+        assert_eq!(state.get_inner().len(), 1);
     }
 }
@@ -1,10 +1,21 @@
-use hayate_core::traits::State;
+use hayate_core::{kill_switch::KillSwitch, traits::State};
 
 use crate::models::{Decimal, InternalEvent, Position, Side};
 
+/// Triggers the kill switch once total PnL falls more than `max_drawdown` below its peak.
+#[derive(Debug)]
+struct DrawdownBreaker {
+    max_drawdown: Decimal,
+    peak_pnl: Decimal,
+    kill_switch: KillSwitch,
+}
+
 #[derive(Debug, Default)]
 pub struct PositionState {
     inner: Position,
+    /// Last traded price, used to mark the position
+    mark_price: Option<Decimal>,
+    breaker: Option<DrawdownBreaker>,
 }
 
 #[async_trait::async_trait]
@@ -22,11 +33,22 @@ impl State<InternalEvent> for PositionState {
         match event {
             InternalEvent::OrderFilled(fill) => {
                 self.update_position(fill.side, fill.price, fill.size, fill.timestamp);
+                self.check_drawdown();
+            }
+            InternalEvent::TradeUpdate(trades) => {
+                if let Some(trade) = trades.last() {
+                    self.mark_price = Some(trade.price);
+                    self.check_drawdown();
+                }
             }
             InternalEvent::OrderCancelled(_)
+            | InternalEvent::OrderPendingNew(_)
             | InternalEvent::OrderPlaced(_)
-            | InternalEvent::OrderBookUpdate(_)
-            | InternalEvent::TradeUpdate(_) => {}
+            | InternalEvent::OrderAmended(_)
+            | InternalEvent::OrderPendingCancel(_)
+            | InternalEvent::OrderRejected(_)
+            | InternalEvent::CandleUpdate(_)
+            | InternalEvent::OrderBookUpdate(_) => {}
         }
 
         Ok(())
@@ -37,18 +59,104 @@ impl PositionState {
     pub fn new() -> Self {
         Self {
             inner: Position::default(),
+            mark_price: None,
+            breaker: None,
         }
     }
 
+    /// Enables the drawdown circuit breaker: once realized plus unrealized PnL drops more
+    /// than `max_drawdown` below its peak, `kill_switch` is triggered.
+    pub fn with_drawdown_limit(mut self, max_drawdown: Decimal, kill_switch: KillSwitch) -> Self {
+        self.breaker = Some(DrawdownBreaker {
+            max_drawdown,
+            peak_pnl: Decimal::ZERO,
+            kill_switch,
+        });
+        self
+    }
+
     pub fn get_inner(&self) -> &Position {
         &self.inner
     }
 
+    pub fn get_mark_price(&self) -> Option<Decimal> {
+        self.mark_price
+    }
+
+    /// Realized plus unrealized PnL at the mark price, or realized PnL only if no
+    /// trade has been seen yet.
+    pub fn get_total_pnl(&self) -> Decimal {
+        match self.mark_price {
+            Some(price) => self.inner.total_pnl(price),
+            None => self.inner.realized_pnl,
+        }
+    }
+
     pub fn update_position(&mut self, side: Side, price: Decimal, size: Decimal, timestamp: u64) {
-        if !self.inner.is_open() {
-            self.inner = Position::new(side, price, size, timestamp);
-        } else {
-            self.inner.update(side, price, size, timestamp);
+        self.inner.update(side, price, size, timestamp);
+    }
+
+    fn check_drawdown(&mut self) {
+        let total_pnl = self.get_total_pnl();
+        let Some(breaker) = &mut self.breaker else {
+            return;
+        };
+
+        breaker.peak_pnl = breaker.peak_pnl.max(total_pnl);
+        let drawdown = breaker.peak_pnl - total_pnl;
+        if drawdown > breaker.max_drawdown && !breaker.kill_switch.is_triggered() {
+            breaker.kill_switch.trigger(format!(
+                "Drawdown {} exceeds limit {} (peak PnL: {}, current PnL: {})",
+                drawdown, breaker.max_drawdown, breaker.peak_pnl, total_pnl
+            ));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Fill, Trade};
+
+    fn trade(price: u64) -> InternalEvent {
+        InternalEvent::TradeUpdate(vec![Trade {
+            symbol: "BTCUSD".to_string(),
+            side: Side::Bid,
+            price: price.into(),
+            size: 1.into(),
+            timestamp: 0,
+        }])
+    }
+
+    #[test]
+    fn test_drawdown_breaker() {
+        let kill_switch = KillSwitch::new();
+        let mut state = PositionState::new().with_drawdown_limit(10.into(), kill_switch.clone());
+
+        state
+            .process_event(InternalEvent::OrderFilled(Fill {
+                oid: 1,
+                side: Side::Bid,
+                price: 100.into(),
+                size: 2.into(),
+                is_maker: true,
+                timestamp: 0,
+            }))
+            .unwrap();
+
+        // Peak PnL of 20, then back to 12: drawdown of 8 is within the limit
+        state.process_event(trade(110)).unwrap();
+        state.process_event(trade(106)).unwrap();
+        assert_eq!(state.get_total_pnl(), 12.into());
+        assert!(!kill_switch.is_triggered());
+
+        // PnL of 8 is a drawdown of 12 from the peak
+        state.process_event(trade(104)).unwrap();
+        assert!(kill_switch.is_triggered());
+        assert!(kill_switch
+            .trigger_info()
+            .unwrap()
+            .reason
+            .contains("Drawdown"));
+    }
+}
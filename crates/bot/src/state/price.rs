@@ -1,12 +1,19 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use hayate_core::traits::State;
 
-use crate::models::{Decimal, Indicator, InternalEvent};
+use crate::models::{
+    Decimal, HistoricalData, Indicator, InternalEvent, PriceHistory, WarmUpStatus,
+};
 
+/// Indicators updated on every trade, keyed by their instance name.
 #[derive(Debug, Default)]
 pub struct PriceState {
-    price_indicators: HashMap<String, Box<dyn Indicator>>,
+    price_indicators: BTreeMap<String, Box<dyn Indicator>>,
+    /// Market data to warm up the indicators with on sync
+    history: Option<PriceHistory>,
+    /// Time of the latest backfilled data, older live trades are skipped
+    synced_until: Option<u64>,
 }
 
 #[async_trait::async_trait]
@@ -16,21 +23,57 @@ impl State<InternalEvent> for PriceState {
     }
 
     async fn sync(&mut self) -> anyhow::Result<()> {
-        // TODO: Implement sync logic
+        // Backfilled once: after live data, indicators must not see older data again
+        let Some(history) = self.history.take() else {
+            return Ok(());
+        };
+
+        // Indicators can still warm up from live trades, so a failed backfill is not fatal
+        match history.load().await {
+            Ok(data) => {
+                tracing::info!(
+                    "Backfilling price indicators with {} data points",
+                    data.len()
+                );
+                self.backfill(data);
+            }
+            Err(e) => tracing::warn!("Could not load price history from {:?}: {}", history, e),
+        }
+
+        for (name, indicator) in &self.price_indicators {
+            match indicator.warm_up_status() {
+                WarmUpStatus::Ready => tracing::info!("Indicator {} is warmed up", name),
+                WarmUpStatus::WarmingUp { samples, required } => tracing::warn!(
+                    "Indicator {} has {} of {} samples after backfill, warming up from live trades",
+                    name,
+                    samples,
+                    required
+                ),
+            }
+        }
         Ok(())
     }
 
     fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
         match event {
             InternalEvent::TradeUpdate(trades) => {
-                for trade in trades {
-                    self.update(trade.price, trade.timestamp);
+                let synced_until = self.synced_until.unwrap_or(0);
+                // Live trades already covered by the backfill
+                for trade in trades.iter().filter(|t| t.timestamp >= synced_until) {
+                    for indicator in self.price_indicators.values_mut() {
+                        indicator.update_trade(trade);
+                    }
                 }
             }
             InternalEvent::OrderBookUpdate(_)
+            | InternalEvent::OrderPendingNew(_)
             | InternalEvent::OrderPlaced(_)
             | InternalEvent::OrderFilled(_)
-            | InternalEvent::OrderCancelled(_) => {}
+            | InternalEvent::OrderAmended(_)
+            | InternalEvent::OrderPendingCancel(_)
+            | InternalEvent::OrderCancelled(_)
+            | InternalEvent::OrderRejected(_)
+            | InternalEvent::CandleUpdate(_) => {}
         }
 
         Ok(())
@@ -39,21 +82,75 @@ impl State<InternalEvent> for PriceState {
 
 impl PriceState {
     pub fn new() -> Self {
-        Self {
-            price_indicators: HashMap::new(),
+        Self::default()
+    }
+
+    /// Warms up the indicators from `history` on sync.
+    pub fn with_history(mut self, history: PriceHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Feeds past market data to the indicators, oldest first.
+    pub fn backfill(&mut self, data: HistoricalData) {
+        self.synced_until = data.last_timestamp().max(self.synced_until);
+        match data {
+            HistoricalData::Candles(candles) => {
+                for candle in &candles {
+                    for indicator in self.price_indicators.values_mut() {
+                        indicator.update_candle(candle);
+                    }
+                }
+            }
+            HistoricalData::Trades(trades) => {
+                for trade in &trades {
+                    for indicator in self.price_indicators.values_mut() {
+                        indicator.update_trade(trade);
+                    }
+                }
+            }
         }
     }
 
+    /// Adds an indicator under its instance name, replacing any indicator of that name.
     pub fn add_indicator(&mut self, indicator: Box<dyn Indicator>) {
-        self.price_indicators
-            .insert(indicator.name().to_string(), indicator);
+        let name = indicator.name().to_string();
+        if self
+            .price_indicators
+            .insert(name.clone(), indicator)
+            .is_some()
+        {
+            tracing::warn!("Replaced indicator {} in price state", name);
+        }
     }
 
     pub fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
         self.price_indicators.get(name).map(|ind| ind.as_ref())
     }
 
-    pub fn get_indicators(&self) -> &HashMap<String, Box<dyn Indicator>> {
+    /// The indicator of type `T` named `name`.
+    pub fn get<T: Indicator>(&self, name: &str) -> Option<&T> {
+        self.get_indicator(name)?.downcast_ref()
+    }
+
+    /// The first indicator of type `T` by name, for states holding a single one.
+    pub fn find<T: Indicator>(&self) -> Option<&T> {
+        self.find_all().next()
+    }
+
+    /// All indicators of type `T`, ordered by name.
+    pub fn find_all<T: Indicator>(&self) -> impl Iterator<Item = &T> {
+        self.price_indicators
+            .values()
+            .filter_map(|ind| ind.downcast_ref())
+    }
+
+    /// An output of a multi-output indicator, e.g. the `upper` band of Bollinger Bands.
+    pub fn get_output(&self, name: &str, output: &str) -> Option<Decimal> {
+        self.get_indicator(name)?.output(output)
+    }
+
+    pub fn get_indicators(&self) -> &BTreeMap<String, Box<dyn Indicator>> {
         &self.price_indicators
     }
 
@@ -63,3 +160,81 @@ impl PriceState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Bollinger, Candle, Natr, Rsi, RsiSmoothing, Side, Sma, Trade};
+
+    #[test]
+    fn test_price_state_lookup() {
+        let mut state = PriceState::new();
+        state.add_indicator(Box::new(Rsi::new(14, 1000)));
+        state.add_indicator(Box::new(
+            Rsi::new(50, 60_000).with_smoothing(RsiSmoothing::Wilder),
+        ));
+        state.add_indicator(Box::new(Bollinger::new(20, 2.into(), 1000)));
+
+        let names: Vec<_> = state.get_indicators().keys().cloned().collect();
+        assert_eq!(
+            names,
+            vec!["bollinger_20_2_1s", "rsi_14_1s", "rsi_wilder_50_1m"]
+        );
+
+        assert!(state.get::<Rsi>("rsi_wilder_50_1m").is_some());
+        assert!(state.get::<Natr>("rsi_14_1s").is_none());
+        assert_eq!(state.find::<Rsi>().unwrap().name(), "rsi_14_1s");
+        assert_eq!(state.find_all::<Rsi>().count(), 2);
+        assert!(state.find::<Natr>().is_none());
+
+        for i in 0..=20 {
+            state.update(Decimal::from(100 + i % 2), i * 1000);
+        }
+        // 20 closes alternating 100 and 101: 100.5 + 2 * 0.5
+        assert_eq!(
+            state.get_output("bollinger_20_2_1s", "upper"),
+            Some(Decimal::from_str_unchecked("101.5"))
+        );
+        assert_eq!(state.get_output("bollinger_20_2_1s", "width"), None);
+    }
+
+    #[test]
+    fn test_backfill() {
+        let trade = |price: u64, timestamp: u64| Trade {
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Bid,
+            price: price.into(),
+            size: 1.into(),
+            timestamp,
+        };
+
+        let mut state = PriceState::new();
+        state.add_indicator(Box::new(Sma::new(2, 1000)));
+        state.backfill(HistoricalData::Trades(
+            (0..3).map(|i| trade(100 + i, i * 1000)).collect(),
+        ));
+        // Bars of 100 and 101 closed, 102 still open
+        assert_eq!(
+            state.find::<Sma>().unwrap().value(),
+            Some(Decimal::from_str_unchecked("100.5"))
+        );
+
+        // A live trade older than the backfill is skipped, the next one closes 102
+        let live = vec![trade(200, 1500), trade(104, 3000)];
+        state
+            .process_event(InternalEvent::TradeUpdate(live))
+            .unwrap();
+        assert_eq!(
+            state.find::<Sma>().unwrap().value(),
+            Some(Decimal::from_str_unchecked("101.5"))
+        );
+
+        let mut state = PriceState::new();
+        state.add_indicator(Box::new(Sma::new(2, 60_000)));
+        let candles = (0..2)
+            .map(|i| Candle::empty(i * 60_000, Decimal::from(100 + i)))
+            .collect();
+        state.backfill(HistoricalData::Candles(candles));
+        assert!(state.find::<Sma>().unwrap().is_ready());
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use hayate_core::traits::State;
+
+use crate::models::{Decimal, InternalEvent, Trade, TradeFlow};
+
+/// Market trade flow over several rolling windows: buy and sell volume, order-flow
+/// imbalance, arrival rate, VWAP and large trades.
+#[derive(Debug, Default)]
+pub struct TradeFlowState {
+    /// Flows keyed by their window in ms
+    flows: BTreeMap<u64, TradeFlow>,
+    /// Trades of at least this size are reported as large
+    large_trade_size: Option<Decimal>,
+    last_large_trade: Option<Trade>,
+}
+
+#[async_trait::async_trait]
+impl State<InternalEvent> for TradeFlowState {
+    fn name(&self) -> &str {
+        "trade_flow"
+    }
+
+    async fn sync(&mut self) -> anyhow::Result<()> {
+        // The flow only covers trades seen live, windows fill up as they arrive
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        match event {
+            InternalEvent::TradeUpdate(trades) => {
+                for trade in &trades {
+                    self.update(trade);
+                }
+            }
+            InternalEvent::OrderBookUpdate(_)
+            | InternalEvent::OrderPendingNew(_)
+            | InternalEvent::OrderPlaced(_)
+            | InternalEvent::OrderFilled(_)
+            | InternalEvent::OrderAmended(_)
+            | InternalEvent::OrderPendingCancel(_)
+            | InternalEvent::OrderCancelled(_)
+            | InternalEvent::OrderRejected(_)
+            | InternalEvent::CandleUpdate(_) => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl TradeFlowState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks the trades of the last `window` ms.
+    pub fn with_window(mut self, window: u64) -> Self {
+        self.flows.insert(window, TradeFlow::new(window));
+        self
+    }
+
+    /// Reports trades of at least `size` as large.
+    pub fn with_large_trade_size(mut self, size: Decimal) -> Self {
+        self.large_trade_size = Some(size);
+        self
+    }
+
+    pub fn update(&mut self, trade: &Trade) {
+        for flow in self.flows.values_mut() {
+            flow.update(trade);
+        }
+        if self
+            .large_trade_size
+            .is_some_and(|large_size| trade.size >= large_size)
+        {
+            self.last_large_trade = Some(trade.clone());
+        }
+    }
+
+    /// Drops the trades that fell out of their window at `now`, for readers with a
+    /// clock of their own such as fill models.
+    pub fn expire(&mut self, now: u64) {
+        for flow in self.flows.values_mut() {
+            flow.expire(now);
+        }
+    }
+
+    /// The flow of the `window` ms window.
+    pub fn get_flow(&self, window: u64) -> Option<&TradeFlow> {
+        self.flows.get(&window)
+    }
+
+    /// Flows ordered by window, shortest first.
+    pub fn get_flows(&self) -> impl Iterator<Item = &TradeFlow> {
+        self.flows.values()
+    }
+
+    /// Large trades within the `window` ms window, oldest first, none if no large trade
+    /// size is set.
+    pub fn get_large_trades(&self, window: u64) -> impl Iterator<Item = &Trade> {
+        self.large_trade_size
+            .zip(self.flows.get(&window))
+            .into_iter()
+            .flat_map(|(size, flow)| flow.large_trades(size))
+    }
+
+    /// The latest large trade, regardless of the windows.
+    pub fn get_last_large_trade(&self) -> Option<&Trade> {
+        self.last_large_trade.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Side;
+
+    #[test]
+    fn test_trade_flow_state() {
+        let mut state = TradeFlowState::new()
+            .with_window(1000)
+            .with_window(60_000)
+            .with_large_trade_size(5.into());
+
+        let trades = [
+            (Side::Bid, 5, 0),
+            (Side::Ask, 1, 30_000),
+            (Side::Bid, 2, 30_500),
+        ]
+        .into_iter()
+        .map(|(side, size, timestamp)| Trade {
+            symbol: "BTCUSDT".to_string(),
+            side,
+            price: 100.into(),
+            size: size.into(),
+            timestamp,
+        })
+        .collect();
+        state
+            .process_event(InternalEvent::TradeUpdate(trades))
+            .unwrap();
+
+        let short = state.get_flow(1000).unwrap();
+        assert_eq!(
+            (short.buy_volume(), short.sell_volume()),
+            (2.into(), 1.into())
+        );
+        let long = state.get_flow(60_000).unwrap();
+        assert_eq!(long.volume(), 8.into());
+        assert!(state.get_flow(5000).is_none());
+
+        assert_eq!(state.get_large_trades(60_000).count(), 1);
+        assert_eq!(state.get_large_trades(1000).count(), 0);
+        assert_eq!(state.get_last_large_trade().unwrap().timestamp, 0);
+
+        state.expire(61_000);
+        assert_eq!(state.get_flow(60_000).unwrap().volume(), 3.into());
+        assert_eq!(state.get_flow(1000).unwrap().trade_count(), 0);
+    }
+}
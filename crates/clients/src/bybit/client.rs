@@ -68,6 +68,22 @@ impl WsHandler for BybitWsHandler {
         sender.send(Message::Text(trade_subscribe_msg.into()))?;
         tracing::info!("Subscribed to trade updates for {}", symbol);
 
+        let kline_interval = "1"; // Kline interval in minutes
+        let kline_topic = format!("kline.{}.{}", kline_interval, symbol);
+        let kline_subscribe_msg = serde_json::json!({
+            "req_id": "test", // optional
+            "op": "subscribe",
+            "args": [kline_topic]
+        })
+        .to_string();
+
+        sender.send(Message::Text(kline_subscribe_msg.into()))?;
+        tracing::info!(
+            "Subscribed to {}m kline updates for {}",
+            kline_interval,
+            symbol
+        );
+
         self.ws_sender = Some(sender);
         Ok(())
     }
@@ -1,5 +1,7 @@
 mod client;
+mod rest;
 mod types;
 
 pub use client::*;
+pub use rest::*;
 pub use types::*;
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use transport::HttpClient;
+
+use crate::bybit::types::{
+    BybitInstrumentInfo, BybitInstrumentsInfo, BybitKline, BybitKlines, BybitOrderBookData,
+    BybitRecentTrade, BybitRecentTrades, BybitResponse, BYBIT_REST_ENDPOINT,
+};
+
+pub struct BybitRestClient {
+    inner: HttpClient,
+}
+
+impl BybitRestClient {
+    pub fn new() -> Self {
+        Self::with_base_url(BYBIT_REST_ENDPOINT)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            inner: HttpClient::new(base_url),
+        }
+    }
+
+    /// Queries the instrument specifications of a `category` (spot, linear, inverse or
+    /// option), optionally for a single `symbol`, following pagination.
+    pub async fn get_instruments_info(
+        &mut self,
+        category: &str,
+        symbol: Option<&str>,
+    ) -> anyhow::Result<Vec<BybitInstrumentInfo>> {
+        let mut instruments = Vec::new();
+        let mut params = HashMap::from([("category".to_string(), category.to_string())]);
+        if let Some(symbol) = symbol {
+            params.insert("symbol".to_string(), symbol.to_string());
+        }
+
+        loop {
+            let response: BybitResponse<BybitInstrumentsInfo> = self
+                .inner
+                .get("/v5/market/instruments-info", None, Some(&params))
+                .await?;
+
+            if response.code != 0 {
+                return Err(anyhow::anyhow!(
+                    "Bybit instruments-info request failed with code {}: {}",
+                    response.code,
+                    response.message
+                ));
+            }
+
+            instruments.extend(response.result.list);
+
+            if response.result.next_page_cursor.is_empty() {
+                break;
+            }
+            params.insert("cursor".to_string(), response.result.next_page_cursor);
+        }
+
+        tracing::debug!(
+            "Fetched {} instruments for category {}",
+            instruments.len(),
+            category
+        );
+        Ok(instruments)
+    }
+
+    /// Queries the last `limit` klines (at most 1000) of `symbol` at `interval` (1, 3, 5,
+    /// 15, 30, 60, 120, 240, 360, 720 minutes, D, W or M), oldest first. The last kline
+    /// is the one still open.
+    pub async fn get_klines(
+        &mut self,
+        category: &str,
+        symbol: &str,
+        interval: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<BybitKline>> {
+        let params = HashMap::from([
+            ("category".to_string(), category.to_string()),
+            ("symbol".to_string(), symbol.to_string()),
+            ("interval".to_string(), interval.to_string()),
+            ("limit".to_string(), limit.to_string()),
+        ]);
+
+        let response: BybitResponse<BybitKlines> = self
+            .inner
+            .get("/v5/market/kline", None, Some(&params))
+            .await?;
+        if response.code != 0 {
+            return Err(anyhow::anyhow!(
+                "Bybit kline request failed with code {}: {}",
+                response.code,
+                response.message
+            ));
+        }
+
+        let mut klines = response.result.list;
+        klines.reverse();
+        tracing::debug!("Fetched {} {} klines of {}", klines.len(), interval, symbol);
+        Ok(klines)
+    }
+
+    /// Queries the last `limit` public trades of `symbol`, oldest first. Bybit caps
+    /// `limit` at 60 for spot and 1000 for other categories.
+    pub async fn get_recent_trades(
+        &mut self,
+        category: &str,
+        symbol: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<BybitRecentTrade>> {
+        let params = HashMap::from([
+            ("category".to_string(), category.to_string()),
+            ("symbol".to_string(), symbol.to_string()),
+            ("limit".to_string(), limit.to_string()),
+        ]);
+
+        let response: BybitResponse<BybitRecentTrades> = self
+            .inner
+            .get("/v5/market/recent-trade", None, Some(&params))
+            .await?;
+        if response.code != 0 {
+            return Err(anyhow::anyhow!(
+                "Bybit recent-trade request failed with code {}: {}",
+                response.code,
+                response.message
+            ));
+        }
+
+        let mut trades = response.result.list;
+        trades.reverse();
+        tracing::debug!("Fetched {} recent trades of {}", trades.len(), symbol);
+        Ok(trades)
+    }
+
+    /// Queries the order book of `symbol` with `limit` levels per side. Bybit caps
+    /// `limit` at 200 for spot and 500 for other categories.
+    pub async fn get_orderbook(
+        &mut self,
+        category: &str,
+        symbol: &str,
+        limit: usize,
+    ) -> anyhow::Result<BybitOrderBookData> {
+        let params = HashMap::from([
+            ("category".to_string(), category.to_string()),
+            ("symbol".to_string(), symbol.to_string()),
+            ("limit".to_string(), limit.to_string()),
+        ]);
+
+        let response: BybitResponse<BybitOrderBookData> = self
+            .inner
+            .get("/v5/market/orderbook", None, Some(&params))
+            .await?;
+        if response.code != 0 {
+            return Err(anyhow::anyhow!(
+                "Bybit orderbook request failed with code {}: {}",
+                response.code,
+                response.message
+            ));
+        }
+
+        tracing::debug!(
+            "Fetched order book of {} at update {}",
+            symbol,
+            response.result.update_id
+        );
+        Ok(response.result)
+    }
+}
+
+impl Default for BybitRestClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
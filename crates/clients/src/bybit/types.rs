@@ -1,6 +1,7 @@
 use serde::Deserialize;
 
 pub const BYBIT_ENDPOINT: &str = "wss://stream.bybit.com/v5/public/spot";
+pub const BYBIT_REST_ENDPOINT: &str = "https://api.bybit.com";
 pub type BybitOrderEntry = Vec<String>; // [price, size]
 
 #[derive(Deserialize, Debug)]
@@ -19,6 +20,7 @@ pub enum BybitMessage {
     },
     OrderBookUpdate(BybitOrderBookUpdate),
     TradeUpdate(BybitTradeUpdate),
+    KlineUpdate(BybitKlineUpdate),
 }
 
 #[derive(Deserialize, Debug)]
@@ -114,3 +116,179 @@ pub struct BybitTradeData {
     #[serde(rename = "RPI")]
     pub rpi: bool,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct BybitKlineUpdate {
+    /// Topic name, e.g. kline.1.BTCUSDT
+    pub topic: String,
+    /// The timestamp (ms) that the system generates the data
+    #[serde(rename = "ts")]
+    pub timestamp: u64,
+    /// Data type: snapshot
+    #[serde(rename = "type")]
+    pub data_type: BybitDataType,
+    /// Kline data
+    #[serde(rename = "data")]
+    pub data: Vec<BybitKlineData>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitKlineData {
+    /// The start timestamp (ms)
+    pub start: u64,
+    /// The end timestamp (ms)
+    pub end: u64,
+    /// Kline interval: 1,3,5,15,30,60,120,240,360,720 (minutes), D, W, M
+    pub interval: String,
+    pub open: String,
+    pub close: String,
+    pub high: String,
+    pub low: String,
+    /// Trade volume in base coin
+    pub volume: String,
+    /// Turnover in quote coin
+    pub turnover: String,
+    /// Whether the kline is closed
+    pub confirm: bool,
+    /// The timestamp (ms) of the last matched order in the kline
+    pub timestamp: u64,
+}
+
+impl BybitKlineData {
+    /// The interval in milliseconds, `None` for monthly klines which have no fixed length
+    pub fn interval_ms(&self) -> Option<u64> {
+        match self.interval.as_str() {
+            "D" => Some(24 * 60 * 60 * 1000),
+            "W" => Some(7 * 24 * 60 * 60 * 1000),
+            "M" => None,
+            minutes => minutes.parse::<u64>().ok().map(|m| m * 60 * 1000),
+        }
+    }
+}
+
+/// Common envelope of Bybit V5 REST responses
+#[derive(Deserialize, Debug)]
+pub struct BybitResponse<T> {
+    #[serde(rename = "retCode")]
+    pub code: i64,
+    #[serde(rename = "retMsg")]
+    pub message: String,
+    pub result: T,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitKlines {
+    /// Symbol name, e.g. BTCUSDT
+    pub symbol: String,
+    /// Product type: spot, linear, inverse
+    pub category: String,
+    /// Klines, newest first
+    pub list: Vec<BybitKline>,
+}
+
+/// A kline of the REST API, sent as an array of strings
+/// `[startTime, openPrice, highPrice, lowPrice, closePrice, volume, turnover]`
+#[derive(Deserialize, Debug)]
+pub struct BybitKline {
+    /// The start timestamp (ms)
+    pub start: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    /// Trade volume in base coin
+    pub volume: String,
+    /// Turnover in quote coin
+    pub turnover: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitRecentTrades {
+    /// Product type: spot, linear, inverse, option
+    pub category: String,
+    /// Trades, newest first
+    pub list: Vec<BybitRecentTrade>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitRecentTrade {
+    /// Execution ID
+    #[serde(rename = "execId")]
+    pub exec_id: String,
+    /// Symbol name, e.g. BTCUSDT
+    pub symbol: String,
+    pub price: String,
+    pub size: String,
+    /// Side of taker: Buy, Sell
+    pub side: String,
+    /// The timestamp (ms) that the order is filled
+    pub time: String,
+    #[serde(rename = "isBlockTrade")]
+    pub is_block_trade: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitInstrumentsInfo {
+    /// Product type: spot, linear, inverse, option
+    pub category: String,
+    pub list: Vec<BybitInstrumentInfo>,
+    /// Cursor for the next page, empty on the last page (not provided for spot)
+    #[serde(rename = "nextPageCursor", default)]
+    pub next_page_cursor: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitInstrumentInfo {
+    /// Symbol name, e.g. BTCUSDT
+    pub symbol: String,
+    /// Instrument status, e.g. Trading
+    pub status: String,
+    #[serde(rename = "priceFilter")]
+    pub price_filter: BybitPriceFilter,
+    #[serde(rename = "lotSizeFilter")]
+    pub lot_size_filter: BybitLotSizeFilter,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitPriceFilter {
+    /// The step to increase/reduce order price
+    #[serde(rename = "tickSize")]
+    pub tick_size: String,
+}
+
+/// Lot size filter, whose fields differ between spot and derivatives categories
+#[derive(Deserialize, Debug)]
+pub struct BybitLotSizeFilter {
+    /// Spot only: the precision of the base coin
+    #[serde(rename = "basePrecision")]
+    pub base_precision: Option<String>,
+    /// Derivatives only: the step to increase/reduce order quantity
+    #[serde(rename = "qtyStep")]
+    pub qty_step: Option<String>,
+    /// Minimum order quantity
+    #[serde(rename = "minOrderQty")]
+    pub min_order_qty: String,
+    /// Maximum order quantity
+    #[serde(rename = "maxOrderQty")]
+    pub max_order_qty: String,
+    /// Spot only: minimum order amount in quote coin
+    #[serde(rename = "minOrderAmt")]
+    pub min_order_amt: Option<String>,
+    /// Derivatives only: minimum notional value
+    #[serde(rename = "minNotionalValue")]
+    pub min_notional_value: Option<String>,
+}
+
+impl BybitLotSizeFilter {
+    /// The quantity step, whichever of `qtyStep` or `basePrecision` is provided
+    pub fn lot_size(&self) -> Option<&str> {
+        self.qty_step.as_deref().or(self.base_precision.as_deref())
+    }
+
+    /// The minimum notional, whichever of `minNotionalValue` or `minOrderAmt` is provided
+    pub fn min_notional(&self) -> Option<&str> {
+        self.min_notional_value
+            .as_deref()
+            .or(self.min_order_amt.as_deref())
+    }
+}
@@ -0,0 +1,198 @@
+use tokio::sync::{broadcast, mpsc};
+
+/// What happens when collectors produce events faster than the states apply them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Collectors wait for room in the channel, no event is lost
+    #[default]
+    Block,
+    /// The oldest events are dropped, then the states are synced again to recover
+    /// from the missed updates
+    DropOldest,
+    /// The bot shuts down on the first dropped event
+    Fail,
+}
+
+/// The channel carrying events from the collectors to the states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventChannel {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for EventChannel {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            overflow: OverflowPolicy::Block,
+        }
+    }
+}
+
+impl EventChannel {
+    pub(crate) fn open<E: Clone>(&self) -> (EventSender<E>, EventReceiver<E>) {
+        match self.overflow {
+            OverflowPolicy::Block => {
+                let (tx, rx) = mpsc::channel(self.capacity);
+                (EventSender::Bounded(tx), EventReceiver::Bounded(rx))
+            }
+            // A broadcast channel overwrites the oldest events and reports how many
+            // the receiver missed
+            OverflowPolicy::DropOldest | OverflowPolicy::Fail => {
+                let (tx, rx) = broadcast::channel(self.capacity);
+                (EventSender::Broadcast(tx), EventReceiver::Broadcast(rx))
+            }
+        }
+    }
+}
+
+pub(crate) enum EventSender<E> {
+    Bounded(mpsc::Sender<E>),
+    Broadcast(broadcast::Sender<E>),
+}
+
+impl<E> Clone for EventSender<E> {
+    fn clone(&self) -> Self {
+        match self {
+            EventSender::Bounded(tx) => EventSender::Bounded(tx.clone()),
+            EventSender::Broadcast(tx) => EventSender::Broadcast(tx.clone()),
+        }
+    }
+}
+
+impl<E> EventSender<E> {
+    /// Sends an event, waiting for room with [`OverflowPolicy::Block`]. Returns `false`
+    /// once the receiver is gone.
+    pub(crate) async fn send(&self, event: E) -> bool {
+        match self {
+            EventSender::Bounded(tx) => tx.send(event).await.is_ok(),
+            EventSender::Broadcast(tx) => tx.send(event).is_ok(),
+        }
+    }
+}
+
+/// Feeds events back into the channel of the states, for executors reporting outcomes
+/// the venue returns through the request path, e.g. acks, rejects or fills of a REST
+/// response. Events are ordered with those of the collectors.
+pub struct EventSink<E> {
+    sender: Option<EventSender<E>>,
+}
+
+impl<E> Clone for EventSink<E> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<E> EventSink<E> {
+    pub(crate) fn new(sender: EventSender<E>) -> Self {
+        Self {
+            sender: Some(sender),
+        }
+    }
+
+    /// A sink dropping every event, for executors running outside of a bot.
+    pub fn discard() -> Self {
+        Self { sender: None }
+    }
+
+    /// A sink feeding `sender`, for executors running outside of a bot whose events
+    /// are still needed, e.g. in tests.
+    pub fn from_sender(sender: mpsc::Sender<E>) -> Self {
+        Self::new(EventSender::Bounded(sender))
+    }
+
+    /// Sends an event to the states. Returns `false` if it was dropped because the
+    /// states stopped or the sink discards events.
+    pub async fn send(&self, event: E) -> bool {
+        match &self.sender {
+            Some(sender) => sender.send(event).await,
+            None => false,
+        }
+    }
+}
+
+pub(crate) enum Received<E> {
+    Event(E),
+    /// Events were dropped before they could be received
+    Lagged(u64),
+    Closed,
+}
+
+pub(crate) enum EventReceiver<E> {
+    Bounded(mpsc::Receiver<E>),
+    Broadcast(broadcast::Receiver<E>),
+}
+
+impl<E: Clone> EventReceiver<E> {
+    pub(crate) async fn recv(&mut self) -> Received<E> {
+        match self {
+            EventReceiver::Bounded(rx) => match rx.recv().await {
+                Some(event) => Received::Event(event),
+                None => Received::Closed,
+            },
+            EventReceiver::Broadcast(rx) => match rx.recv().await {
+                Ok(event) => Received::Event(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => Received::Lagged(missed),
+                Err(broadcast::error::RecvError::Closed) => Received::Closed,
+            },
+        }
+    }
+
+    /// Number of events waiting to be received.
+    pub(crate) fn backlog(&self) -> usize {
+        match self {
+            EventReceiver::Bounded(rx) => rx.len(),
+            EventReceiver::Broadcast(rx) => rx.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_block_is_lossless() {
+        let channel = EventChannel {
+            capacity: 2,
+            overflow: OverflowPolicy::Block,
+        };
+        let (tx, mut rx) = channel.open::<u64>();
+
+        let sender = tokio::spawn(async move {
+            for event in 0..10 {
+                assert!(tx.send(event).await);
+            }
+        });
+
+        for expected in 0..10 {
+            assert!(matches!(rx.recv().await, Received::Event(event) if event == expected));
+            assert!(rx.backlog() <= 2);
+        }
+        sender.await.unwrap();
+        assert!(matches!(rx.recv().await, Received::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_reports_lag() {
+        let channel = EventChannel {
+            capacity: 2,
+            overflow: OverflowPolicy::DropOldest,
+        };
+        let (tx, mut rx) = channel.open::<u64>();
+
+        for event in 0..5 {
+            assert!(tx.send(event).await);
+        }
+        assert_eq!(rx.backlog(), 5);
+        assert!(matches!(rx.recv().await, Received::Lagged(3)));
+        assert!(matches!(rx.recv().await, Received::Event(3)));
+        assert!(matches!(rx.recv().await, Received::Event(4)));
+
+        drop(tx);
+        assert!(matches!(rx.recv().await, Received::Closed));
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio_util::sync::CancellationToken;
+
+/// Records why and when the kill switch was triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillTrigger {
+    pub reason: String,
+    /// Unix timestamp in milliseconds
+    pub triggered_at: u64,
+}
+
+/// A global kill switch shared between the bot loop and anything that may need to
+/// stop trading, e.g. a signal handler or a drawdown monitor. Triggering it stops the
+/// bot loop after giving the bot a chance to clean up, while collectors, states and
+/// executors keep running.
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch {
+    token: CancellationToken,
+    trigger: Arc<Mutex<Option<KillTrigger>>>,
+}
+
+impl KillSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Triggers the kill switch. Returns `false` if it was already triggered, in which
+    /// case the original reason is kept.
+    pub fn trigger(&self, reason: impl Into<String>) -> bool {
+        let mut trigger = self.trigger.lock().unwrap();
+        if trigger.is_some() {
+            return false;
+        }
+
+        let triggered_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let reason = reason.into();
+        tracing::warn!("Kill switch triggered: {}", reason);
+
+        *trigger = Some(KillTrigger {
+            reason,
+            triggered_at,
+        });
+        self.token.cancel();
+        true
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn trigger_info(&self) -> Option<KillTrigger> {
+        self.trigger.lock().unwrap().clone()
+    }
+
+    /// Completes once the kill switch has been triggered.
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kill_switch_keeps_first_trigger() {
+        let kill_switch = KillSwitch::new();
+        assert!(!kill_switch.is_triggered());
+        assert!(kill_switch.trigger_info().is_none());
+
+        let clone = kill_switch.clone();
+        assert!(clone.trigger("drawdown"));
+        assert!(!kill_switch.trigger("manual"));
+
+        kill_switch.triggered().await;
+        assert!(kill_switch.is_triggered());
+        assert_eq!(kill_switch.trigger_info().unwrap().reason, "drawdown");
+    }
+}
@@ -1,3 +1,7 @@
+pub mod events;
+pub mod kill_switch;
 pub mod mappers;
+pub mod registry;
 pub mod run;
+pub mod supervisor;
 pub mod traits;
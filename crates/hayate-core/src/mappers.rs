@@ -1,27 +1,28 @@
-use crate::traits::Executor;
+use crate::{events::EventSink, traits::Executor};
 
-pub struct ExecutorMap<A, F> {
-    executor: Box<dyn Executor<A>>,
+pub struct ExecutorMap<A, E, F> {
+    executor: Box<dyn Executor<A, E>>,
     f: F,
 }
 
-impl<A, F> ExecutorMap<A, F> {
-    pub fn new(executor: Box<dyn Executor<A>>, f: F) -> Self {
+impl<A, E, F> ExecutorMap<A, E, F> {
+    pub fn new(executor: Box<dyn Executor<A, E>>, f: F) -> Self {
         Self { executor, f }
     }
 }
 
 #[async_trait::async_trait]
-impl<A1, A2, F> Executor<A1> for ExecutorMap<A2, F>
+impl<A1, A2, E, F> Executor<A1, E> for ExecutorMap<A2, E, F>
 where
     A1: Send + Sync + 'static,
     A2: Send + Sync + 'static,
+    E: Send + Sync + 'static,
     F: Fn(A1) -> Option<A2> + Send + Sync + Clone + 'static,
 {
-    async fn execute(&self, action: A1) -> anyhow::Result<()> {
+    async fn execute(&self, action: A1, events: &EventSink<E>) -> anyhow::Result<()> {
         let action = (self.f)(action);
         match action {
-            Some(action) => self.executor.execute(action).await,
+            Some(action) => self.executor.execute(action, events).await,
             None => Ok(()),
         }
     }
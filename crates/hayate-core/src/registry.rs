@@ -0,0 +1,347 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::traits::State;
+
+/// A state shared between its task, the bot and executors, with its type erased.
+pub type SharedState<E> = Arc<RwLock<dyn State<E>>>;
+
+/// Event processing counters of a state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateStats {
+    pub name: String,
+    /// Events applied to the state, including those it failed to process
+    pub applied: u64,
+    pub errors: u64,
+    /// Events dropped before reaching the state
+    pub dropped: u64,
+    /// Events waiting to be applied after the last one
+    pub backlog: usize,
+    pub max_backlog: usize,
+}
+
+struct Entry<E> {
+    type_id: TypeId,
+    state: SharedState<E>,
+    stats: Arc<Mutex<StateStats>>,
+}
+
+impl<E> Clone for Entry<E> {
+    fn clone(&self) -> Self {
+        Self {
+            type_id: self.type_id,
+            state: self.state.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+/// The states of a bot, registered by type with at most one state of each type.
+///
+/// States of any crate can be registered, inputs and executors then look them up by
+/// type through a [`StateContext`]. Clones share the states registered so far.
+///
+/// Events are applied to all states at once by [`StateRegistry::apply`], which holds the
+/// registry epoch for writing, while [`StateRegistry::read`] holds it for reading: a
+/// context never sees a state updated by an event that the others have not seen yet.
+pub struct StateRegistry<E> {
+    /// States in registration order
+    states: Vec<Entry<E>>,
+    /// Number of events applied to the states
+    sequence: Arc<RwLock<u64>>,
+}
+
+impl<E: 'static> StateRegistry<E> {
+    pub fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            sequence: Arc::new(RwLock::new(0)),
+        }
+    }
+
+    /// Registers a state, replacing any state of the same type, and returns a handle to
+    /// it for components that need the concrete type.
+    ///
+    /// Reads through the handle bypass the registry epoch: they may see the state
+    /// updated by an event that the other states have not seen yet. Components reading
+    /// several states, or the sequence, should go through [`StateRegistry::read`].
+    pub fn register<S: State<E>>(&mut self, state: S) -> Arc<RwLock<S>> {
+        let stats = StateStats {
+            name: state.name().to_string(),
+            ..Default::default()
+        };
+        let state = Arc::new(RwLock::new(state));
+        let entry = Entry {
+            type_id: TypeId::of::<S>(),
+            state: state.clone(),
+            stats: Arc::new(Mutex::new(stats)),
+        };
+
+        match self.states.iter_mut().find(|e| e.type_id == entry.type_id) {
+            Some(existing) => {
+                tracing::warn!("Replaced state {} in registry", type_name::<S>());
+                *existing = entry;
+            }
+            None => self.states.push(entry),
+        }
+        state
+    }
+
+    /// States in registration order.
+    pub fn states(&self) -> impl Iterator<Item = &SharedState<E>> {
+        self.states.iter().map(|entry| &entry.state)
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Syncs every state concurrently. Readers wait until all states are synced.
+    pub async fn sync(&self) -> anyhow::Result<()> {
+        let _sequence = self.sequence.write().await;
+        let results = futures::future::join_all(self.states().map(|state| async move {
+            let mut state = state.write().await;
+            state
+                .sync()
+                .await
+                .map_err(|e| anyhow::anyhow!("Error syncing state {}: {}", state.name(), e))?;
+            tracing::info!("State {} synced.", state.name());
+            Ok::<_, anyhow::Error>(())
+        }))
+        .await;
+        results.into_iter().collect()
+    }
+
+    /// Applies an event to every state, in registration order, and returns the sequence
+    /// number of the event. Errors of a state are logged and do not stop the others.
+    pub async fn apply(&self, event: E) -> u64
+    where
+        E: Clone,
+    {
+        let mut sequence = self.sequence.write().await;
+        for entry in &self.states {
+            let mut state = entry.state.write().await;
+            let result = state.process_event(event.clone());
+            let mut stats = entry.stats.lock().unwrap();
+            stats.applied += 1;
+            match result {
+                Ok(_) => tracing::debug!("Event processed successfully in state {}", state.name()),
+                Err(e) => {
+                    stats.errors += 1;
+                    tracing::error!("Error processing event in state {}: {}", state.name(), e)
+                }
+            }
+        }
+        *sequence += 1;
+        *sequence
+    }
+
+    /// Records the number of events waiting to be applied.
+    pub fn record_backlog(&self, backlog: usize) {
+        for entry in &self.states {
+            let mut stats = entry.stats.lock().unwrap();
+            stats.backlog = backlog;
+            stats.max_backlog = stats.max_backlog.max(backlog);
+        }
+    }
+
+    /// Recovers from `dropped` events lost before reaching the states with
+    /// [`State::resync`], e.g. to wait for a fresh order book instead of applying deltas
+    /// to a stale one. Readers wait until all states are resynced.
+    pub async fn resync(&self, dropped: u64) -> anyhow::Result<()> {
+        let _sequence = self.sequence.write().await;
+        let results = futures::future::join_all(self.states.iter().map(|entry| async move {
+            entry.stats.lock().unwrap().dropped += dropped;
+            let mut state = entry.state.write().await;
+            state
+                .resync()
+                .await
+                .map_err(|e| anyhow::anyhow!("Error resyncing state {}: {}", state.name(), e))?;
+            tracing::info!("State {} resynced.", state.name());
+            Ok::<_, anyhow::Error>(())
+        }))
+        .await;
+        results.into_iter().collect()
+    }
+
+    /// Event processing counters of every state, in registration order.
+    pub fn stats(&self) -> Vec<StateStats> {
+        self.states
+            .iter()
+            .map(|entry| entry.stats.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Read-locks every state, in registration order, until the context is dropped. No
+    /// event is applied in the meantime.
+    pub async fn read(&self) -> StateContext<'_, E> {
+        let sequence = self.sequence.read().await;
+        let mut states = HashMap::with_capacity(self.states.len());
+        for entry in &self.states {
+            states.insert(entry.type_id, entry.state.read().await);
+        }
+        StateContext { sequence, states }
+    }
+}
+
+impl<E> Clone for StateRegistry<E> {
+    fn clone(&self) -> Self {
+        Self {
+            states: self.states.clone(),
+            sequence: self.sequence.clone(),
+        }
+    }
+}
+
+impl<E: 'static> Default for StateRegistry<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read access to the registered states, looked up by type, all as of the same event.
+pub struct StateContext<'a, E> {
+    sequence: RwLockReadGuard<'a, u64>,
+    states: HashMap<TypeId, RwLockReadGuard<'a, dyn State<E>>>,
+}
+
+impl<E: 'static> StateContext<'_, E> {
+    /// Sequence number of the last event applied to the states, 0 before the first one.
+    pub fn sequence(&self) -> u64 {
+        *self.sequence
+    }
+
+    /// The state of type `S`, if registered.
+    pub fn get<S: State<E>>(&self) -> Option<&S> {
+        let state: &dyn State<E> = &**self.states.get(&TypeId::of::<S>())?;
+        (state as &dyn Any).downcast_ref()
+    }
+
+    /// The state of type `S`, failing if it is not registered.
+    pub fn require<S: State<E>>(&self) -> anyhow::Result<&S> {
+        self.get()
+            .ok_or_else(|| anyhow::anyhow!("State {} is not registered", type_name::<S>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter(usize);
+
+    #[async_trait::async_trait]
+    impl State<usize> for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        async fn sync(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn process_event(&mut self, event: usize) -> anyhow::Result<()> {
+            self.0 += event;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Last(Option<usize>);
+
+    #[async_trait::async_trait]
+    impl State<usize> for Last {
+        fn name(&self) -> &str {
+            "last"
+        }
+
+        async fn sync(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn process_event(&mut self, event: usize) -> anyhow::Result<()> {
+            self.0 = Some(event);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_lookup_by_type() {
+        let mut registry = StateRegistry::new();
+        let counter = registry.register(Counter::default());
+        assert_eq!(registry.len(), 1);
+
+        for state in registry.states() {
+            state.write().await.process_event(2).unwrap();
+        }
+        assert_eq!(counter.read().await.0, 2);
+
+        {
+            let ctx = registry.read().await;
+            assert_eq!(ctx.require::<Counter>().unwrap().0, 2);
+            assert!(ctx.get::<Last>().is_none());
+            assert!(ctx.require::<Last>().is_err());
+        }
+
+        assert_eq!(registry.apply(3).await, 1);
+        assert_eq!(registry.read().await.sequence(), 1);
+        assert_eq!(counter.read().await.0, 5);
+
+        registry.record_backlog(4);
+        registry.record_backlog(1);
+        registry.resync(2).await.unwrap();
+        assert_eq!(
+            registry.stats(),
+            vec![StateStats {
+                name: "counter".to_string(),
+                applied: 1,
+                errors: 0,
+                dropped: 2,
+                backlog: 1,
+                max_backlog: 4,
+            }]
+        );
+
+        // Registering a type again replaces the state
+        registry.register(Last::default());
+        registry.register(Counter(5));
+        assert_eq!(registry.len(), 2);
+        let ctx = registry.read().await;
+        assert_eq!(ctx.get::<Counter>().unwrap().0, 5);
+        assert_eq!(ctx.get::<Last>().unwrap().0, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_reads_are_consistent() {
+        let mut registry = StateRegistry::new();
+        registry.register(Counter::default());
+        registry.register(Last::default());
+
+        let writer = registry.clone();
+        let handle = tokio::spawn(async move {
+            for event in 1..=1000 {
+                writer.apply(event).await;
+                tokio::task::yield_now().await;
+            }
+        });
+
+        // Both states always reflect the same events
+        while !handle.is_finished() {
+            let ctx = registry.read().await;
+            let last = ctx.require::<Last>().unwrap().0.unwrap_or(0);
+            assert_eq!(last as u64, ctx.sequence());
+            assert_eq!(ctx.require::<Counter>().unwrap().0, last * (last + 1) / 2);
+        }
+        handle.await.unwrap();
+    }
+}
@@ -1,175 +1,596 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use tokio::{
-    sync::{broadcast, RwLock},
+    sync::{broadcast, Mutex},
     task::JoinSet,
+    time::Instant,
 };
 use tokio_stream::StreamExt;
-use tokio_util::sync::CancellationToken;
 
-use crate::traits::{Bot, Collector, Executor, Input, State};
+use crate::{
+    events::{EventChannel, EventSink, OverflowPolicy, Received},
+    kill_switch::KillSwitch,
+    registry::StateRegistry,
+    supervisor::{
+        Component, ComponentKind, RestartPolicy, ShutdownReason, ShutdownReport, Supervisor,
+    },
+    traits::{Bot, Collected, Collector, Executor, Input},
+};
+
+/// Options of [`run_bot`].
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub events: EventChannel,
+    /// Restart policies overriding [`ComponentKind::default_policy`]
+    pub restart_policies: HashMap<ComponentKind, RestartPolicy>,
+    /// How long shutdown waits for the orders of the bot to be closed
+    pub shutdown_timeout: Duration,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            events: EventChannel::default(),
+            restart_policies: HashMap::new(),
+            shutdown_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the channel carrying events from the collectors to the states.
+    pub fn with_event_channel(mut self, events: EventChannel) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Sets what happens when a component of the given kind fails.
+    pub fn with_restart_policy(mut self, kind: ComponentKind, policy: RestartPolicy) -> Self {
+        self.restart_policies.insert(kind, policy);
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    pub fn restart_policy(&self, kind: ComponentKind) -> RestartPolicy {
+        self.restart_policies
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_policy())
+    }
+}
 
-pub fn run_bot<B, S, E, A, I>(
+/// Starts the collectors, states, bot and executors under `supervisor`, which stops them
+/// all when one fails beyond its restart policy. The bot does not evaluate while a
+/// component is restarting, and states are synced again when a collector restarts.
+///
+/// Shutdown is ordered: the bot loop stops first, the actions of [`Bot::on_shutdown`] are
+/// executed and the orders of the bot are awaited until closed or until the shutdown
+/// timeout, then the states, collectors and executors stop. The outcome is reported by
+/// [`Supervisor::shutdown_report`].
+pub fn run_bot<B, E, A, I>(
     bot: B,
-    states: Vec<Arc<RwLock<S>>>,
+    states: StateRegistry<E>,
     collectors: Vec<Box<dyn Collector<E>>>,
-    executor: Vec<Box<dyn Executor<A>>>,
-    shutdown: CancellationToken,
+    executor: Vec<Box<dyn Executor<A, E>>>,
+    kill_switch: KillSwitch,
+    supervisor: Supervisor,
+    options: RunOptions,
 ) -> JoinSet<()>
 where
     B: Bot<I, A> + Send + Sync + 'static,
-    S: State<E> + Send + Sync + 'static,
     E: Clone + Send + Sync + 'static,
     A: Clone + Send + Sync + 'static,
-    I: Input<S> + Send + Sync + 'static,
+    I: Input<E> + Send + Sync + 'static,
 {
     let mut set = JoinSet::new();
-    let states_clone = states.clone();
 
     // Set up bot internal channels
-    let (event_tx, _) = broadcast::channel::<E>(1024);
-    let (action_tx, _) = broadcast::channel::<A>(1024);
+    let (event_tx, event_rx) = options.events.open::<E>();
+    let (action_tx, action_rx) = broadcast::channel::<A>(1024);
+    let bot = Arc::new(bot);
+    // Held by the bot while it evaluates, so that no action follows the shutdown ones
+    let evaluating = Arc::new(Mutex::new(()));
 
-    // Start the executor
-    for exec in executor {
-        let mut action_rx = action_tx.subscribe();
-        let shutdown_signal = shutdown.clone();
+    // Start the executors, a restarted executor only receives the actions sent after it.
+    // Events they report join those of the collectors
+    for (index, exec) in executor.into_iter().enumerate() {
+        let exec: Arc<dyn Executor<A, E>> = Arc::from(exec);
+        let action_rx = action_rx.resubscribe();
+        let events = EventSink::new(event_tx.clone());
+        let shutdown_signal = supervisor.clone();
 
-        set.spawn(async move {
-            tracing::info!("Starting Executor...");
-            loop {
-                tokio::select! {
-                    action = action_rx.recv() => match action {
-                        Ok(action) => {
-                            match exec.execute(action).await {
-                                Ok(_) => tracing::debug!("Action executed successfully."),
-                                Err(e) => tracing::error!("Error executing action: {}", e),
+        let policy = options.restart_policy(ComponentKind::Executor);
+        supervisor.spawn(&mut set, Component::Executor(index), policy, move |_| {
+            let exec = exec.clone();
+            let mut action_rx = action_rx.resubscribe();
+            let events = events.clone();
+            let shutdown_signal = shutdown_signal.clone();
+
+            async move {
+                tracing::info!("Starting Executor...");
+                loop {
+                    tokio::select! {
+                        action = action_rx.recv() => match action {
+                            Ok(action) => {
+                                match exec.execute(action, &events).await {
+                                    Ok(_) => tracing::debug!("Action executed successfully."),
+                                    Err(e) => tracing::error!("Error executing action: {}", e),
+                                }
                             }
-                        }
-                        Err(_) => {
-                            tracing::info!("Action channel closed, stopping executor.");
-                            break;
+                            Err(broadcast::error::RecvError::Lagged(dropped)) => {
+                                tracing::warn!("Executor lagged behind, {} actions dropped.", dropped);
+                            }
+                            Err(broadcast::error::RecvError::Closed) => {
+                                tracing::info!("Action channel closed, stopping executor.");
+                                break;
+                            },
                         },
-                    },
-                    _ = shutdown_signal.cancelled() => {
-                        tracing::info!("Shutdown signal received, stopping Executor.");
-                        break;
+                        _ = shutdown_signal.stopped() => {
+                            tracing::info!("Shutdown complete, stopping Executor.");
+                            break;
+                        }
                     }
                 }
+                tracing::info!("Executor finished.");
+                Ok(())
             }
-            tracing::info!("Executor finished.");
         });
     }
+    drop(action_rx);
 
-    // Start the states
-    for state in states {
-        let mut event_rx = event_tx.subscribe();
-        let shutdown_signal = shutdown.clone();
+    // Start the states, applying every event to all of them at once
+    {
+        let states = states.clone();
+        let overflow = options.events.overflow;
+        // Kept across restarts so that no event is lost in between
+        let event_rx = Arc::new(Mutex::new(event_rx));
+        let shutdown_signal = supervisor.clone();
 
-        set.spawn(async move {
-            tracing::info!("Starting State");
-            let mut state_lock = state.write().await;
-            state_lock.sync().await.unwrap();
-            tracing::info!("State {} synced.", state_lock.name());
-            drop(state_lock);
-
-            loop {
-                tokio::select! {
-                    event = event_rx.recv() => match event {
-                        Ok(event) => {
-                            let mut state_lock = state.write().await;
-                            match state_lock.process_event(event.clone()) {
-                                Ok(_) => tracing::debug!("Event processed successfully in state {}:", state_lock.name()),
-                                Err(e) => tracing::error!("Error processing event in state {}: {}", state_lock.name(), e),
+        let policy = options.restart_policy(ComponentKind::States);
+        supervisor.spawn(&mut set, Component::States, policy, move |_| {
+            let states = states.clone();
+            let event_rx = event_rx.clone();
+            let shutdown_signal = shutdown_signal.clone();
+
+            async move {
+                tracing::info!("Starting States...");
+                let mut event_rx = event_rx.lock().await;
+                states.sync().await?;
+
+                loop {
+                    tokio::select! {
+                        event = event_rx.recv() => match event {
+                            Received::Event(event) => {
+                                states.record_backlog(event_rx.backlog());
+                                let sequence = states.apply(event).await;
+                                tracing::debug!("Event {} applied to states.", sequence);
+                            }
+                            Received::Lagged(dropped) => {
+                                if overflow == OverflowPolicy::Fail {
+                                    // Still synced again to see the orders close on shutdown
+                                    shutdown_signal.shutdown(ShutdownReason::EventsDropped(dropped));
+                                }
+                                tracing::warn!("States lagged behind, {} events dropped, syncing states again.", dropped);
+                                states.resync(dropped).await?;
+                            }
+                            Received::Closed => {
+                                tracing::info!("Event channel closed, stopping states.");
+                                break;
                             }
-                            drop(state_lock);
-                        }
-                        Err(_) => {
-                            tracing::info!("Event channel closed, stopping state.");
-                            break;
                         },
-                    },
-                    _ = shutdown_signal.cancelled() => {
-                        tracing::info!("Shutdown signal received, stopping State.");
-                        break;
+                        _ = shutdown_signal.stopped() => {
+                            tracing::info!("Shutdown complete, stopping States.");
+                            break;
+                        }
                     }
                 }
+                tracing::info!("States finished.");
+                Ok(())
             }
-            tracing::info!("State finished.");
         });
     }
 
     // Start bot
-    let shutdown_signal = shutdown.clone();
-    set.spawn(async move {
-        tracing::info!("Starting Bot...");
-        let mut interval = tokio::time::interval(Duration::from_millis(bot.interval_ms()));
-
-        'bot: loop {
-            tokio::select! {
-                _ = interval.tick() => {
-                    let mut input = I::empty();
-
-                    // FIXME: distribute the state reading
-                    for state in &states_clone {
-                        let lock = state.read().await;
-                        if let Err(e) = input.read_state(&*lock) {
-                            tracing::error!("Error reading state: {}", e);
-                            continue;
+    {
+        let bot = bot.clone();
+        let states = states.clone();
+        let action_tx = action_tx.clone();
+        let kill_switch = kill_switch.clone();
+        let evaluating = evaluating.clone();
+        let shutdown_signal = supervisor.clone();
+
+        let policy = options.restart_policy(ComponentKind::Bot);
+        supervisor.spawn(&mut set, Component::Bot, policy, move |_| {
+            let bot = bot.clone();
+            let states = states.clone();
+            let action_tx = action_tx.clone();
+            let kill_switch = kill_switch.clone();
+            let evaluating = evaluating.clone();
+            let shutdown_signal = shutdown_signal.clone();
+
+            async move {
+                tracing::info!("Starting Bot...");
+                let mut interval = tokio::time::interval(Duration::from_millis(bot.interval_ms()));
+
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let _evaluating = evaluating.lock().await;
+                            if shutdown_signal.is_shutting_down() {
+                                break;
+                            }
+                            if !shutdown_signal.is_healthy() {
+                                tracing::warn!("Components are restarting, skipping bot evaluation.");
+                                continue;
+                            }
+                            let Some(input) = read_input::<E, I>(&states).await else {
+                                continue;
+                            };
+
+                            match bot.evaluate(input) {
+                                Ok(actions) => {
+                                    for action in actions {
+                                        match action_tx.send(action) {
+                                            Ok(_) => tracing::debug!("Action sent successfully."),
+                                            Err(_) => anyhow::bail!("Action channel closed"),
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    tracing::error!("Error evaluating bot: {}", e);
+                                    continue;
+                                }
+                            }
                         }
-                        drop(lock);
-                    }
+                        _ = kill_switch.triggered() => {
+                            tracing::warn!("Kill switch triggered, winding down Bot.");
+                            let input = read_input::<E, I>(&states).await;
 
-                    match bot.evaluate(input) {
-                        Ok(actions) => {
-                            for action in actions {
-                                match action_tx.send(action) {
-                                    Ok(_) => tracing::debug!("Action sent successfully."),
-                                    Err(_) => break 'bot,
+                            match input.map(|input| bot.on_kill(input)) {
+                                Some(Ok(actions)) => {
+                                    for action in actions {
+                                        if action_tx.send(action).is_err() {
+                                            tracing::error!("Action channel closed, kill actions not sent.");
+                                            break;
+                                        }
+                                    }
                                 }
+                                Some(Err(e)) => tracing::error!("Error winding down bot: {}", e),
+                                None => tracing::error!("Cannot wind down bot without input."),
                             }
+                            break;
                         }
-                        Err(e) => {
-                            tracing::error!("Error evaluating bot: {}", e);
-                            continue;
+                        _ = shutdown_signal.cancelled() => {
+                            tracing::info!("Shutdown signal received, stopping Bot.");
+                            break;
                         }
                     }
                 }
-                _ = shutdown_signal.cancelled() => {
-                    tracing::info!("Shutdown signal received, stopping Bot.");
-                    break;
-                }
+
+                tracing::info!("Bot finished.");
+                Ok(())
             }
-        }
+        });
+    }
 
-        tracing::info!("Bot finished.");
-    });
+    // Shut down in order once requested
+    {
+        let states = states.clone();
+        let shutdown_timeout = options.shutdown_timeout;
+        let shutdown_signal = supervisor.clone();
+
+        set.spawn(async move {
+            shutdown_signal.cancelled().await;
+            // Wait for the bot to stop sending actions
+            let _evaluating = evaluating.lock().await;
+            tracing::info!("Shutting down, closing the orders of the bot...");
+
+            let open_orders =
+                close_orders::<B, E, A, I>(&bot, &states, &action_tx, shutdown_timeout).await;
+            drop(action_tx);
+            shutdown_signal.complete(ShutdownReport {
+                reason: shutdown_signal
+                    .shutdown_reason()
+                    .unwrap_or(ShutdownReason::Requested),
+                open_orders,
+            });
+        });
+    }
 
     // Start the collectors
-    for collector in collectors {
+    for (index, collector) in collectors.into_iter().enumerate() {
+        let collector: Arc<dyn Collector<E>> = Arc::from(collector);
         let sender = event_tx.clone();
-        let shutdown_signal = shutdown.clone();
+        let states = states.clone();
+        let shutdown_signal = supervisor.clone();
 
-        set.spawn(async move {
-            tracing::info!("Starting Collector...");
-            let mut event_stream = collector.get_event_stream().await.unwrap();
-            loop {
-                tokio::select! {
-                    Some(event) = event_stream.next() => {
-                        match sender.send(event) {
-                            Ok(_) => {},
-                            Err(_) => break,
-                        }
+        let policy = options.restart_policy(ComponentKind::Collector);
+        supervisor.spawn(
+            &mut set,
+            Component::Collector(index),
+            policy,
+            move |attempt| {
+                let collector = collector.clone();
+                let sender = sender.clone();
+                let states = states.clone();
+                let shutdown_signal = shutdown_signal.clone();
+
+                async move {
+                    tracing::info!("Starting Collector...");
+                    let mut event_stream = collector.get_collected_stream().await?;
+                    if attempt > 0 {
+                        // Events were missed while the collector was down
+                        states.resync(0).await?;
                     }
-                    _ = shutdown_signal.cancelled() => {
-                        tracing::info!("Shutdown signal received, stopping Collector.");
-                        break;
+
+                    loop {
+                        tokio::select! {
+                            event = event_stream.next() => match event {
+                                Some(Collected::Event(event)) => {
+                                    if !sender.send(event).await {
+                                        break;
+                                    }
+                                }
+                                Some(Collected::Lagged(dropped)) => {
+                                    tracing::warn!("Collector lagged behind, {} events dropped, syncing states again.", dropped);
+                                    states.resync(dropped).await?;
+                                }
+                                None => anyhow::bail!("Event stream ended"),
+                            },
+                            _ = shutdown_signal.stopped() => {
+                                tracing::info!("Shutdown complete, stopping Collector.");
+                                break;
+                            }
+                        }
                     }
+                    tracing::info!("Collector finished.");
+                    Ok(())
                 }
-            }
-            tracing::info!("Collector finished.");
-        });
+            },
+        );
     }
 
     set
 }
+
+/// Sends the shutdown actions of the bot, then waits up to `timeout` for its orders to be
+/// closed. Returns the orders left open, `None` if the states could not be read.
+async fn close_orders<B, E, A, I>(
+    bot: &B,
+    states: &StateRegistry<E>,
+    action_tx: &broadcast::Sender<A>,
+    timeout: Duration,
+) -> Option<Vec<String>>
+where
+    B: Bot<I, A>,
+    E: 'static,
+    I: Input<E>,
+{
+    let input = read_input::<E, I>(states).await?;
+    match bot.on_shutdown(input) {
+        Ok(actions) => {
+            for action in actions {
+                if action_tx.send(action).is_err() {
+                    tracing::error!("Action channel closed, shutdown actions not sent.");
+                    break;
+                }
+            }
+        }
+        Err(e) => tracing::error!("Error shutting down bot: {}", e),
+    }
+
+    let deadline = Instant::now() + timeout;
+    let poll = Duration::from_millis(bot.interval_ms()).min(timeout);
+    loop {
+        let input = read_input::<E, I>(states).await?;
+        let open_orders = bot.open_orders(&input);
+        if open_orders.is_empty() || Instant::now() >= deadline {
+            return Some(open_orders);
+        }
+        tracing::info!("Waiting for {} orders to close...", open_orders.len());
+        tokio::time::sleep(poll).await;
+    }
+}
+
+async fn read_input<E, I>(states: &StateRegistry<E>) -> Option<I>
+where
+    E: 'static,
+    I: Input<E>,
+{
+    let ctx = states.read().await;
+    tracing::debug!("Reading states as of event {}.", ctx.sequence());
+    match I::read(&ctx) {
+        Ok(input) => Some(input),
+        Err(e) => {
+            tracing::error!("Error reading states: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::{registry::StateContext, traits::State};
+
+    #[derive(Debug, Clone, Copy)]
+    enum OrderEvent {
+        Opened(u64),
+        Closed(u64),
+    }
+
+    #[derive(Default)]
+    struct Orders(Vec<u64>);
+
+    #[async_trait::async_trait]
+    impl State<OrderEvent> for Orders {
+        fn name(&self) -> &str {
+            "orders"
+        }
+
+        async fn sync(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn process_event(&mut self, event: OrderEvent) -> anyhow::Result<()> {
+            match event {
+                OrderEvent::Opened(oid) => self.0.push(oid),
+                OrderEvent::Closed(oid) => self.0.retain(|o| *o != oid),
+            }
+            Ok(())
+        }
+    }
+
+    struct OpenOrders(Vec<u64>);
+
+    impl Input<OrderEvent> for OpenOrders {
+        fn read(ctx: &StateContext<'_, OrderEvent>) -> anyhow::Result<Self> {
+            Ok(OpenOrders(ctx.require::<Orders>()?.0.clone()))
+        }
+    }
+
+    struct Quoter;
+
+    impl Bot<OpenOrders, u64> for Quoter {
+        fn interval_ms(&self) -> u64 {
+            1
+        }
+
+        fn evaluate(&self, _input: OpenOrders) -> anyhow::Result<Vec<u64>> {
+            Ok(Vec::new())
+        }
+
+        fn on_shutdown(&self, input: OpenOrders) -> anyhow::Result<Vec<u64>> {
+            Ok(input.0)
+        }
+
+        fn open_orders(&self, input: &OpenOrders) -> Vec<String> {
+            input.0.iter().map(|oid| oid.to_string()).collect()
+        }
+    }
+
+    /// Cancels orders, reporting the cancels in its response if `acks` is set.
+    struct Canceller {
+        acks: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Executor<u64, OrderEvent> for Canceller {
+        async fn execute(&self, oid: u64, events: &EventSink<OrderEvent>) -> anyhow::Result<()> {
+            if self.acks {
+                assert!(events.send(OrderEvent::Closed(oid)).await);
+            }
+            Ok(())
+        }
+    }
+
+    struct Opener;
+
+    #[async_trait::async_trait]
+    impl Collector<OrderEvent> for Opener {
+        async fn get_event_stream(
+            &self,
+        ) -> anyhow::Result<crate::traits::CollectorStream<'_, OrderEvent>> {
+            let events = tokio_stream::iter([1, 2, 3].map(OrderEvent::Opened))
+                .chain(futures::stream::pending());
+            Ok(Box::pin(events))
+        }
+    }
+
+    async fn run_until_shutdown(acks: bool) -> ShutdownReport {
+        let mut states = StateRegistry::new();
+        let orders = states.register(Orders::default());
+        let shutdown = CancellationToken::new();
+        let supervisor = Supervisor::new(shutdown.clone());
+
+        let set = run_bot(
+            Quoter,
+            states,
+            vec![Box::new(Opener)],
+            vec![Box::new(Canceller { acks })],
+            KillSwitch::new(),
+            supervisor.clone(),
+            RunOptions::default().with_shutdown_timeout(Duration::from_millis(50)),
+        );
+
+        while orders.read().await.0.len() < 3 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        shutdown.cancel();
+        set.join_all().await;
+        supervisor.shutdown_report().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_closes_orders() {
+        // Cancels acknowledged through the events of the executor close the orders
+        let report = run_until_shutdown(true).await;
+        assert!(report.is_clean());
+        assert_eq!(report.reason, ShutdownReason::Requested);
+
+        // Orders still open after the timeout are reported
+        let report = run_until_shutdown(false).await;
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.open_orders,
+            Some(vec!["1".to_string(), "2".to_string(), "3".to_string()])
+        );
+    }
+
+    struct Lossy;
+
+    #[async_trait::async_trait]
+    impl Collector<OrderEvent> for Lossy {
+        async fn get_event_stream(
+            &self,
+        ) -> anyhow::Result<crate::traits::CollectorStream<'_, OrderEvent>> {
+            Ok(Box::pin(futures::stream::pending()))
+        }
+
+        async fn get_collected_stream(
+            &self,
+        ) -> anyhow::Result<crate::traits::CollectedStream<'_, OrderEvent>> {
+            let events = tokio_stream::iter([
+                Collected::Event(OrderEvent::Opened(1)),
+                Collected::Lagged(2),
+            ])
+            .chain(futures::stream::pending());
+            Ok(Box::pin(events))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collector_lag_resyncs_states() {
+        let mut states = StateRegistry::new();
+        states.register(Orders::default());
+        let shutdown = CancellationToken::new();
+        let supervisor = Supervisor::new(shutdown.clone());
+
+        let set = run_bot(
+            Quoter,
+            states.clone(),
+            vec![Box::new(Lossy)],
+            vec![Box::new(Canceller { acks: true })],
+            KillSwitch::new(),
+            supervisor,
+            RunOptions::default(),
+        );
+
+        while states.stats()[0].dropped == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(states.stats()[0].dropped, 2);
+        shutdown.cancel();
+        set.join_all().await;
+    }
+}
@@ -0,0 +1,530 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::FutureExt;
+use tokio::{task::JoinSet, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+/// The kinds of components started by [`crate::run::run_bot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ComponentKind {
+    Collector,
+    States,
+    Bot,
+    Executor,
+}
+
+impl ComponentKind {
+    /// Collectors and executors restart with the default backoff, while the bot cannot
+    /// run without its states or its loop and shuts down.
+    pub fn default_policy(&self) -> RestartPolicy {
+        match self {
+            ComponentKind::Collector | ComponentKind::Executor => {
+                RestartPolicy::Restart(Backoff::default())
+            }
+            ComponentKind::States | ComponentKind::Bot => RestartPolicy::Escalate,
+        }
+    }
+}
+
+/// A component started by [`crate::run::run_bot`], collectors and executors are numbered
+/// in the order they were given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Component {
+    Collector(usize),
+    States,
+    Bot,
+    Executor(usize),
+}
+
+impl Component {
+    pub fn kind(&self) -> ComponentKind {
+        match self {
+            Component::Collector(_) => ComponentKind::Collector,
+            Component::States => ComponentKind::States,
+            Component::Bot => ComponentKind::Bot,
+            Component::Executor(_) => ComponentKind::Executor,
+        }
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Collector(index) => write!(f, "Collector #{}", index),
+            Component::States => write!(f, "States"),
+            Component::Bot => write!(f, "Bot"),
+            Component::Executor(index) => write!(f, "Executor #{}", index),
+        }
+    }
+}
+
+/// Delays between the restarts of a failing component, doubling from `initial` up to
+/// `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// Restarts in a row after which the failure is escalated, a component running for
+    /// at least `max` before failing starts over
+    pub max_restarts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+            max_restarts: Some(5),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the `attempt`-th restart in a row, starting at 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// What happens when a component fails, i.e. returns an error or panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Restarts the component after a backoff, escalating after too many restarts
+    Restart(Backoff),
+    /// Shuts the whole system down
+    Escalate,
+    /// Leaves the component stopped while the others keep running
+    Ignore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    Running,
+    /// Waiting to restart after the `attempt`-th failure in a row
+    Restarting {
+        attempt: u32,
+        error: String,
+    },
+    /// Finished without error
+    Stopped,
+    Failed(String),
+}
+
+/// Why the system stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// The shutdown token was cancelled from outside, e.g. on Ctrl-C
+    Requested,
+    /// A component failed and its policy escalated the failure
+    ComponentFailed { component: Component, error: String },
+    /// Events were dropped with [`crate::events::OverflowPolicy::Fail`]
+    EventsDropped(u64),
+}
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownReason::Requested => write!(f, "Shutdown requested"),
+            ShutdownReason::ComponentFailed { component, error } => {
+                write!(f, "{} failed: {}", component, error)
+            }
+            ShutdownReason::EventsDropped(dropped) => {
+                write!(f, "{} events dropped before reaching the states", dropped)
+            }
+        }
+    }
+}
+
+/// Outcome of the shutdown sequence of [`crate::run::run_bot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub reason: ShutdownReason,
+    /// Orders the bot still saw open once the sequence completed, `None` if the states
+    /// could not be read
+    pub open_orders: Option<Vec<String>>,
+}
+
+impl ShutdownReport {
+    /// Whether every order is known to be closed.
+    pub fn is_clean(&self) -> bool {
+        self.open_orders
+            .as_ref()
+            .is_some_and(|orders| orders.is_empty())
+    }
+}
+
+/// Runs the components of a bot under their restart policies and tracks their health.
+/// Clones share the health of the components and the shutdown reason.
+///
+/// Shutting down is two-staged: once `shutdown` is cancelled the bot stops and cleans up
+/// its orders, then [`Supervisor::complete`] stops the components left running.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    shutdown: CancellationToken,
+    stopped: CancellationToken,
+    health: Arc<Mutex<BTreeMap<Component, Health>>>,
+    reason: Arc<Mutex<Option<ShutdownReason>>>,
+    report: Arc<Mutex<Option<ShutdownReport>>>,
+}
+
+impl Supervisor {
+    /// A supervisor stopping the system by cancelling `shutdown`.
+    pub fn new(shutdown: CancellationToken) -> Self {
+        Self {
+            shutdown,
+            stopped: CancellationToken::new(),
+            health: Arc::new(Mutex::new(BTreeMap::new())),
+            reason: Arc::new(Mutex::new(None)),
+            report: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stops the system. Returns `false` if it was already stopping, in which case the
+    /// original reason is kept.
+    pub fn shutdown(&self, reason: ShutdownReason) -> bool {
+        let mut current = self.reason.lock().unwrap();
+        if current.is_some() || self.shutdown.is_cancelled() {
+            return false;
+        }
+
+        tracing::error!("Shutting down: {}", reason);
+        *current = Some(reason);
+        self.shutdown.cancel();
+        true
+    }
+
+    /// Why the system stopped, `None` while it is running.
+    pub fn shutdown_reason(&self) -> Option<ShutdownReason> {
+        let reason = self.reason.lock().unwrap().clone();
+        match reason {
+            Some(reason) => Some(reason),
+            None if self.shutdown.is_cancelled() => Some(ShutdownReason::Requested),
+            None => None,
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Completes once the system is stopping.
+    pub async fn cancelled(&self) {
+        self.shutdown.cancelled().await
+    }
+
+    /// Ends the shutdown sequence, stopping the components left running.
+    pub fn complete(&self, report: ShutdownReport) {
+        if report.is_clean() {
+            tracing::info!("Shutdown complete: {}", report.reason);
+        } else {
+            tracing::error!(
+                "Shutdown complete with orders left open ({}): {:?}",
+                report.reason,
+                report.open_orders
+            );
+        }
+        *self.report.lock().unwrap() = Some(report);
+        self.stopped.cancel();
+    }
+
+    /// Completes once the shutdown sequence is over.
+    pub async fn stopped(&self) {
+        self.stopped.cancelled().await
+    }
+
+    /// A token cancelled once the shutdown sequence is over, for components running
+    /// next to the bot that must outlive its order cleanup, e.g. a venue connection.
+    pub fn stopped_token(&self) -> CancellationToken {
+        self.stopped.clone()
+    }
+
+    /// Outcome of the shutdown sequence, `None` until it is over.
+    pub fn shutdown_report(&self) -> Option<ShutdownReport> {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Health of every supervised component, in [`Component`] order.
+    pub fn health(&self) -> Vec<(Component, Health)> {
+        let health = self.health.lock().unwrap();
+        health.iter().map(|(c, h)| (*c, h.clone())).collect()
+    }
+
+    /// Whether no component is waiting to restart.
+    pub fn is_healthy(&self) -> bool {
+        let health = self.health.lock().unwrap();
+        !health
+            .values()
+            .any(|h| matches!(h, Health::Restarting { .. }))
+    }
+
+    fn set_health(&self, component: Component, health: Health) {
+        self.health.lock().unwrap().insert(component, health);
+    }
+
+    fn fail(&self, component: Component, error: String) {
+        self.set_health(component, Health::Failed(error.clone()));
+        self.shutdown(ShutdownReason::ComponentFailed { component, error });
+    }
+
+    /// Spawns a component on `set`, running `task` again under `policy` whenever it
+    /// fails. `task` is given the number of restarts in a row, 0 on the first run.
+    pub fn spawn<F, Fut>(
+        &self,
+        set: &mut JoinSet<()>,
+        component: Component,
+        policy: RestartPolicy,
+        mut task: F,
+    ) where
+        F: FnMut(u32) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let supervisor = self.clone();
+        supervisor.set_health(component, Health::Running);
+
+        set.spawn(async move {
+            let mut attempt = 0;
+            loop {
+                supervisor.set_health(component, Health::Running);
+                let started = Instant::now();
+                let error = match AssertUnwindSafe(task(attempt)).catch_unwind().await {
+                    Ok(Ok(_)) => {
+                        supervisor.set_health(component, Health::Stopped);
+                        return;
+                    }
+                    Ok(Err(e)) => e.to_string(),
+                    Err(panic) => format!("Panicked: {}", panic_message(&*panic)),
+                };
+
+                if supervisor.is_shutting_down() {
+                    tracing::warn!("{} failed while shutting down: {}", component, error);
+                    supervisor.set_health(component, Health::Failed(error));
+                    return;
+                }
+
+                match policy {
+                    RestartPolicy::Restart(backoff) => {
+                        if started.elapsed() >= backoff.max {
+                            attempt = 0;
+                        }
+                        attempt += 1;
+                        if backoff.max_restarts.is_some_and(|max| attempt > max) {
+                            tracing::error!(
+                                "{} failed after {} restarts: {}",
+                                component,
+                                attempt - 1,
+                                error
+                            );
+                            supervisor.fail(component, error);
+                            return;
+                        }
+
+                        let delay = backoff.delay(attempt);
+                        tracing::warn!(
+                            "{} failed, restarting in {:?} (attempt {}): {}",
+                            component,
+                            delay,
+                            attempt,
+                            error
+                        );
+                        supervisor.set_health(component, Health::Restarting { attempt, error });
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => {}
+                            _ = supervisor.cancelled() => {
+                                supervisor.set_health(component, Health::Stopped);
+                                return;
+                            }
+                        }
+                    }
+                    RestartPolicy::Escalate => {
+                        supervisor.fail(component, error);
+                        return;
+                    }
+                    RestartPolicy::Ignore => {
+                        tracing::warn!("{} failed, leaving it stopped: {}", component, error);
+                        supervisor.set_health(component, Health::Failed(error));
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restart_then_escalate() {
+        let supervisor = Supervisor::new(CancellationToken::new());
+        let mut set = JoinSet::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let backoff = Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(100),
+            max_restarts: Some(2),
+        };
+
+        let counter = runs.clone();
+        supervisor.spawn(
+            &mut set,
+            Component::Collector(0),
+            RestartPolicy::Restart(backoff),
+            move |attempt| {
+                assert_eq!(counter.fetch_add(1, Ordering::SeqCst), attempt);
+                async { Err(anyhow::anyhow!("stream ended")) }
+            },
+        );
+        set.join_all().await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert!(supervisor.is_shutting_down());
+        assert_eq!(
+            supervisor.shutdown_reason(),
+            Some(ShutdownReason::ComponentFailed {
+                component: Component::Collector(0),
+                error: "stream ended".to_string(),
+            })
+        );
+        assert_eq!(
+            supervisor.health(),
+            vec![(
+                Component::Collector(0),
+                Health::Failed("stream ended".to_string())
+            )]
+        );
+        assert_eq!(backoff.delay(1), Duration::from_millis(1));
+        assert_eq!(backoff.delay(3), Duration::from_millis(4));
+        assert_eq!(backoff.delay(10), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_panic_is_a_failure() {
+        let supervisor = Supervisor::new(CancellationToken::new());
+        let mut set = JoinSet::new();
+        let backoff = Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(100),
+            max_restarts: Some(1),
+        };
+
+        supervisor.spawn(
+            &mut set,
+            Component::Collector(0),
+            RestartPolicy::Restart(backoff),
+            |attempt| async move {
+                if attempt == 0 {
+                    panic!("book out of sync");
+                }
+                Err(anyhow::anyhow!("still out of sync"))
+            },
+        );
+        set.join_all().await;
+
+        // Restarted once after the panic, then escalated
+        assert_eq!(
+            supervisor.shutdown_reason(),
+            Some(ShutdownReason::ComponentFailed {
+                component: Component::Collector(0),
+                error: "still out of sync".to_string(),
+            })
+        );
+
+        let supervisor = Supervisor::new(CancellationToken::new());
+        let mut set = JoinSet::new();
+        supervisor.spawn(
+            &mut set,
+            Component::Bot,
+            RestartPolicy::Escalate,
+            |_| async { panic!("{} levels", 0) },
+        );
+        set.join_all().await;
+        assert!(supervisor.is_shutting_down());
+        assert_eq!(
+            supervisor.health(),
+            vec![(
+                Component::Bot,
+                Health::Failed("Panicked: 0 levels".to_string())
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ignore_and_requested_shutdown() {
+        let shutdown = CancellationToken::new();
+        let supervisor = Supervisor::new(shutdown.clone());
+        let mut set = JoinSet::new();
+
+        supervisor.spawn(
+            &mut set,
+            Component::Executor(1),
+            RestartPolicy::Ignore,
+            |_| async { Err(anyhow::anyhow!("rejected")) },
+        );
+        let stopping = supervisor.clone();
+        supervisor.spawn(
+            &mut set,
+            Component::Bot,
+            RestartPolicy::Escalate,
+            move |_| {
+                let stopping = stopping.clone();
+                async move {
+                    stopping.cancelled().await;
+                    Ok(())
+                }
+            },
+        );
+
+        assert!(supervisor.shutdown_reason().is_none());
+        shutdown.cancel();
+        set.join_all().await;
+
+        assert!(supervisor.is_healthy());
+        assert_eq!(
+            supervisor.shutdown_reason(),
+            Some(ShutdownReason::Requested)
+        );
+        assert!(!supervisor.shutdown(ShutdownReason::EventsDropped(1)));
+
+        assert!(supervisor.shutdown_report().is_none());
+        let report = ShutdownReport {
+            reason: ShutdownReason::Requested,
+            open_orders: Some(vec!["1".to_string()]),
+        };
+        assert!(!report.is_clean());
+        supervisor.complete(report.clone());
+        supervisor.stopped().await;
+        assert_eq!(supervisor.shutdown_report(), Some(report));
+        assert_eq!(
+            supervisor.health(),
+            vec![
+                (Component::Bot, Health::Stopped),
+                (
+                    Component::Executor(1),
+                    Health::Failed("rejected".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -1,33 +1,83 @@
-use std::pin::Pin;
+use std::{any::Any, pin::Pin};
 
 use anyhow::Result;
-use tokio_stream::Stream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{events::EventSink, registry::StateContext};
 
 pub type CollectorStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
+pub type CollectedStream<'a, E> = Pin<Box<dyn Stream<Item = Collected<E>> + Send + 'a>>;
+
+/// An item of a collected stream: an event, or the number of events the collector lost
+/// before the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collected<E> {
+    Event(E),
+    Lagged(u64),
+}
 
 #[async_trait::async_trait]
 pub trait Collector<E>: Send + Sync {
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>>;
+
+    /// The events of [`Collector::get_event_stream`] along with the events the collector
+    /// lost, e.g. when it lagged behind a broadcast it reads from. The bot resyncs its
+    /// states on a loss. Collectors that cannot lose events keep the default.
+    async fn get_collected_stream(&self) -> Result<CollectedStream<'_, E>>
+    where
+        E: 'static,
+    {
+        let stream = self.get_event_stream().await?;
+        Ok(Box::pin(stream.map(Collected::Event)))
+    }
 }
 
 #[async_trait::async_trait]
-pub trait State<E>: Send + Sync {
+pub trait State<E>: Any + Send + Sync {
     fn name(&self) -> &str;
     async fn sync(&mut self) -> Result<()>;
+
+    /// Called after events were lost before reaching the state, e.g. when the event
+    /// channel overflowed or a collector restarted. States that cannot simply sync again
+    /// once live events are flowing override it, e.g. to wait for a fresh snapshot.
+    async fn resync(&mut self) -> Result<()> {
+        self.sync().await
+    }
+
     fn process_event(&mut self, event: E) -> Result<()>;
 }
 
 pub trait Bot<I, A>: Send + Sync {
     fn interval_ms(&self) -> u64;
     fn evaluate(&self, input: I) -> Result<Vec<A>>;
+
+    /// Called once when the kill switch is triggered, before the bot loop stops.
+    /// Returns the actions needed to wind down, e.g. cancelling open orders.
+    fn on_kill(&self, _input: I) -> Result<Vec<A>> {
+        Ok(Vec::new())
+    }
+
+    /// Called once on shutdown, after the bot loop stopped. Returns the actions needed
+    /// to leave nothing resting on the venue, e.g. cancelling open orders.
+    fn on_shutdown(&self, _input: I) -> Result<Vec<A>> {
+        Ok(Vec::new())
+    }
+
+    /// Orders still open according to `input`. Shutdown waits for them to be closed
+    /// after [`Bot::on_shutdown`], up to a timeout.
+    fn open_orders(&self, _input: &I) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 #[async_trait::async_trait]
-pub trait Executor<A>: Send + Sync {
-    async fn execute(&self, action: A) -> Result<()>;
+pub trait Executor<A, E>: Send + Sync {
+    /// Executes an action. Outcomes reported through the request rather than a stream
+    /// are sent to `events` so that the states see them.
+    async fn execute(&self, action: A, events: &EventSink<E>) -> Result<()>;
 }
 
-pub trait Input<S> {
-    fn empty() -> Self;
-    fn read_state(&mut self, state: &S) -> Result<()>;
+/// Connects states and bot: reads the states a bot needs, looked up by type.
+pub trait Input<E>: Sized {
+    fn read(ctx: &StateContext<'_, E>) -> Result<Self>;
 }